@@ -1,5 +1,3 @@
-<<<<<<< HEAD
-=======
 (function_definition
   name: (identifier) @function)
 
@@ -50,5 +48,3 @@
   "|"
   "^"
 ] @operator
->>>>>>> Snippet
-
@@ -1,69 +1,9 @@
-<<<<<<< HEAD
-=======
-(function_item
-  name: (identifier) @function)
-
-(type_identifier) @type
-(primitive_type) @type
-
-(string_literal) @string
-(raw_string_literal) @string
-(char_literal) @string
-
-(integer_literal) @number
-(float_literal) @number
-
-(line_comment) @comment
-(block_comment) @comment
-
-[
-  "fn"
-  "let"
-  "const"
-  "struct"
-  "enum"
-  "trait"
-  "impl"
-  "pub"
-  "mut"
-  "return"
-  "match"
-  "if"
-  "else"
-  "for"
-  "while"
-  "loop"
-  "break"
-  "continue"
-] @keyword
-
-(parameter
-  pattern: (identifier) @parameter)
-
-[
-  "+"
-  "-"
-  "*"
-  "/"
-  "="
-  "=="
-  "!="
-  ">"
-  "<"
-  ">="
-  "<="
-  "&"
-  "|"
-  "^"
-] @operator
->>>>>>> Snippet
 ; Keywords
 [
   "as"
   "break"
   "const"
   "continue"
-  "crate"
   "else"
   "enum"
   "extern"
@@ -77,15 +17,11 @@
   "match"
   "mod"
   "move"
-  "mut"
   "pub"
   "ref"
   "return"
-  "self"
-  "Self"
   "static"
   "struct"
-  "super"
   "trait"
   "type"
   "unsafe"
@@ -97,6 +33,11 @@
   "dyn"
 ] @keyword
 
+(crate) @keyword
+(mutable_specifier) @keyword
+(self) @keyword
+(super) @keyword
+
 ; Functions
 (function_item name: (identifier) @function)
 (call_expression function: (identifier) @function)
@@ -155,6 +96,5 @@
   "|="
   "^="
   "!"
-  "~"
 ] @operator
 
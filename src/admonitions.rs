@@ -0,0 +1,149 @@
+use crate::{
+    blockquotes::parse_quote_line,
+    inline::{parse_inline, wrap_spans},
+    width::text_width,
+};
+
+/// The kinds of GitHub's alerts, in the order of their icons in the glyphs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdmonitionKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AdmonitionKind {
+    pub fn from_name(name: &str) -> Option<AdmonitionKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "note" => Some(AdmonitionKind::Note),
+            "tip" => Some(AdmonitionKind::Tip),
+            "important" => Some(AdmonitionKind::Important),
+            "warning" => Some(AdmonitionKind::Warning),
+            "caution" => Some(AdmonitionKind::Caution),
+            _ => None,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Warning => "Warning",
+            AdmonitionKind::Caution => "Caution",
+        }
+    }
+}
+
+/// A blockquote starting with a `> [!NOTE]` line, drawn as a box.
+#[derive(Debug, PartialEq)]
+pub struct Admonition<'a> {
+    pub kind: AdmonitionKind,
+    /// The text after the marker, or the name of the kind
+    pub title: &'a str,
+    /// The lines of the quote below the marker, without the `>`
+    pub lines: Vec<&'a str>,
+}
+
+impl<'a> Admonition<'a> {
+    /// Parses the admonition starting at the first line, returning it and the
+    /// number of lines it spans.
+    pub fn parse(lines: &[&'a str]) -> Option<(Admonition<'a>, usize)> {
+        let first = parse_quote_line(lines.first()?)?;
+        let (name, title) = first.text.strip_prefix("[!")?.split_once(']')?;
+        let kind = AdmonitionKind::from_name(name)?;
+        let body: Vec<&str> = lines[1..]
+            .iter()
+            .map_while(|line| line.strip_prefix('>'))
+            .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+            .collect();
+        let count = 1 + body.len();
+        let title = match title.trim() {
+            "" => kind.title(),
+            title => title,
+        };
+        Some((
+            Admonition {
+                kind,
+                title,
+                lines: body,
+            },
+            count,
+        ))
+    }
+
+    /// The width of the box with nothing wrapped: its lines or its title with
+    /// the icon, and the borders.
+    pub fn width(&self) -> usize {
+        let widest = self
+            .lines
+            .iter()
+            .map(|line| {
+                parse_inline(line)
+                    .iter()
+                    .map(|span| text_width(&span.text))
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or_default();
+        (widest + 4).max(text_width(self.title) + 7)
+    }
+
+    /// The number of rows of the box when it is `width` columns wide.
+    pub fn rows(&self, width: usize) -> usize {
+        let inner = width.saturating_sub(4).max(1);
+        let body: usize = self
+            .lines
+            .iter()
+            .map(|line| wrap_spans(parse_inline(line), inner).len().max(1))
+            .sum();
+        body + 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_admonition() {
+        let lines = [
+            "> [!WARNING]",
+            "> Keep your **hands** inside",
+            ">",
+            "> > nested",
+            "After",
+        ];
+        assert_eq!(
+            Admonition::parse(&lines),
+            Some((
+                Admonition {
+                    kind: AdmonitionKind::Warning,
+                    title: "Warning",
+                    lines: vec!["Keep your **hands** inside", "", "> nested"],
+                },
+                4
+            ))
+        );
+        let (titled, count) = Admonition::parse(&["> [!tip] Try this"]).unwrap();
+        assert_eq!(
+            (titled.kind, titled.title, count),
+            (AdmonitionKind::Tip, "Try this", 1)
+        );
+        assert_eq!(Admonition::parse(&["> [!HINT]", "> Nope"]), None);
+        assert_eq!(Admonition::parse(&["> Just a quote"]), None);
+    }
+
+    #[test]
+    fn test_admonition_size() {
+        let (admonition, _) =
+            Admonition::parse(&["> [!NOTE]", "> Short", ">", "> A longer line"]).unwrap();
+        assert_eq!(admonition.width(), 17);
+        assert_eq!(admonition.rows(17), 5);
+        assert_eq!(admonition.rows(12), 6);
+        let (empty, _) = Admonition::parse(&["> [!IMPORTANT]"]).unwrap();
+        assert_eq!((empty.width(), empty.rows(80)), (16, 2));
+    }
+}
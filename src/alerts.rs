@@ -0,0 +1,136 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+/// A way to get the presenter's attention.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alert {
+    /// The terminal bell
+    Bell,
+    /// A notification on the desktop, through `notify-send` or on macOS
+    /// `osascript`
+    Desktop,
+}
+
+impl Alert {
+    pub fn from_name(name: &str) -> Option<Alert> {
+        match name {
+            "bell" => Some(Alert::Bell),
+            "notify" => Some(Alert::Desktop),
+            _ => None,
+        }
+    }
+}
+
+/// The alerts of an `alert: bell notify "Message"` directive, shown when the
+/// slide comes up. The message is optional.
+#[derive(Debug, PartialEq)]
+pub struct SlideAlert {
+    pub alerts: Vec<Alert>,
+    pub message: Option<String>,
+}
+
+impl SlideAlert {
+    pub fn parse(directive: &str) -> Option<SlideAlert> {
+        let (names, message) = match directive.split_once('"') {
+            Some((names, message)) => (names, Some(message.strip_suffix('"')?.to_string())),
+            None => (directive, None),
+        };
+        let alerts = parse_alerts(names)?;
+        (!alerts.is_empty()).then_some(SlideAlert { alerts, message })
+    }
+}
+
+/// Parses alert names separated by spaces, like `bell notify`.
+pub fn parse_alerts(names: &str) -> Option<Vec<Alert>> {
+    names.split_whitespace().map(Alert::from_name).collect()
+}
+
+/// Rings the bell or sends the message to the desktop.
+pub fn trigger(alert: Alert, message: &str, stdout: &mut impl Write) -> Result<(), String> {
+    match alert {
+        Alert::Bell => write!(stdout, "\x07")
+            .and_then(|_| stdout.flush())
+            .map_err(|err| err.to_string()),
+        Alert::Desktop => desktop_notification(message)
+            .map_err(|err| format!("Could not show a desktop notification: {}", err)),
+    }
+}
+
+/// Extensions of the audio files played with the system's player rather than
+/// run as a command.
+const AUDIO_EXTENSIONS: [&str; 7] = ["wav", "mp3", "ogg", "oga", "flac", "aiff", "m4a"];
+
+/// Plays an audio file with `afplay` on macOS or `paplay` elsewhere, or runs
+/// anything else as a shell command, in the background.
+pub fn play_sound(sound: &str) -> Result<(), String> {
+    let is_audio_file = Path::new(sound)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    let command = if is_audio_file {
+        let mut command = Command::new(if cfg!(target_os = "macos") {
+            "afplay"
+        } else {
+            "paplay"
+        });
+        command.arg(sound);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(sound);
+        command
+    };
+    spawn_quietly(command).map_err(|err| format!("Could not play '{}': {}", sound, err))
+}
+
+fn desktop_notification(message: &str) -> std::io::Result<()> {
+    let command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title \"term_deck\"",
+            message
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("term_deck").arg(message);
+        command
+    };
+    spawn_quietly(command)
+}
+
+/// Starts the command without waiting for it to finish.
+fn spawn_quietly(mut command: Command) -> std::io::Result<()> {
+    // Output of the command would end up on the slide
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slide_alert() {
+        assert_eq!(
+            SlideAlert::parse("bell notify \"Switch to the demo laptop\""),
+            Some(SlideAlert {
+                alerts: vec![Alert::Bell, Alert::Desktop],
+                message: Some("Switch to the demo laptop".to_string()),
+            })
+        );
+        assert_eq!(SlideAlert::parse("bell").unwrap().message, None);
+        assert_eq!(SlideAlert::parse("siren"), None);
+        assert_eq!(SlideAlert::parse("\"Only a message\""), None);
+        assert_eq!(SlideAlert::parse("notify \"Unterminated"), None);
+    }
+}
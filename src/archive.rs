@@ -0,0 +1,178 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::{
+    bundle,
+    colors::{self, Theme},
+};
+
+/// The extension of presentation archives, which are gzipped tar files of a
+/// bundled presentation.
+pub const ARCHIVE_EXTENSION: &str = "termdeck";
+
+/// The theme stored in an archive next to the presentation.
+const THEME_FILE: &str = "theme.toml";
+
+pub fn is_archive(path: &str) -> bool {
+    Path::new(path).extension() == Some(OsStr::new(ARCHIVE_EXTENSION))
+}
+
+/// Bundles the presentation and writes it into an archive, together with the
+/// theme file if it uses one of the user's themes. Prints what went wrong and
+/// returns false if the archive could not be written.
+pub fn pack(
+    presentation_file: &str,
+    content: &str,
+    archive_path: &Path,
+    theme_file: Option<&Path>,
+) -> bool {
+    let staging_dir = env::temp_dir().join(format!("term_deck-pack-{}", process::id()));
+    let result = write_archive(
+        presentation_file,
+        content,
+        archive_path,
+        theme_file,
+        &staging_dir,
+    );
+    let _ = fs::remove_dir_all(&staging_dir);
+    match result {
+        Ok(image_count) => {
+            println!(
+                "Packed {} with {} images into {}",
+                presentation_file,
+                image_count,
+                archive_path.display()
+            );
+            true
+        }
+        Err(errors) => {
+            for err in errors {
+                eprintln!("{}", err);
+            }
+            false
+        }
+    }
+}
+
+fn write_archive(
+    presentation_file: &str,
+    content: &str,
+    archive_path: &Path,
+    theme_file: Option<&Path>,
+    staging_dir: &Path,
+) -> Result<usize, Vec<String>> {
+    let image_count = bundle::write(presentation_file, content, staging_dir)?;
+    if let Some(theme_file) = theme_file {
+        fs::copy(theme_file, staging_dir.join(THEME_FILE))
+            .map_err(|err| vec![format!("Could not copy {}: {}", theme_file.display(), err)])?;
+    }
+    let file = File::create(archive_path).map_err(|err| {
+        vec![format!(
+            "Could not create {}: {}",
+            archive_path.display(),
+            err
+        )]
+    })?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder
+        .append_dir_all(".", staging_dir)
+        .and_then(|_| builder.into_inner())
+        .and_then(|encoder| encoder.finish())
+        .map_err(|err| {
+            vec![format!(
+                "Could not write {}: {}",
+                archive_path.display(),
+                err
+            )]
+        })?;
+    Ok(image_count)
+}
+
+/// An archive extracted into a temporary directory, which is removed again
+/// when this is dropped.
+pub struct Unpacked {
+    pub directory: PathBuf,
+    pub presentation_file: String,
+    pub theme: Option<Theme>,
+}
+
+impl Drop for Unpacked {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+pub fn unpack(archive_path: &str) -> Result<Unpacked, String> {
+    let directory = env::temp_dir().join(format!("term_deck-{}", process::id()));
+    let file = File::open(archive_path)
+        .map_err(|err| format!("Could not open {}: {}", archive_path, err))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(&directory)
+        .map_err(|err| format!("Could not unpack {}: {}", archive_path, err))?;
+
+    let presentation_file =
+        presentation_in(&directory).ok_or(format!("{} contains no presentation", archive_path))?;
+    let theme_path = directory.join(THEME_FILE);
+    let theme = theme_path
+        .exists()
+        .then(|| colors::load_theme(&theme_path))
+        .transpose()?;
+    Ok(Unpacked {
+        presentation_file: presentation_file.display().to_string(),
+        directory,
+        theme,
+    })
+}
+
+/// The markdown file at the top of the directory.
+fn presentation_in(directory: &Path) -> Option<PathBuf> {
+    fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.is_file() && path.extension() == Some(OsStr::new("md")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_and_unpack() {
+        let source_dir = env::temp_dir().join(format!("term_deck-test-{}", process::id()));
+        fs::create_dir_all(source_dir.join("images")).unwrap();
+        fs::write(source_dir.join("images/logo.png"), "png").unwrap();
+        let presentation_file = source_dir.join("talk.md").display().to_string();
+        let archive_path = source_dir.join("talk.termdeck");
+        let content = "# Talk\n![Logo](images/logo.png)\n";
+
+        let packed = write_archive(
+            &presentation_file,
+            content,
+            &archive_path,
+            None,
+            &source_dir.join("staging"),
+        );
+        assert_eq!(packed, Ok(1));
+        assert!(is_archive(&archive_path.display().to_string()));
+
+        let unpacked = unpack(&archive_path.display().to_string()).unwrap();
+        assert!(unpacked.presentation_file.ends_with("talk.md"));
+        assert_eq!(
+            fs::read_to_string(&unpacked.presentation_file).unwrap(),
+            content
+        );
+        assert!(unpacked.directory.join("images/logo.png").exists());
+        assert!(unpacked.theme.is_none());
+        let directory = unpacked.directory.clone();
+        drop(unpacked);
+        assert!(!directory.exists());
+        fs::remove_dir_all(&source_dir).unwrap();
+    }
+}
@@ -0,0 +1,708 @@
+use crate::width;
+
+/// Banner-size text drawn with block characters, scaled up from a 5x7 pixel
+/// font.
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// Largest useful scale, beyond it the letters become hard to read as
+/// letters.
+pub const MAX_SCALE: usize = 8;
+
+/// Rows of every printable ASCII character, the highest bit being the
+/// leftmost pixel.
+const FONT: [(char, [u8; GLYPH_HEIGHT]); 95] = [
+    (
+        ' ',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '!',
+        [
+            0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+        ],
+    ),
+    (
+        '"',
+        [
+            0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '#',
+        [
+            0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+        ],
+    ),
+    (
+        '$',
+        [
+            0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100,
+        ],
+    ),
+    (
+        '%',
+        [
+            0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+        ],
+    ),
+    (
+        '&',
+        [
+            0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101,
+        ],
+    ),
+    (
+        '\'',
+        [
+            0b00100, 0b00100, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '(',
+        [
+            0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+        ],
+    ),
+    (
+        ')',
+        [
+            0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '*',
+        [
+            0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000,
+        ],
+    ),
+    (
+        '+',
+        [
+            0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+        ],
+    ),
+    (
+        ',',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '-',
+        [
+            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '.',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+        ],
+    ),
+    (
+        '/',
+        [
+            0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+        ],
+    ),
+    (
+        '0',
+        [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '1',
+        [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        '2',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+    ),
+    (
+        '3',
+        [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '4',
+        [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+    ),
+    (
+        '5',
+        [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '6',
+        [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '7',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+    ),
+    (
+        '8',
+        [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '9',
+        [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+    ),
+    (
+        ':',
+        [
+            0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+        ],
+    ),
+    (
+        ';',
+        [
+            0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '<',
+        [
+            0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010,
+        ],
+    ),
+    (
+        '=',
+        [
+            0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '>',
+        [
+            0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '?',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+        ],
+    ),
+    (
+        '@',
+        [
+            0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110,
+        ],
+    ),
+    (
+        'A',
+        [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'B',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'C',
+        [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'D',
+        [
+            0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+        ],
+    ),
+    (
+        'E',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'F',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'G',
+        [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+    ),
+    (
+        'H',
+        [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'I',
+        [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        'J',
+        [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+    ),
+    (
+        'K',
+        [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'L',
+        [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'M',
+        [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'N',
+        [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'O',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'P',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'Q',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+    ),
+    (
+        'R',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'S',
+        [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+    ),
+    (
+        'T',
+        [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'U',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'V',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+    ),
+    (
+        'W',
+        [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+    ),
+    (
+        'X',
+        [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'Y',
+        [
+            0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'Z',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        '[',
+        [
+            0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110,
+        ],
+    ),
+    (
+        '\\',
+        [
+            0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000,
+        ],
+    ),
+    (
+        ']',
+        [
+            0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110,
+        ],
+    ),
+    (
+        '^',
+        [
+            0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        '_',
+        [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111,
+        ],
+    ),
+    (
+        '`',
+        [
+            0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000,
+        ],
+    ),
+    (
+        'a',
+        [
+            0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111,
+        ],
+    ),
+    (
+        'b',
+        [
+            0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'c',
+        [
+            0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'd',
+        [
+            0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111,
+        ],
+    ),
+    (
+        'e',
+        [
+            0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110,
+        ],
+    ),
+    (
+        'f',
+        [
+            0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000,
+        ],
+    ),
+    (
+        'g',
+        [
+            0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+        ],
+    ),
+    (
+        'h',
+        [
+            0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'i',
+        [
+            0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        'j',
+        [
+            0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+    ),
+    (
+        'k',
+        [
+            0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010,
+        ],
+    ),
+    (
+        'l',
+        [
+            0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        'm',
+        [
+            0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'n',
+        [
+            0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'o',
+        [
+            0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'p',
+        [
+            0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'q',
+        [
+            0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001,
+        ],
+    ),
+    (
+        'r',
+        [
+            0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        's',
+        [
+            0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110,
+        ],
+    ),
+    (
+        't',
+        [
+            0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110,
+        ],
+    ),
+    (
+        'u',
+        [
+            0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101,
+        ],
+    ),
+    (
+        'v',
+        [
+            0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+    ),
+    (
+        'w',
+        [
+            0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010,
+        ],
+    ),
+    (
+        'x',
+        [
+            0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001,
+        ],
+    ),
+    (
+        'y',
+        [
+            0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+        ],
+    ),
+    (
+        'z',
+        [
+            0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+    ),
+    (
+        '{',
+        [
+            0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010,
+        ],
+    ),
+    (
+        '|',
+        [
+            0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        '}',
+        [
+            0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000,
+        ],
+    ),
+    (
+        '~',
+        [
+            0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000,
+        ],
+    ),
+];
+
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    FONT.iter()
+        .find(|(character, _)| *character == c)
+        .or_else(|| FONT.iter().find(|(character, _)| *character == '?'))
+        .map(|(_, rows)| *rows)
+        .unwrap()
+}
+
+/// The number of terminal columns a line of text takes up at the given scale.
+pub fn width(text: &str, scale: usize) -> usize {
+    // Letters are separated by a blank pixel column
+    (text.chars().count() * (GLYPH_WIDTH + 1) * scale).saturating_sub(scale)
+}
+
+/// The number of terminal rows a line of text takes up at the given scale.
+pub fn height(scale: usize) -> usize {
+    (GLYPH_HEIGHT * scale).div_ceil(2)
+}
+
+/// Draws a line of text `scale` times the size of the pixel font. Two pixel
+/// rows share a terminal row using the full, upper half and lower half
+/// `blocks`, which keeps the pixels roughly square.
+pub fn render_line(text: &str, scale: usize, [full, upper, lower]: [char; 3]) -> Vec<String> {
+    let glyphs: Vec<[u8; GLYPH_HEIGHT]> = text.chars().map(glyph).collect();
+    let pixel = |x: usize, y: usize| {
+        let (x, y) = (x / scale, y / scale);
+        let column = x % (GLYPH_WIDTH + 1);
+        if y >= GLYPH_HEIGHT || column == GLYPH_WIDTH {
+            return false;
+        }
+        glyphs[x / (GLYPH_WIDTH + 1)][y] & (1 << (GLYPH_WIDTH - 1 - column)) != 0
+    };
+
+    (0..height(scale))
+        .map(|row| {
+            (0..width(text, scale))
+                .map(|x| match (pixel(x, row * 2), pixel(x, row * 2 + 1)) {
+                    (true, true) => full,
+                    (true, false) => upper,
+                    (false, true) => lower,
+                    (false, false) => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Wraps the text into lines and picks the largest scale up to `max_scale` at
+/// which all of them fit into the area, separated by a blank row. Scales that
+/// keep every word on one line are preferred. Returns the scale and the lines,
+/// or `None` if the text doesn't fit even at the smallest scale.
+pub fn fit(
+    text: &str,
+    area_width: usize,
+    area_height: usize,
+    max_scale: usize,
+) -> Option<(usize, Vec<String>)> {
+    let longest_word = text
+        .split_whitespace()
+        .map(|word| word.chars().count())
+        .max()
+        .unwrap_or(0);
+    [false, true].into_iter().find_map(|break_words| {
+        (1..=max_scale).rev().find_map(|scale| {
+            let per_line = (area_width + scale) / ((GLYPH_WIDTH + 1) * scale);
+            if per_line == 0 || (!break_words && per_line < longest_word) {
+                return None;
+            }
+            let lines = wrap(text, per_line);
+            let total_height = lines.len() * (height(scale) + 1) - 1;
+            (total_height <= area_height).then_some((scale, lines))
+        })
+    })
+}
+
+/// Greedily wraps the words of the text into lines of at most `max_chars`
+/// characters, breaking up words that are longer than a line.
+pub(crate) fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    width::wrap_by(text, max_chars, |_| 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCKS: [char; 3] = ['█', '▀', '▄'];
+
+    #[test]
+    fn test_render_line() {
+        let lines = render_line("I1", 1, BLOCKS);
+        assert_eq!(
+            lines,
+            vec![" ▀█▀   ▄█  ", "  █     █  ", "  █     █  ", " ▀▀▀   ▀▀▀ ",]
+        );
+        assert_eq!(
+            render_line("I", 2, BLOCKS)[0].chars().count(),
+            width("I", 2)
+        );
+        assert_eq!(render_line("I", 2, BLOCKS).len(), height(2));
+    }
+
+    #[test]
+    fn test_fit_picks_largest_scale() {
+        let (scale, lines) = fit("hi", 80, 24, MAX_SCALE).unwrap();
+        assert_eq!(scale, 6);
+        assert_eq!(lines, vec!["hi"]);
+        assert_eq!(fit("hi", 80, 24, 2).unwrap().0, 2);
+        let (scale, lines) = fit("wifi password", 40, 24, MAX_SCALE).unwrap();
+        assert_eq!(scale, 1);
+        assert_eq!(lines, vec!["wifi", "passwo", "rd"]);
+        assert_eq!(fit("too long to fit", 10, 3, MAX_SCALE), None);
+        // A smaller scale is used rather than breaking up a word
+        assert_eq!(
+            fit("Welcome", 80, 24, 2),
+            Some((1, vec!["Welcome".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_words() {
+        assert_eq!(wrap("abcdefg hi", 3), vec!["abc", "def", "g", "hi"]);
+    }
+}
@@ -0,0 +1,41 @@
+/// A single `> text` line of a blockquote, nested once per `>`.
+#[derive(Debug, PartialEq)]
+pub struct QuoteLine<'a> {
+    pub depth: usize,
+    pub text: &'a str,
+}
+
+/// Parses a line of a blockquote, where nested quotes start with `> >` or
+/// `>>`.
+pub fn parse_quote_line(line: &str) -> Option<QuoteLine<'_>> {
+    let mut text = line.strip_prefix('>')?.trim_start();
+    let mut depth = 1;
+    while let Some(rest) = text.strip_prefix('>') {
+        text = rest.trim_start();
+        depth += 1;
+    }
+    Some(QuoteLine {
+        depth,
+        text: text.trim_end(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quote_line() {
+        assert_eq!(
+            parse_quote_line("> Simple is better"),
+            Some(QuoteLine {
+                depth: 1,
+                text: "Simple is better"
+            })
+        );
+        assert_eq!(parse_quote_line("> > >> deep").unwrap().depth, 4);
+        assert_eq!(parse_quote_line(">").unwrap().text, "");
+        assert_eq!(parse_quote_line("  > indented"), None);
+        assert_eq!(parse_quote_line("a > b"), None);
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path},
+};
+
+use crate::{exec, images::Image, rendering, sources};
+
+/// Writes a copy of the presentation with the output of `exec_include`
+/// directives and the included code files inserted, together with every image
+/// it shows, into the output directory. Prints what went wrong and returns false if the bundle is
+/// incomplete.
+pub fn run(presentation_file: &str, content: &str, output_dir: &Path) -> bool {
+    match write(presentation_file, content, output_dir) {
+        Ok(image_count) => {
+            println!(
+                "Bundled {} with {} images into {}",
+                presentation_file,
+                image_count,
+                output_dir.display()
+            );
+            true
+        }
+        Err(errors) => {
+            for err in errors {
+                eprintln!("{}", err);
+            }
+            false
+        }
+    }
+}
+
+/// Writes the bundle and returns the number of images in it.
+pub fn write(
+    presentation_file: &str,
+    content: &str,
+    output_dir: &Path,
+) -> Result<usize, Vec<String>> {
+    let source_dir = Path::new(presentation_file)
+        .parent()
+        .unwrap_or(Path::new(""));
+    let (content, mut errors) = exec::expand_includes(content, source_dir);
+    let (content, file_errors) = sources::expand_code_files(&content, source_dir);
+    errors.extend(file_errors);
+
+    let mut images = Images::default();
+    let content = rewrite_image_paths(&content, |path| images.add(path));
+    for (source, target) in &images.copies {
+        let source_path = source_dir.join(source);
+        let target_path = output_dir.join(target);
+        let copied = target_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::copy(&source_path, &target_path));
+        if let Err(err) = copied {
+            errors.push(format!("Could not copy {}: {}", source_path.display(), err));
+        }
+    }
+
+    let file_name = Path::new(presentation_file)
+        .file_name()
+        .unwrap_or("presentation.md".as_ref());
+    let written =
+        fs::create_dir_all(output_dir).and_then(|_| fs::write(output_dir.join(file_name), content));
+    if let Err(err) = written {
+        errors.push(format!(
+            "Could not write to {}: {}",
+            output_dir.display(),
+            err
+        ));
+    }
+    if errors.is_empty() {
+        Ok(images.copies.len())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The images of the bundle, by the path they are referenced by in the
+/// presentation.
+#[derive(Debug, Default)]
+struct Images {
+    targets: HashMap<String, String>,
+    copies: Vec<(String, String)>,
+}
+
+impl Images {
+    /// Returns the path the image is referenced by in the bundle. Paths inside
+    /// the presentation directory are kept, others are moved to `images/`.
+    fn add(&mut self, path: &str) -> String {
+        if let Some(target) = self.targets.get(path) {
+            return target.clone();
+        }
+        let target = if is_nested(Path::new(path)) {
+            path.to_string()
+        } else {
+            let file_name = Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "image".to_string());
+            let mut target = format!("images/{}", file_name);
+            let mut number = 1;
+            while self.copies.iter().any(|(_, taken)| *taken == target) {
+                number += 1;
+                target = format!("images/{}-{}", number, file_name);
+            }
+            target
+        };
+        self.targets.insert(path.to_string(), target.clone());
+        self.copies.push((path.to_string(), target.clone()));
+        target
+    }
+}
+
+/// Whether a relative path stays below the directory it is relative to.
+fn is_nested(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Replaces the paths of inline images and of `full_image` and
+/// `background_image` directives outside of code blocks.
+fn rewrite_image_paths(content: &str, mut rewrite: impl FnMut(&str) -> String) -> String {
+    let mut result = String::new();
+    let mut in_code_block = false;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let line_ending = &line[text.len()..];
+        if text.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block {
+            result.push_str(line);
+            continue;
+        }
+        if let Some(image) = Image::parse(text) {
+            let title = image
+                .title
+                .map(|title| format!(" \"{}\"", title))
+                .unwrap_or_default();
+            result.push_str(&format!(
+                "![{}]({}{})",
+                image.alt,
+                rewrite(image.path),
+                title
+            ));
+        } else if let Some(image) = rendering::extract_full_image(text) {
+            let path = rewrite(image.path);
+            match image.caption {
+                Some(caption) => {
+                    result.push_str(&format!("<!-- full_image: {} \"{}\" -->", path, caption))
+                }
+                None => result.push_str(&format!("<!-- full_image: {} -->", path)),
+            }
+        } else if let Some(path) = rendering::extract_directive(text, "background_image") {
+            result.push_str(&format!("<!-- background_image: {} -->", rewrite(path)));
+        } else {
+            result.push_str(text);
+        }
+        result.push_str(line_ending);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_image_paths() {
+        let content = "![Logo](../shared/logo.png \"width=20\")\n\
+            <!-- full_image: photos/forest.jpg \"Into the woods\" -->\n\
+            ```markdown\n![Logo](../shared/logo.png)\n```\n\
+            <!-- background_image: /usr/share/brand.png -->\n";
+        let mut images = Images::default();
+        let rewritten = rewrite_image_paths(content, |path| images.add(path));
+        assert_eq!(
+            rewritten,
+            "![Logo](images/logo.png \"width=20\")\n\
+            <!-- full_image: photos/forest.jpg \"Into the woods\" -->\n\
+            ```markdown\n![Logo](../shared/logo.png)\n```\n\
+            <!-- background_image: images/brand.png -->\n"
+        );
+        assert_eq!(images.copies.len(), 3);
+    }
+
+    #[test]
+    fn test_image_names_stay_unique() {
+        let mut images = Images::default();
+        assert_eq!(images.add("../a/logo.png"), "images/logo.png");
+        assert_eq!(images.add("../b/logo.png"), "images/2-logo.png");
+        assert_eq!(images.add("../a/logo.png"), "images/logo.png");
+        assert_eq!(images.copies.len(), 2);
+    }
+}
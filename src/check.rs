@@ -0,0 +1,444 @@
+use std::{env, fs, path::Path, process};
+
+use crate::{
+    cli::Options,
+    error::Error,
+    exec::ExecCache,
+    expand_includes,
+    languages::LanguageAliases,
+    parse_metadata, parse_presentation,
+    rendering::{self, CodeBlock, Overflow},
+    source_line, split_front_matter, split_slides, Presentation,
+};
+
+#[derive(Debug, Default)]
+pub struct CheckOptions {
+    pub compile: bool,
+    /// Run the `+annotate` snippets and compare their output to the cached one
+    pub annotate: bool,
+    /// Compiler commands per language overriding the defaults. `{file}` is
+    /// replaced by the snippet path and `{dir}` by a scratch directory.
+    pub compilers: Vec<(String, String)>,
+    /// The terminal size in columns and rows to check that every slide
+    /// fits into
+    pub fit: Option<(u16, u16)>,
+}
+
+impl CheckOptions {
+    pub fn any_selected(&self) -> bool {
+        self.compile || self.annotate || self.fit.is_some()
+    }
+
+    /// Selects every check that works without further input, which leaves out
+    /// the fit check as it needs a terminal size.
+    pub fn select_all(&mut self) {
+        self.compile = true;
+        self.annotate = true;
+    }
+
+    fn compiler(&self, language: &str) -> Option<String> {
+        self.compilers
+            .iter()
+            .rev()
+            .find(|(configured, _)| configured == language)
+            .map(|(_, command)| command.clone())
+            .or_else(|| default_compiler(language).map(String::from))
+    }
+}
+
+fn default_compiler(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" => {
+            Some("rustc --edition 2021 --crate-type lib --emit metadata --out-dir {dir} {file}")
+        }
+        "python" => Some("python3 -m py_compile {file}"),
+        "bash" => Some("bash -n {file}"),
+        "sh" => Some("sh -n {file}"),
+        "javascript" => Some("node --check {file}"),
+        _ => None,
+    }
+}
+
+fn file_extension(language: &str) -> &str {
+    match language {
+        "rust" => "rs",
+        "python" => "py",
+        "bash" | "sh" => "sh",
+        "javascript" => "js",
+        "java" => "java",
+        _ => "txt",
+    }
+}
+
+/// Runs the selected checks and prints a report. Returns false if any check
+/// failed.
+pub fn run(
+    presentation_file: &str,
+    content: &str,
+    options: &CheckOptions,
+    presentation_options: Options,
+) -> bool {
+    let aliases = &presentation_options.language_aliases;
+    // Checked as presented, with what the commands and files include
+    let (content, origins, errors) = expand_includes(presentation_file, content, false);
+    for err in &errors {
+        println!("{}: {}", presentation_file, err);
+    }
+    let mut success = errors.is_empty();
+    let blocks = numbered_code_blocks(&content, &origins, aliases);
+    if options.compile {
+        success &= check_compile(presentation_file, &blocks, options);
+    }
+    if options.annotate {
+        success &= check_annotations(presentation_file, &blocks);
+    }
+    if let Some(size) = options.fit {
+        let expanded = (content.as_str(), origins.as_slice());
+        success &= check_fit(presentation_file, expanded, size, presentation_options);
+    }
+    success
+}
+
+/// The code blocks of the expanded content with the line of the presentation
+/// file they start at and the number of their slide.
+fn numbered_code_blocks(
+    content: &str,
+    origins: &[usize],
+    aliases: &LanguageAliases,
+) -> Vec<(usize, usize, CodeBlock)> {
+    numbered_slides(content)
+        .into_iter()
+        .flat_map(|(slide_number, slide_start_line, slide)| {
+            rendering::code_blocks(slide, aliases)
+                .into_iter()
+                .map(move |(line_index, block)| {
+                    let line = source_line(origins, slide_start_line + line_index);
+                    (line, slide_number, block)
+                })
+        })
+        .collect()
+}
+
+/// The slides of the content with their number as shown in the footer and
+/// the line they start at.
+fn numbered_slides(content: &str) -> Vec<(usize, usize, &str)> {
+    let (_, content_without_metadata) = split_front_matter(content);
+    let metadata_lines = content[..content.len() - content_without_metadata.len()]
+        .matches('\n')
+        .count();
+    let metadata = parse_metadata(content)
+        .map(|(metadata, _)| metadata)
+        .unwrap_or_default();
+    // The generated title slide comes before the first slide of the file
+    let first_slide = if metadata.has_title_slide() { 2 } else { 1 };
+    let mut start_line = metadata_lines + 1;
+    split_slides(content_without_metadata, &metadata.options)
+        .into_iter()
+        .enumerate()
+        .map(|(index, slide)| {
+            let numbered = (first_slide + index, start_line, slide);
+            start_line += slide.matches('\n').count();
+            numbered
+        })
+        .collect()
+}
+
+fn check_compile(
+    presentation_file: &str,
+    blocks: &[(usize, usize, CodeBlock)],
+    options: &CheckOptions,
+) -> bool {
+    let scratch_dir = env::temp_dir().join(format!("term_deck-check-{}", process::id()));
+    if let Err(err) = fs::create_dir_all(&scratch_dir) {
+        eprintln!("Could not create {}: {}", scratch_dir.display(), err);
+        return false;
+    }
+
+    let (mut checked, mut failed, mut skipped) = (0, 0, 0);
+    for (line, slide_number, block) in blocks {
+        if block
+            .attributes
+            .iter()
+            .any(|attribute| attribute == "+no_check")
+        {
+            continue;
+        }
+        let Some(command) = options.compiler(&block.language) else {
+            skipped += 1;
+            continue;
+        };
+        checked += 1;
+        if let Err(output) =
+            compile_snippet(&block.language, &block.content, &command, &scratch_dir)
+        {
+            failed += 1;
+            println!(
+                "{}:{} (slide {}, {}): snippet does not compile",
+                presentation_file, line, slide_number, block.language
+            );
+            for line in output.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&scratch_dir);
+
+    println!(
+        "Checked {} snippets: {} failed, {} skipped without a compiler",
+        checked, failed, skipped
+    );
+    failed == 0
+}
+
+/// Runs the `+annotate` snippets like loading the presentation does, and
+/// reports the ones whose output doesn't fill their markers or changed since
+/// it was cached, as then the slides showed outdated output.
+fn check_annotations(presentation_file: &str, blocks: &[(usize, usize, CodeBlock)]) -> bool {
+    let mut cache = ExecCache::new(false);
+    let (mut checked, mut failed) = (0, 0);
+    for (line, slide_number, block) in blocks {
+        if !block.is_annotated() {
+            continue;
+        }
+        checked += 1;
+        let (output, cached) = cache.rerun(&block.language, &block.content);
+        let problems = annotation_problems(&block.content, output, cached.as_deref());
+        if problems.is_empty() {
+            continue;
+        }
+        failed += 1;
+        println!(
+            "{}:{} (slide {}, {}): {}",
+            presentation_file,
+            line,
+            slide_number,
+            block.language,
+            problems.join(", ")
+        );
+        for line in output.lines() {
+            println!("    {}", line);
+        }
+    }
+    println!("Checked {} annotated snippets: {} failed", checked, failed);
+    failed == 0
+}
+
+fn annotation_problems(content: &str, output: &str, cached: Option<&str>) -> Vec<String> {
+    let markers = content
+        .lines()
+        .filter(|line| rendering::is_annotation_marker(line))
+        .count();
+    let lines = output.lines().count();
+    let mut problems = Vec::new();
+    if lines < markers {
+        problems.push(format!(
+            "{} of output for {}",
+            amount(lines, "line"),
+            amount(markers, "marker")
+        ));
+    }
+    if cached.is_some_and(|cached| cached != output) {
+        problems.push("output changed since it was cached".to_string());
+    }
+    problems
+}
+
+fn check_fit(
+    presentation_file: &str,
+    (content, origins): (&str, &[usize]),
+    (width, height): (u16, u16),
+    options: Options,
+) -> bool {
+    let slides = match measure_slides(content, (width, height), options) {
+        Ok(slides) => slides,
+        Err(err) => {
+            println!("{}: {}", presentation_file, err);
+            return false;
+        }
+    };
+    let mut overflowing = 0;
+    for (line, slide_number, overflow) in &slides {
+        if *overflow == Overflow::default() {
+            continue;
+        }
+        overflowing += 1;
+        println!(
+            "{}:{} (slide {}): {}",
+            presentation_file,
+            source_line(origins, *line),
+            slide_number,
+            overflow_text(overflow)
+        );
+    }
+    println!(
+        "Checked {} slides at {}x{}: {} overflow",
+        slides.len(),
+        width,
+        height,
+        overflowing
+    );
+    overflowing == 0
+}
+
+/// Lays out every slide fully revealed like presenting it on a terminal of the
+/// given size does. Returns the line, the number and the overflow of each.
+fn measure_slides(
+    content: &str,
+    size: (u16, u16),
+    options: Options,
+) -> Result<Vec<(usize, usize, Overflow)>, String> {
+    let (metadata, slides) = parse_presentation(content, &options.language_aliases)?;
+    let mut presentation = Presentation::new(metadata, slides, "", options, 0);
+    rendering::set_layout_size(Some(size));
+    let measured = (0..presentation.total_slides())
+        .map(|index| {
+            presentation.go_to_slide(index);
+            presentation.reveal_all_steps();
+            let overflow = rendering::measure_overflow(&presentation)?;
+            Ok((presentation.current_line(), index + 1, overflow))
+        })
+        .collect::<Result<_, Error>>()
+        .map_err(|err| err.to_string());
+    rendering::set_layout_size(None);
+    measured
+}
+
+fn overflow_text(overflow: &Overflow) -> String {
+    let mut excess = Vec::new();
+    if overflow.rows > 0 {
+        excess.push(format!("{} too tall", amount(overflow.rows, "row")));
+    }
+    if overflow.columns > 0 {
+        excess.push(format!("{} too wide", amount(overflow.columns, "column")));
+    }
+    excess.join(", ")
+}
+
+fn amount(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+fn compile_snippet(
+    language: &str,
+    content: &str,
+    command: &str,
+    scratch_dir: &Path,
+) -> Result<(), String> {
+    let file = scratch_dir.join(format!("snippet.{}", file_extension(language)));
+    fs::write(&file, content).map_err(|err| err.to_string())?;
+
+    let file = file.to_string_lossy();
+    let dir = scratch_dir.to_string_lossy();
+    let mut arguments: Vec<String> = command
+        .split_whitespace()
+        .map(|argument| argument.replace("{file}", &file).replace("{dir}", &dir))
+        .collect();
+    if !command.contains("{file}") {
+        arguments.push(file.to_string());
+    }
+
+    let output = process::Command::new(&arguments[0])
+        .args(&arguments[1..])
+        .output()
+        .map_err(|err| format!("Failed to run {}: {}", arguments[0], err))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let mut text = String::from_utf8_lossy(&output.stderr).to_string();
+        text.push_str(&String::from_utf8_lossy(&output.stdout));
+        Err(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_compiler_overrides_default() {
+        let options = CheckOptions {
+            compile: true,
+            compilers: vec![("python".to_string(), "mypy {file}".to_string())],
+            ..CheckOptions::default()
+        };
+        assert_eq!(options.compiler("python").unwrap(), "mypy {file}");
+        assert_eq!(
+            options.compiler("bash").unwrap(),
+            default_compiler("bash").unwrap()
+        );
+        assert_eq!(options.compiler("cobol"), None);
+    }
+
+    #[test]
+    fn test_included_snippet_is_reported_at_its_fence() {
+        let directory = env::temp_dir().join(format!("term_deck-check-test-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("broken.sh"), "if then\nfi\n").unwrap();
+        let presentation_file = directory.join("talk.md").display().to_string();
+        let content =
+            "# Intro\n---\n<!-- exec_include: printf 'a\\nb\\n' -->\n```sh file=broken.sh\n```\n";
+        let options = CheckOptions {
+            compile: true,
+            ..CheckOptions::default()
+        };
+        assert!(!run(
+            &presentation_file,
+            content,
+            &options,
+            Options::default()
+        ));
+
+        let (expanded, origins, errors) = expand_includes(&presentation_file, content, false);
+        assert!(errors.is_empty());
+        assert_eq!(origins, [1, 2, 3, 3, 4, 4, 4, 4]);
+        let blocks = numbered_code_blocks(&expanded, &origins, &LanguageAliases::default());
+        let [(line, slide_number, block)] = blocks.as_slice() else {
+            panic!("expected one code block");
+        };
+        assert_eq!((*line, *slide_number), (4, 2));
+        assert_eq!(block.content, "if then\nfi");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_annotation_problems() {
+        let content = "print(1)  # =>\nprint(2)  # =>\n";
+        assert!(annotation_problems(content, "1\n2\n", Some("1\n2\n")).is_empty());
+        assert_eq!(
+            annotation_problems(content, "1\n", None),
+            ["1 line of output for 2 markers"]
+        );
+        assert_eq!(
+            annotation_problems(content, "1\n3\n", Some("1\n2\n")),
+            ["output changed since it was cached"]
+        );
+    }
+
+    #[test]
+    fn test_measure_slides() {
+        let overflowing = |content: &str, size| {
+            let slides = measure_slides(content, size, Options::default()).unwrap();
+            slides
+                .into_iter()
+                .filter(|(_, _, overflow)| *overflow != Overflow::default())
+                .collect::<Vec<_>>()
+        };
+        let overflow = |rows, columns| Overflow { rows, columns };
+        let slide = "# Title\n\nA line of exactly thirty-one ch\n\n```rust\nfn main() {}\n```\n";
+        assert_eq!(overflowing(slide, (40, 12)), []);
+        // The text wraps into two rows, pushing the code down past the footer
+        assert_eq!(overflowing(slide, (20, 10)), [(1, 1, overflow(3, 0))]);
+        // Five rows from row 4 including the borders
+        let table = "| a | b |\n|---|---|\n| 1 | 2 |\n";
+        assert_eq!(overflowing(table, (9, 9)), [(1, 1, overflow(1, 0))]);
+        // The line numbers take two more columns
+        let numbered = "```rust,linenos\nfn main() {}\n```\n";
+        assert_eq!(overflowing(numbered, (15, 12)), [(1, 1, overflow(0, 2))]);
+        // The title slide comes first and the footnote takes up the bottom
+        let deck = "---\ntitle: Talk\n---\n# Notes[^1]\n\n1\n2\n3\n\n[^1]: A note\n";
+        assert_eq!(overflowing(deck, (40, 12)), [(4, 2, overflow(1, 0))]);
+    }
+}
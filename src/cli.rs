@@ -0,0 +1,605 @@
+use std::{path::Path, time::Duration};
+
+use crate::{
+    alerts::{self, Alert},
+    archive::ARCHIVE_EXTENSION,
+    check::CheckOptions,
+    cues::parse_duration,
+    images::{Dither, ImageOptions, Palette},
+    languages::LanguageAliases,
+    server::DEFAULT_PORT,
+};
+
+pub enum Command {
+    Present,
+    Check(CheckOptions),
+    /// Write a self-contained copy of the presentation to the directory
+    Bundle(String),
+    /// Write the presentation with its images and theme into an archive
+    Pack(String),
+    /// Report what the terminal can do
+    Doctor,
+}
+
+pub struct Args {
+    pub command: Command,
+    pub presentation_file: String,
+    /// Further presentations to switch to
+    pub decks: Vec<String>,
+    /// Index of the slide to start at. Too large values start at the last one.
+    pub start_slide: usize,
+    /// Start at the slide the presentation was left at the last time.
+    pub resume: bool,
+    pub options: Options,
+}
+
+/// Settings that influence how the presentation is rendered and run.
+pub struct Options {
+    pub image_options: ImageOptions,
+    pub refresh_exec: bool,
+    pub language_aliases: LanguageAliases,
+    pub tab_width: usize,
+    /// Ask before quitting, so a stray `q` does not end the talk.
+    pub confirm_quit: bool,
+    /// Columns and rows the slides are moved right and down by.
+    pub shift: (u16, u16),
+    /// Show the slides in reverse video, e.g. for a teleprompter.
+    pub invert: bool,
+    /// The name of the theme to start with, overriding the frontmatter.
+    pub theme: Option<String>,
+    /// A file with the times at which the slides are due.
+    pub cues: Option<String>,
+    /// Go to the slides at the times given in the cue file.
+    pub follow_cues: bool,
+    /// The length of a lightning talk, which is split evenly across the
+    /// slides.
+    pub lightning: Option<Duration>,
+    /// The port of the web server for polls.
+    pub port: u16,
+    /// A secret the web server requires in every request.
+    pub token: Option<String>,
+    /// How to tell the presenter that the talk is behind its schedule.
+    pub overtime_alerts: Vec<Alert>,
+    /// Record the time spent on each slide and report it on exit.
+    pub rehearse: bool,
+    /// Move on to the next slide after this long, unless the slide sets its
+    /// own `duration`.
+    pub auto_advance: Option<Duration>,
+    /// Go from the last slide back to the first and the other way round.
+    pub loop_slides: bool,
+    /// Draw borders, bullets, bars and banner letters with ASCII characters
+    /// only, whatever the theme and configuration say.
+    pub ascii: bool,
+    /// A file to append the events of the talk to, one JSON object per line.
+    pub event_log: Option<String>,
+    /// Only move through the slides, e.g. for attendees following along on
+    /// their own laptops.
+    pub read_only: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            image_options: ImageOptions::default(),
+            refresh_exec: false,
+            language_aliases: LanguageAliases::default(),
+            tab_width: 4,
+            confirm_quit: false,
+            shift: (0, 0),
+            invert: false,
+            theme: None,
+            cues: None,
+            follow_cues: false,
+            lightning: None,
+            port: DEFAULT_PORT,
+            token: None,
+            overtime_alerts: Vec::new(),
+            rehearse: false,
+            auto_advance: None,
+            loop_slides: false,
+            ascii: false,
+            event_log: None,
+            read_only: false,
+        }
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut presentation_file = None;
+    let mut decks = Vec::new();
+    let mut dither = None;
+    let mut palette = None;
+    let mut check = None;
+    let mut subcommand = None;
+    // The directory or archive written by `bundle` and `pack`
+    let mut output = None;
+    let mut start_slide = 0;
+    let mut resume = false;
+    let mut options = Options::default();
+
+    let mut iter = args.iter().skip(1).peekable();
+    if let Some(name) = iter
+        .peek()
+        .map(|arg| arg.as_str())
+        .filter(|arg| ["check", "bundle", "pack", "doctor"].contains(arg))
+    {
+        iter.next();
+        subcommand = Some(name);
+    }
+    if subcommand == Some("check") {
+        check = Some(CheckOptions::default());
+    }
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--compile" if check.is_some() => {
+                check.as_mut().unwrap().compile = true;
+            }
+            "--annotate" if check.is_some() => {
+                check.as_mut().unwrap().annotate = true;
+            }
+            "--compiler" if check.is_some() => {
+                let value = next_value(&mut iter, arg)?;
+                let (language, command) = value.split_once('=').ok_or(format!(
+                    "Expected <language>=<command> for --compiler, got '{}'",
+                    value
+                ))?;
+                check
+                    .as_mut()
+                    .unwrap()
+                    .compilers
+                    .push((language.to_string(), command.to_string()));
+            }
+            "--fit" if check.is_some() => {
+                let value = next_value(&mut iter, arg)?;
+                check.as_mut().unwrap().fit = Some(parse_size(value).ok_or(format!(
+                    "Expected <columns>x<rows> for --fit, got '{}'",
+                    value
+                ))?);
+            }
+            "--dither" => {
+                let value = next_value(&mut iter, arg)?;
+                dither = Some(Dither::from_name(value).ok_or(format!(
+                    "Unknown dither mode '{}', expected none, ordered or floyd-steinberg",
+                    value
+                ))?);
+            }
+            "--palette" => {
+                let value = next_value(&mut iter, arg)?;
+                palette = Some(Palette::from_size(value).ok_or(format!(
+                    "Unknown palette size '{}', expected 8, 16 or 256",
+                    value
+                ))?);
+            }
+            "--refresh-exec" => options.refresh_exec = true,
+            "--confirm-quit" => options.confirm_quit = true,
+            "--slide" => {
+                let value = next_value(&mut iter, arg)?;
+                start_slide = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|number| *number > 0)
+                    .ok_or(format!("Invalid slide number '{}'", value))?
+                    - 1;
+            }
+            "--last" => start_slide = usize::MAX,
+            "--resume" => resume = true,
+            "--invert" => options.invert = true,
+            "--rehearse" => options.rehearse = true,
+            "--theme" => options.theme = Some(next_value(&mut iter, arg)?.to_string()),
+            "--cues" => options.cues = Some(next_value(&mut iter, arg)?.to_string()),
+            "--event-log" => options.event_log = Some(next_value(&mut iter, arg)?.to_string()),
+            "--follow-cues" => options.follow_cues = true,
+            "--lightning" => {
+                let value = next_value(&mut iter, arg)?;
+                options.lightning = Some(parse_duration(value).ok_or(format!(
+                    "Invalid duration '{}', expected e.g. 5m or 4m30s",
+                    value
+                ))?);
+            }
+            "--auto-advance" => {
+                let value = next_value(&mut iter, arg)?;
+                options.auto_advance = Some(parse_duration(value).ok_or(format!(
+                    "Invalid duration '{}', expected e.g. 10s or 1m30s",
+                    value
+                ))?);
+            }
+            "--loop" => options.loop_slides = true,
+            "--ascii" => options.ascii = true,
+            "--read-only" => options.read_only = true,
+            "--port" => {
+                let value = next_value(&mut iter, arg)?;
+                options.port = value
+                    .parse()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or(format!("Invalid port '{}'", value))?;
+            }
+            "--token" => {
+                let value = next_value(&mut iter, arg)?;
+                let valid = !value.is_empty()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if !valid {
+                    return Err(format!(
+                        "Invalid token '{}', expected letters, digits, - and _",
+                        value
+                    ));
+                }
+                options.token = Some(value.to_string());
+            }
+            "--overtime-alert" => {
+                let value = next_value(&mut iter, arg)?;
+                options.overtime_alerts = alerts::parse_alerts(&value.replace(',', " "))
+                    .filter(|alerts| !alerts.is_empty())
+                    .ok_or(format!(
+                        "Unknown alert '{}', expected bell, notify or bell,notify",
+                        value
+                    ))?;
+            }
+            "--shift" => {
+                let value = next_value(&mut iter, arg)?;
+                options.shift = parse_shift(value).ok_or(format!(
+                    "Expected <columns>[,<rows>] for --shift, got '{}'",
+                    value
+                ))?;
+            }
+            "--tab-width" => {
+                let value = next_value(&mut iter, arg)?;
+                options.tab_width = value
+                    .parse()
+                    .ok()
+                    .filter(|width| *width > 0)
+                    .ok_or(format!("Invalid tab width '{}'", value))?;
+            }
+            "--language-alias" => {
+                let value = next_value(&mut iter, arg)?;
+                let (alias, language) = value.split_once('=').ok_or(format!(
+                    "Expected <alias>=<language> for --language-alias, got '{}'",
+                    value
+                ))?;
+                options.language_aliases.insert(alias, language);
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if presentation_file.is_some()
+                && output.is_none()
+                && matches!(subcommand, Some("bundle" | "pack")) =>
+            {
+                output = Some(arg.clone())
+            }
+            _ if presentation_file.is_some() && subcommand.is_none() => decks.push(arg.clone()),
+            _ if subcommand == Some("doctor") => {
+                return Err("doctor checks the terminal and takes no presentation".to_string())
+            }
+            _ => presentation_file = Some(arg.clone()),
+        }
+    }
+
+    let presentation_file = match subcommand {
+        Some("doctor") => String::new(),
+        _ => presentation_file
+            .ok_or("Please provide a presentation markdown file as an argument!")?,
+    };
+
+    if palette.is_some() && dither == Some(Dither::None) {
+        return Err("--palette has no effect with --dither none".to_string());
+    }
+    if let Some(palette) = palette {
+        // Choosing a palette implies quantization, Floyd-Steinberg keeps photos
+        // the most recognizable.
+        options.image_options.palette = palette;
+        options.image_options.dither = Dither::FloydSteinberg;
+    }
+    if let Some(dither) = dither {
+        options.image_options.dither = dither;
+    }
+
+    if options.follow_cues && options.cues.is_none() {
+        return Err("--follow-cues needs a cue file given with --cues".to_string());
+    }
+    if !options.overtime_alerts.is_empty() && options.cues.is_none() && options.lightning.is_none()
+    {
+        return Err("--overtime-alert needs --cues or --lightning".to_string());
+    }
+    if options.lightning.is_some() && options.cues.is_some() {
+        return Err("--lightning can't be combined with --cues".to_string());
+    }
+    if options.auto_advance.is_some() && (options.lightning.is_some() || options.cues.is_some()) {
+        return Err("--auto-advance can't be combined with --lightning or --cues".to_string());
+    }
+
+    let command = match (check, subcommand) {
+        (Some(mut options), _) => {
+            // Without an explicit selection every check is run
+            if !options.any_selected() {
+                options.select_all();
+            }
+            Command::Check(options)
+        }
+        (None, Some("bundle")) => {
+            Command::Bundle(output.ok_or("Please provide the directory to write the bundle to!")?)
+        }
+        (None, Some("pack")) => Command::Pack(output.unwrap_or_else(|| {
+            Path::new(&presentation_file)
+                .with_extension(ARCHIVE_EXTENSION)
+                .display()
+                .to_string()
+        })),
+        (None, Some("doctor")) => Command::Doctor,
+        _ => Command::Present,
+    };
+
+    Ok(Args {
+        command,
+        presentation_file,
+        decks,
+        start_slide,
+        resume,
+        options,
+    })
+}
+
+fn parse_shift(value: &str) -> Option<(u16, u16)> {
+    let (columns, rows) = value.split_once(',').unwrap_or((value, "0"));
+    Some((columns.trim().parse().ok()?, rows.trim().parse().ok()?))
+}
+
+/// Parses a terminal size like `80x24`.
+fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (columns, rows) = value.split_once('x')?;
+    let size = (columns.trim().parse().ok()?, rows.trim().parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+fn next_value<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<&'a str, String> {
+    iter.next()
+        .map(|value| value.as_str())
+        .ok_or(format!("Missing value for {}", option))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        std::iter::once("term_deck")
+            .chain(values.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_presentation_file_only() {
+        let args = parse_args(&args(&["slides.md"])).unwrap();
+        assert_eq!(args.presentation_file, "slides.md");
+        assert_eq!(args.options.image_options.dither, Dither::None);
+    }
+
+    #[test]
+    fn test_palette_implies_floyd_steinberg() {
+        let args = parse_args(&args(&["--palette", "16", "slides.md"])).unwrap();
+        assert_eq!(args.options.image_options.palette, Palette::Ansi16);
+        assert_eq!(args.options.image_options.dither, Dither::FloydSteinberg);
+    }
+
+    #[test]
+    fn test_palette_without_dithering() {
+        assert!(parse_args(&args(&["--palette", "8", "--dither", "none", "slides.md"])).is_err());
+    }
+
+    #[test]
+    fn test_explicit_dither_mode() {
+        let args = parse_args(&args(&["slides.md", "--dither", "ordered"])).unwrap();
+        assert_eq!(args.options.image_options.dither, Dither::Ordered);
+        assert_eq!(args.options.image_options.palette, Palette::Xterm256);
+    }
+
+    #[test]
+    fn test_check_subcommand() {
+        let args = parse_args(&args(&[
+            "check",
+            "--compile",
+            "--compiler",
+            "python=python3 -m py_compile {file}",
+            "slides.md",
+        ]))
+        .unwrap();
+        assert_eq!(args.presentation_file, "slides.md");
+        match args.command {
+            Command::Check(options) => {
+                assert!(options.compile);
+                assert_eq!(
+                    options.compilers,
+                    vec![(
+                        "python".to_string(),
+                        "python3 -m py_compile {file}".to_string()
+                    )]
+                );
+            }
+            _ => panic!("expected check command"),
+        }
+    }
+
+    #[test]
+    fn test_bundle_subcommand() {
+        let parsed = parse_args(&args(&["bundle", "deck.md", "out/"])).unwrap();
+        assert_eq!(parsed.presentation_file, "deck.md");
+        assert!(matches!(parsed.command, Command::Bundle(dir) if dir == "out/"));
+        assert!(parse_args(&args(&["bundle", "deck.md"])).is_err());
+    }
+
+    #[test]
+    fn test_doctor_subcommand() {
+        assert!(matches!(
+            parse_args(&args(&["doctor"])).unwrap().command,
+            Command::Doctor
+        ));
+        assert!(parse_args(&args(&["doctor", "deck.md"])).is_err());
+    }
+
+    #[test]
+    fn test_pack_subcommand() {
+        let parsed = parse_args(&args(&["pack", "talks/deck.md"])).unwrap();
+        assert!(matches!(parsed.command, Command::Pack(file) if file == "talks/deck.termdeck"));
+        let parsed = parse_args(&args(&["pack", "deck.md", "share.termdeck"])).unwrap();
+        assert!(matches!(parsed.command, Command::Pack(file) if file == "share.termdeck"));
+    }
+
+    #[test]
+    fn test_check_fit() {
+        let parsed = parse_args(&args(&["check", "--fit", "80x24", "slides.md"])).unwrap();
+        match parsed.command {
+            Command::Check(options) => {
+                assert_eq!(options.fit, Some((80, 24)));
+                // Asking for the fit check leaves out the others
+                assert!(!options.compile && !options.annotate);
+            }
+            _ => panic!("expected check command"),
+        }
+        assert!(parse_args(&args(&["check", "--fit", "80", "slides.md"])).is_err());
+        assert!(parse_args(&args(&["check", "--fit", "0x24", "slides.md"])).is_err());
+        assert!(parse_args(&args(&["--fit", "80x24", "slides.md"])).is_err());
+    }
+
+    #[test]
+    fn test_check_options_require_check_command() {
+        assert!(parse_args(&args(&["--compile", "slides.md"])).is_err());
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let parsed = parse_args(&args(&["--tab-width", "8", "slides.md"])).unwrap();
+        assert_eq!(parsed.options.tab_width, 8);
+        assert!(parse_args(&args(&["--tab-width", "0", "slides.md"])).is_err());
+    }
+
+    #[test]
+    fn test_shift() {
+        let parsed = parse_args(&args(&["--shift", "10,2", "slides.md"])).unwrap();
+        assert_eq!(parsed.options.shift, (10, 2));
+        let parsed = parse_args(&args(&["--shift", "6", "slides.md"])).unwrap();
+        assert_eq!(parsed.options.shift, (6, 0));
+        assert!(parse_args(&args(&["--shift", "-1", "slides.md"])).is_err());
+    }
+
+    #[test]
+    fn test_start_slide() {
+        assert_eq!(parse_args(&args(&["slides.md"])).unwrap().start_slide, 0);
+        let parsed = parse_args(&args(&["--slide", "23", "slides.md"])).unwrap();
+        assert_eq!(parsed.start_slide, 22);
+        let parsed = parse_args(&args(&["slides.md", "--last"])).unwrap();
+        assert_eq!(parsed.start_slide, usize::MAX);
+        assert!(parse_args(&args(&["--slide", "0", "slides.md"])).is_err());
+        assert!(
+            parse_args(&args(&["--resume", "slides.md"]))
+                .unwrap()
+                .resume
+        );
+    }
+
+    #[test]
+    fn test_several_decks() {
+        let parsed = parse_args(&args(&["talk.md", "--last", "backup.md"])).unwrap();
+        assert_eq!(parsed.presentation_file, "talk.md");
+        assert_eq!(parsed.decks, vec!["backup.md"]);
+    }
+
+    #[test]
+    fn test_cues() {
+        let parsed =
+            parse_args(&args(&["talk.md", "--cues", "talk.cues", "--follow-cues"])).unwrap();
+        assert_eq!(parsed.options.cues.as_deref(), Some("talk.cues"));
+        assert!(parsed.options.follow_cues);
+        assert!(parse_args(&args(&["talk.md", "--follow-cues"])).is_err());
+    }
+
+    #[test]
+    fn test_event_log() {
+        let parsed = parse_args(&args(&["talk.md", "--event-log", "talk.ndjson"])).unwrap();
+        assert_eq!(parsed.options.event_log.as_deref(), Some("talk.ndjson"));
+        assert!(parse_args(&args(&["talk.md", "--event-log"])).is_err());
+    }
+
+    #[test]
+    fn test_read_only() {
+        assert!(!parse_args(&args(&["talk.md"])).unwrap().options.read_only);
+        let parsed = parse_args(&args(&["--read-only", "talk.md"])).unwrap();
+        assert!(parsed.options.read_only);
+    }
+
+    #[test]
+    fn test_lightning() {
+        let parsed = parse_args(&args(&["talk.md", "--lightning", "5m"])).unwrap();
+        assert_eq!(parsed.options.lightning, Some(Duration::from_secs(300)));
+        assert!(parse_args(&args(&["talk.md", "--lightning", "5 minutes"])).is_err());
+    }
+
+    #[test]
+    fn test_auto_advance() {
+        let parsed = parse_args(&args(&["--auto-advance", "10s", "--loop", "talk.md"])).unwrap();
+        assert_eq!(parsed.options.auto_advance, Some(Duration::from_secs(10)));
+        assert!(parsed.options.loop_slides);
+        assert!(parse_args(&args(&["--auto-advance", "ten", "talk.md"])).is_err());
+        assert!(parse_args(&args(&[
+            "--auto-advance",
+            "10s",
+            "--lightning",
+            "5m",
+            "talk.md"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_overtime_alert() {
+        let parsed = parse_args(&args(&[
+            "talk.md",
+            "--lightning",
+            "5m",
+            "--overtime-alert",
+            "bell,notify",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed.options.overtime_alerts,
+            vec![Alert::Bell, Alert::Desktop]
+        );
+        assert!(parse_args(&args(&["talk.md", "--overtime-alert", "bell"])).is_err());
+        assert!(parse_args(&args(&[
+            "talk.md",
+            "--lightning",
+            "5m",
+            "--overtime-alert",
+            "x"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_port() {
+        assert_eq!(parse_args(&args(&["talk.md"])).unwrap().options.port, 7070);
+        let parsed = parse_args(&args(&["talk.md", "--port", "8080"])).unwrap();
+        assert_eq!(parsed.options.port, 8080);
+        assert!(parse_args(&args(&["talk.md", "--port", "0"])).is_err());
+    }
+
+    #[test]
+    fn test_token() {
+        let parsed = parse_args(&args(&["talk.md", "--token", "conf-2024_x"])).unwrap();
+        assert_eq!(parsed.options.token.as_deref(), Some("conf-2024_x"));
+        assert!(parse_args(&args(&["talk.md", "--token", "a&b"])).is_err());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert!(parse_args(&args(&["--dither", "ordered"])).is_err());
+    }
+
+    #[test]
+    fn test_unknown_option() {
+        assert!(parse_args(&args(&["--bogus", "slides.md"])).is_err());
+    }
+}
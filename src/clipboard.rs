@@ -0,0 +1,70 @@
+use std::{env, io::Write, sync::Mutex};
+
+use arboard::Clipboard;
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Kept open, as on X11 the copied text is gone once the clipboard is closed.
+static SYSTEM_CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// The OSC 52 sequence that has the terminal put the text on the system
+/// clipboard. It also works over SSH, as long as the terminal allows it.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// Puts the text on the clipboard of the terminal the output goes to. Unless
+/// the terminal is known to understand OSC 52, it is also put on the clipboard
+/// of this machine directly, which fails over SSH.
+pub fn copy(out: &mut impl Write, text: &str) -> Result<(), String> {
+    write!(out, "{}", osc52(text))
+        .and_then(|_| out.flush())
+        .map_err(|err| err.to_string())?;
+    let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let supported = osc52_supported(&var);
+    if supported == Some(true) {
+        return Ok(());
+    }
+    match copy_to_system(text) {
+        // The terminal may still have taken it
+        Err(_) if supported.is_none() => Ok(()),
+        Err(err) => Err(format!("Could not copy: {}", err)),
+        Ok(()) => Ok(()),
+    }
+}
+
+fn copy_to_system(text: &str) -> Result<(), arboard::Error> {
+    let mut clipboard = SYSTEM_CLIPBOARD.lock().unwrap();
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new()?);
+    }
+    clipboard.as_mut().unwrap().set_text(text)
+}
+
+/// Whether the terminal puts text sent with OSC 52 on the clipboard, if that is
+/// known. Some terminals only do when allowed to in their settings.
+pub(crate) fn osc52_supported(var: &dyn Fn(&str) -> Option<String>) -> Option<bool> {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    // tmux passes it on with its default `set-clipboard` setting
+    let known = ["kitty", "alacritty", "foot", "wezterm", "ghostty"]
+        .iter()
+        .any(|name| term.contains(name))
+        || ["WezTerm", "ghostty", "vscode", "tmux"].contains(&program.as_str())
+        || var("WT_SESSION").is_some();
+    if known {
+        return Some(true);
+    }
+    // GNOME Terminal and the other VTE based ones never do
+    var("VTE_VERSION").map(|_| false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("# Hi"), "\x1b]52;c;IyBIaQ==\x07");
+        assert_eq!(osc52(""), "\x1b]52;c;\x07");
+    }
+}
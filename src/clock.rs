@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use crate::{cues::format_duration, notifications::Severity};
+
+/// The presenter's clock in the footer: the time since the presentation was
+/// opened, or the time left when the talk has a `duration`. The severity
+/// colors it once time runs low: a fifth of the talk left is a warning, the
+/// last minute and running over an error.
+pub fn footer_clock(elapsed: Duration, duration: Option<Duration>) -> (String, Severity) {
+    let Some(duration) = duration else {
+        return (format_duration(elapsed), Severity::Info);
+    };
+    if elapsed >= duration {
+        return (
+            format!("{} over", format_duration(elapsed - duration)),
+            Severity::Error,
+        );
+    }
+    let remaining = duration - elapsed;
+    let severity = if remaining <= Duration::from_secs(60) {
+        Severity::Error
+    } else if remaining <= duration / 5 {
+        Severity::Warning
+    } else {
+        Severity::Info
+    };
+    // Rounded up, so the clock starts at the full length
+    let remaining = Duration::from_secs(remaining.as_secs_f64().ceil() as u64);
+    (format!("{} left", format_duration(remaining)), severity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footer_clock() {
+        let at = Duration::from_secs;
+        assert_eq!(
+            footer_clock(at(754), None),
+            ("12:34".to_string(), Severity::Info)
+        );
+        let duration = Some(at(1800));
+        assert_eq!(
+            footer_clock(at(600), duration),
+            ("20:00 left".to_string(), Severity::Info)
+        );
+        assert_eq!(footer_clock(at(1500), duration).1, Severity::Warning);
+        assert_eq!(footer_clock(at(1750), duration).1, Severity::Error);
+        assert_eq!(
+            footer_clock(at(1865), duration),
+            ("1:05 over".to_string(), Severity::Error)
+        );
+    }
+}
@@ -1,82 +1,285 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
 use termion::color::Rgb;
 
-pub struct Color {
-    pub text: Rgb,
-    pub teal: Rgb,
-    pub sky: Rgb,
-    pub peach: Rgb,
-    pub red: Rgb,
-    pub green: Rgb,
-}
+use crate::glyphs::{GlyphOverrides, Glyphs};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ThemeColors {
     pub text: Rgb,
     pub primary: Rgb,
     pub secondary: Rgb,
     pub tertiary: Rgb,
     pub accent: Rgb,
+    pub error: Rgb,
+}
+
+/// Colors of the syntax highlighting for the kinds of tokens that don't use
+/// one of the theme colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeColors {
+    pub conditional: Rgb,
+    pub constant: Rgb,
+    pub repeat: Rgb,
+    pub modifier: Rgb,
+    pub delimiter: Rgb,
+    pub bracket: Rgb,
+    pub spell: Rgb,
+    pub string: Rgb,
+    pub keyword_type: Rgb,
+    pub number: Rgb,
+    pub comment: Rgb,
+    pub parameter: Rgb,
+    pub operator: Rgb,
+    pub default: Rgb,
+}
+
+impl Default for CodeColors {
+    fn default() -> Self {
+        CodeColors {
+            conditional: Rgb(247, 118, 142),
+            constant: Rgb(217, 118, 142),
+            repeat: Rgb(117, 118, 142),
+            modifier: Rgb(187, 118, 142),
+            delimiter: Rgb(155, 118, 142),
+            bracket: Rgb(247, 158, 142),
+            spell: Rgb(158, 186, 106),
+            string: Rgb(158, 206, 106),
+            keyword_type: Rgb(28, 206, 106),
+            number: Rgb(247, 118, 142),
+            comment: Rgb(150, 150, 150),
+            parameter: Rgb(224, 175, 104),
+            operator: Rgb(187, 154, 247),
+            default: Rgb(255, 255, 255),
+        }
+    }
 }
 
-pub enum Theme {
-    CatppuccinLatte,
-    CatppuccinMocha,
-    OneDark,
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: ThemeColors,
+    pub code: CodeColors,
+    pub glyphs: Glyphs,
 }
 
+/// The themes that ship with term_deck, in the order `t` cycles through them.
+const BUILTIN_THEMES: [&str; 3] = [
+    include_str!("../themes/catppuccin-latte.toml"),
+    include_str!("../themes/catppuccin-mocha.toml"),
+    include_str!("../themes/one-dark.toml"),
+];
+
 impl Theme {
-    fn get_colors(&self) -> Color {
-        match self {
-            Theme::CatppuccinLatte => Color {
-                text: hex_to_rgb("#4c4f69"),
-                teal: hex_to_rgb("#179299"),
-                sky: hex_to_rgb("#04a5e5"),
-                peach: hex_to_rgb("#fe640b"),
-                red: hex_to_rgb("#d20f39"),
-                green: hex_to_rgb("#40a02b"),
-            },
-            Theme::CatppuccinMocha => Color {
-                text: hex_to_rgb("#cdd6f4"),
-                teal: hex_to_rgb("#94e2d5"),
-                sky: hex_to_rgb("#94e2d5"),
-                peach: hex_to_rgb("#fab387"),
-                red: hex_to_rgb("#f38ba8"),
-                green: hex_to_rgb("#a6e3a1"),
-            },
-            Theme::OneDark => Color {
-                text: hex_to_rgb("#abb2bf"),
-                teal: hex_to_rgb("#56b6c2"),
-                sky: hex_to_rgb("#61afef"),
-                peach: hex_to_rgb("#e5c07b"),
-                red: hex_to_rgb("#e06c75"),
-                green: hex_to_rgb("#98c379"),
+    pub fn builtin() -> Vec<Theme> {
+        BUILTIN_THEMES
+            .iter()
+            .map(|content| Theme::parse(content).unwrap())
+            .collect()
+    }
+
+    pub fn parse(content: &str) -> Result<Theme, String> {
+        let file: ThemeFile = toml::from_str(content).map_err(|err| err.message().to_string())?;
+        Ok(Theme {
+            name: file.name,
+            colors: ThemeColors {
+                text: file.text.0,
+                primary: file.primary.0,
+                secondary: file.secondary.0,
+                tertiary: file.tertiary.0,
+                accent: file.accent.0,
+                error: file.error.0,
             },
-        }
+            code: file.code.apply(&CodeColors::default()),
+            glyphs: file.glyphs.apply(&Glyphs::unicode()),
+        })
     }
 
     pub fn get_theme_colors(&self) -> ThemeColors {
-        let colors = self.get_colors();
-        ThemeColors {
-            text: colors.text,
-            primary: colors.teal,
-            secondary: colors.sky,
-            tertiary: colors.green,
-            accent: colors.peach,
-        }
+        self.colors
     }
 
     pub fn get_name(&self) -> &str {
-        match self {
-            Theme::CatppuccinLatte => "Catppuccin Latte",
-            Theme::CatppuccinMocha => "Catppuccin Mocha",
-            Theme::OneDark => "One Dark",
+        &self.name
+    }
+
+    /// Whether the name refers to this theme. Case, spaces, dashes and
+    /// underscores don't matter, so `OneDark` and `one-dark` both name
+    /// "One Dark".
+    pub fn matches(&self, name: &str) -> bool {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+        normalize(&self.name) == normalize(name)
+    }
+}
+
+/// Reads every `*.toml` theme in the directory, ordered by file name. A
+/// missing directory means there are no user themes.
+pub fn load_themes(dir: &Path) -> Result<Vec<(PathBuf, Theme)>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("Could not read {}: {}", dir.display(), err)),
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| load_theme(&path).map(|theme| (path, theme)))
+        .collect()
+}
+
+pub fn load_theme(path: &Path) -> Result<Theme, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    Theme::parse(&content).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// A theme file, with colors written as `#rrggbb`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    name: String,
+    text: HexColor,
+    primary: HexColor,
+    secondary: HexColor,
+    tertiary: HexColor,
+    accent: HexColor,
+    error: HexColor,
+    #[serde(default)]
+    code: CodeOverrides,
+    #[serde(default)]
+    glyphs: GlyphOverrides,
+}
+
+/// Colors of the syntax highlighting replacing some of the code colors, in the
+/// `[code]` table of a theme or under `code_colors` in the frontmatter.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CodeOverrides {
+    conditional: Option<HexColor>,
+    constant: Option<HexColor>,
+    repeat: Option<HexColor>,
+    modifier: Option<HexColor>,
+    delimiter: Option<HexColor>,
+    bracket: Option<HexColor>,
+    spell: Option<HexColor>,
+    string: Option<HexColor>,
+    keyword_type: Option<HexColor>,
+    number: Option<HexColor>,
+    comment: Option<HexColor>,
+    parameter: Option<HexColor>,
+    operator: Option<HexColor>,
+    default: Option<HexColor>,
+}
+
+impl CodeOverrides {
+    /// The code colors with the overrides applied.
+    pub fn apply(&self, code: &CodeColors) -> CodeColors {
+        let apply = |color: &Option<HexColor>, base: Rgb| color.as_ref().map_or(base, |c| c.0);
+        CodeColors {
+            conditional: apply(&self.conditional, code.conditional),
+            constant: apply(&self.constant, code.constant),
+            repeat: apply(&self.repeat, code.repeat),
+            modifier: apply(&self.modifier, code.modifier),
+            delimiter: apply(&self.delimiter, code.delimiter),
+            bracket: apply(&self.bracket, code.bracket),
+            spell: apply(&self.spell, code.spell),
+            string: apply(&self.string, code.string),
+            keyword_type: apply(&self.keyword_type, code.keyword_type),
+            number: apply(&self.number, code.number),
+            comment: apply(&self.comment, code.comment),
+            parameter: apply(&self.parameter, code.parameter),
+            operator: apply(&self.operator, code.operator),
+            default: apply(&self.default, code.default),
         }
     }
 }
 
-fn hex_to_rgb(hex: &str) -> Rgb {
-    let r = u8::from_str_radix(&hex[1..3], 16).unwrap();
-    let g = u8::from_str_radix(&hex[3..5], 16).unwrap();
-    let b = u8::from_str_radix(&hex[5..7], 16).unwrap();
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "String")]
+struct HexColor(Rgb);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        hex_to_rgb(&value)
+            .map(HexColor)
+            .ok_or(format!("Invalid color '{}', expected #rrggbb", value))
+    }
+}
+
+pub fn hex_to_rgb(hex: &str) -> Option<Rgb> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let r = u8::from_str_radix(hex.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(hex.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(hex.get(4..6)?, 16).ok()?;
+
+    Some(Rgb(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_themes() {
+        let names: Vec<String> = Theme::builtin()
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["Catppuccin Latte", "Catppuccin Mocha", "One Dark"]
+        );
+    }
 
-    Rgb(r, g, b)
+    #[test]
+    fn test_parse_theme_with_code_colors() {
+        let theme = Theme::parse(
+            "name = \"Paper\"\ntext = \"#000000\"\nprimary = \"#112233\"\n\
+             secondary = \"#112233\"\ntertiary = \"#112233\"\naccent = \"#112233\"\n\
+             error = \"#ff0000\"\n[code]\ncomment = \"#808080\"\n",
+        )
+        .unwrap();
+        assert_eq!(theme.colors.primary, Rgb(0x11, 0x22, 0x33));
+        assert_eq!(theme.code.comment, Rgb(128, 128, 128));
+        assert_eq!(theme.code.string, CodeColors::default().string);
+        assert_eq!(theme.glyphs, Glyphs::unicode());
+    }
+
+    #[test]
+    fn test_theme_name_matching() {
+        let one_dark = &Theme::builtin()[2];
+        assert!(one_dark.matches("One Dark"));
+        assert!(one_dark.matches("OneDark"));
+        assert!(one_dark.matches("one-dark"));
+        assert!(!one_dark.matches("One"));
+    }
+
+    #[test]
+    fn test_invalid_theme() {
+        assert!(Theme::parse("name = \"Half\"\ntext = \"#000000\"\n").is_err());
+        assert_eq!(hex_to_rgb("#12345"), None);
+        assert_eq!(hex_to_rgb("#12345g"), None);
+        assert_eq!(hex_to_rgb("123456"), None);
+    }
 }
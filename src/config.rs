@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    colors::{self, Theme},
+    glyphs::GlyphOverrides,
+    keybindings::KeyBindings,
+};
+
+/// The contents of `~/.config/term_deck/config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    /// The bindings the keys are applied on, `vim`, `emacs` or `arrows`
+    key_preset: Option<String>,
+    /// Keys per action name, replacing the default keys of that action
+    keys: BTreeMap<String, Vec<String>>,
+    panes: Panes,
+    stage: StageWidth,
+    glyphs: GlyphOverrides,
+    sounds: Sounds,
+}
+
+/// Where the panes drawn over the slide go, set in the `[panes]` table.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Panes {
+    pub notes: NotesPane,
+    pub clock: ClockPane,
+}
+
+/// The speaker notes shown with `n`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotesPane {
+    pub position: VerticalPosition,
+    /// The most lines of notes shown, a third of the screen by default
+    pub height: Option<u16>,
+}
+
+/// The clock in the footer shown with `c`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockPane {
+    pub position: HorizontalPosition,
+}
+
+/// The sounds played when another slide comes up, set in the `[sounds]`
+/// table. Each is an audio file or a shell command.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sounds {
+    pub slide: Option<String>,
+    /// Played instead of the slide sound for `section` layout slides
+    pub section: Option<String>,
+}
+
+/// The columns kept free at the sides of the slides and the most columns the
+/// content takes up, set in the `[stage]` table or under `options` in the
+/// frontmatter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StageWidth {
+    pub margin_left: Option<u16>,
+    pub margin_right: Option<u16>,
+    pub max_width: Option<u16>,
+}
+
+impl StageWidth {
+    /// These settings, with the ones not set taken from `defaults`.
+    pub fn or(self, defaults: StageWidth) -> StageWidth {
+        StageWidth {
+            margin_left: self.margin_left.or(defaults.margin_left),
+            margin_right: self.margin_right.or(defaults.margin_right),
+            max_width: self.max_width.or(defaults.max_width),
+        }
+    }
+
+    /// The first and the last column of the content on a screen `width`
+    /// columns wide. Content narrowed by the maximum width is centered between
+    /// the margins.
+    pub fn columns(&self, width: u16) -> (u16, u16) {
+        let left = (1 + self.margin_left.unwrap_or(0)).min(width);
+        let right = width
+            .saturating_sub(self.margin_right.unwrap_or(0))
+            .max(left);
+        let available = right + 1 - left;
+        let excess = self
+            .max_width
+            .map_or(0, |max_width| available.saturating_sub(max_width.max(1)));
+        (left + excess / 2, right - (excess - excess / 2))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HorizontalPosition {
+    Left,
+    #[default]
+    Right,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    pub key_bindings: KeyBindings,
+    /// The themes in `~/.config/term_deck/themes` and the files they were
+    /// read from
+    pub themes: Vec<(PathBuf, Theme)>,
+    pub panes: Panes,
+    /// The margins and maximum width of presentations that don't set their own
+    pub stage: StageWidth,
+    /// Glyphs replacing those of every theme
+    pub glyphs: GlyphOverrides,
+    pub sounds: Sounds,
+    /// Problems with the config that don't keep it from being used
+    pub warnings: Vec<String>,
+}
+
+fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("term_deck"))
+}
+
+/// Reads the user configuration and themes. Missing files mean the defaults
+/// are used.
+pub fn load() -> Result<Config, String> {
+    let Some(dir) = config_dir() else {
+        return Ok(Config::default());
+    };
+    let path = dir.join("config.toml");
+    let mut config = match fs::read_to_string(&path) {
+        Ok(content) => parse(&content).map_err(|err| format!("{}: {}", path.display(), err))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Config::default(),
+        Err(err) => return Err(format!("Could not read {}: {}", path.display(), err)),
+    };
+    config.themes = colors::load_themes(&dir.join("themes"))?;
+    Ok(config)
+}
+
+fn parse(content: &str) -> Result<Config, String> {
+    let file: ConfigFile = toml::from_str(content).map_err(|err| err.message().to_string())?;
+    let mut key_bindings = match &file.key_preset {
+        Some(name) => KeyBindings::preset(name).ok_or(format!(
+            "Unknown key preset '{}', expected vim, emacs or arrows",
+            name
+        ))?,
+        None => KeyBindings::default(),
+    };
+    let warnings = key_bindings.apply_overrides(&file.keys)?;
+    if file.panes.notes.height == Some(0) {
+        return Err("The height of the notes pane has to be at least 1".to_string());
+    }
+    Ok(Config {
+        key_bindings,
+        themes: Vec::new(),
+        panes: file.panes,
+        stage: file.stage,
+        glyphs: file.glyphs,
+        sounds: file.sounds,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::Action;
+    use termion::event::Key;
+
+    #[test]
+    fn test_parse_key_overrides() {
+        let config = parse("[keys]\nnext = [\"j\", \"Down\"]\nquit = [\"Ctrl-q\"]\n").unwrap();
+        let mut pending = Vec::new();
+        let bindings = &config.key_bindings;
+        assert_eq!(bindings.feed(&mut pending, Key::Down), Some(Action::Next));
+        assert_eq!(bindings.feed(&mut pending, Key::Char('q')), None);
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Ctrl('q')),
+            Some(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn test_parse_panes() {
+        let config = parse(
+            "[panes.notes]\nposition = \"top\"\nheight = 8\n\n[panes.clock]\nposition = \"left\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.panes.notes.position, VerticalPosition::Top);
+        assert_eq!(config.panes.notes.height, Some(8));
+        assert_eq!(config.panes.clock.position, HorizontalPosition::Left);
+        assert_eq!(parse("").unwrap().panes, Panes::default());
+        assert!(parse("[panes.notes]\nposition = \"left\"\n").is_err());
+        assert!(parse("[panes.notes]\nheight = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_sounds() {
+        let config = parse("[sounds]\nsection = \"gong.wav\"\n").unwrap();
+        assert_eq!(config.sounds.slide, None);
+        assert_eq!(config.sounds.section.as_deref(), Some("gong.wav"));
+        assert!(parse("[sounds]\nquit = \"bye.wav\"\n").is_err());
+    }
+
+    #[test]
+    fn test_stage_width() {
+        let config = parse("[stage]\nmargin_left = 2\nmax_width = 100\n").unwrap();
+        assert_eq!(config.stage.columns(80), (3, 80));
+        assert_eq!(config.stage.columns(200), (52, 151));
+        let margins = StageWidth {
+            margin_right: Some(4),
+            ..StageWidth::default()
+        };
+        assert_eq!(margins.or(config.stage).columns(80), (3, 76));
+        assert_eq!(margins.columns(3), (1, 1));
+        assert!(parse("[stage]\nmargin = 2\n").is_err());
+    }
+
+    #[test]
+    fn test_keys_override_preset() {
+        let config = parse("key_preset = \"emacs\"\n[keys]\nquit = [\"Ctrl-q\"]\n").unwrap();
+        let mut pending = Vec::new();
+        let bindings = &config.key_bindings;
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Ctrl('p')),
+            Some(Action::Previous)
+        );
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Ctrl('q')),
+            Some(Action::Quit)
+        );
+        assert_eq!(bindings.feed(&mut pending, Key::Char('q')), None);
+        assert!(parse("key_preset = \"nano\"\n").is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(parse("[keys]\nnext = \"j\"\n").is_err());
+        assert!(parse("[keys]\nnext = [\"Pagedown\"]\n").is_err());
+        assert!(parse("[colors]\n").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    alerts::{self, Alert},
+    cues::parse_duration,
+};
+
+/// A break slide counting down, declared as `<!-- countdown: 10m "Back at" -->`.
+/// With `bell` or `notify` after the label the terminal bell rings or a
+/// desktop notification is shown when the time is up.
+#[derive(Debug, PartialEq)]
+pub struct Countdown {
+    pub length: Duration,
+    /// Shown above the timer, followed by the time the break ends
+    pub label: Option<String>,
+    pub alerts: Vec<Alert>,
+}
+
+impl Countdown {
+    pub fn parse(directive: &str) -> Option<Countdown> {
+        let directive = directive.trim();
+        let (length, rest) = directive
+            .split_once(char::is_whitespace)
+            .unwrap_or((directive, ""));
+        let length = parse_duration(length)?;
+        let mut rest = rest.trim();
+        let mut label = None;
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (text, after) = quoted.split_once('"')?;
+            label = Some(text.to_string());
+            rest = after.trim();
+        }
+        Some(Countdown {
+            length,
+            label,
+            alerts: alerts::parse_alerts(rest)?,
+        })
+    }
+}
+
+/// The countdown of the slide on screen, started when the slide came up.
+#[derive(Debug, Clone, Copy)]
+pub struct CountdownTimer {
+    pub slide: usize,
+    started: Instant,
+    ends: DateTime<Local>,
+    /// Whether the time was up the last time it was drawn
+    pub finished: bool,
+    /// Whether the alerts for the end of the countdown were triggered
+    pub alerted: bool,
+}
+
+impl CountdownTimer {
+    pub fn start(slide: usize, length: Duration) -> CountdownTimer {
+        CountdownTimer {
+            slide,
+            started: Instant::now(),
+            ends: Local::now() + length,
+            finished: false,
+            alerted: false,
+        }
+    }
+
+    pub fn remaining(&self, length: Duration) -> Duration {
+        length.saturating_sub(self.started.elapsed())
+    }
+
+    /// The local time at which the countdown ends, as `hh:mm`.
+    pub fn end_time(&self) -> String {
+        self.ends.format("%H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_countdown() {
+        assert_eq!(
+            Countdown::parse("10m \"Back at\" bell"),
+            Some(Countdown {
+                length: Duration::from_secs(600),
+                label: Some("Back at".to_string()),
+                alerts: vec![Alert::Bell],
+            })
+        );
+        let countdown = Countdown::parse("90s").unwrap();
+        assert_eq!(countdown.label, None);
+        assert!(countdown.alerts.is_empty());
+        assert_eq!(Countdown::parse("10 minutes"), None);
+        assert_eq!(Countdown::parse("10m \"Back at"), None);
+        assert_eq!(Countdown::parse("10m loud"), None);
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fmt, fs,
+    time::{Duration, Instant},
+};
+
+/// A cue file: the times at which slides should come up, one `m:ss <slide>`
+/// pair per line.
+#[derive(Debug, PartialEq)]
+pub struct Cues {
+    /// Times and slide indices, ordered by time
+    cues: Vec<(Duration, usize)>,
+    /// When the last slide is over, if the talk has a fixed length
+    end: Option<Duration>,
+}
+
+/// Where the presenter is compared to the cues.
+#[derive(Debug, PartialEq)]
+pub enum Schedule {
+    OnTime,
+    Ahead(Duration),
+    Behind(Duration),
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schedule::OnTime => write!(f, "on time"),
+            Schedule::Ahead(by) => write!(f, "{} ahead", format_duration(*by)),
+            Schedule::Behind(by) => write!(f, "{} behind", format_duration(*by)),
+        }
+    }
+}
+
+impl Cues {
+    pub fn load(path: &str) -> Result<Cues, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))?;
+        Cues::parse(&content).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    /// Parses lines like `1:30 4`, meaning slide 4 is due after a minute and
+    /// a half. Blank lines and lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<Cues, String> {
+        let mut cues: Vec<(Duration, usize)> = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                format!(
+                    "line {}: expected '<m:ss> <slide>', got '{}'",
+                    index + 1,
+                    line
+                )
+            };
+            let (time, slide) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let time = parse_timestamp(time).ok_or_else(invalid)?;
+            let slide = slide
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|slide| *slide > 0)
+                .ok_or_else(invalid)?;
+            if cues.last().is_some_and(|(last, _)| *last > time) {
+                return Err(format!("line {}: cues have to be in order", index + 1));
+            }
+            cues.push((time, slide - 1));
+        }
+        if cues.is_empty() {
+            return Err("no cues found".to_string());
+        }
+        Ok(Cues { cues, end: None })
+    }
+
+    /// Gives every slide the same share of the talk, for lightning talks.
+    pub fn evenly(length: Duration, slides: &[usize]) -> Cues {
+        let share = length / slides.len().max(1) as u32;
+        Cues {
+            cues: slides
+                .iter()
+                .enumerate()
+                .map(|(index, slide)| (share * index as u32, *slide))
+                .collect(),
+            end: Some(length),
+        }
+    }
+
+    /// The time left until the next cue, or until the end of the talk.
+    fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        self.cues
+            .iter()
+            .map(|(time, _)| *time)
+            .chain(self.end)
+            .find(|time| *time > elapsed)
+            .map(|time| time - elapsed)
+    }
+
+    /// The number of cues that are due after the elapsed time.
+    fn due(&self, elapsed: Duration) -> usize {
+        self.cues
+            .iter()
+            .take_while(|(time, _)| *time <= elapsed)
+            .count()
+    }
+
+    /// Compares the slide shown to the slides due after the elapsed time. A
+    /// slide is on time from its cue until the cue of a later slide.
+    pub fn schedule(&self, slide: usize, elapsed: Duration) -> Schedule {
+        let start = self
+            .cues
+            .iter()
+            .rev()
+            .find(|(_, cued)| *cued <= slide)
+            .map_or(Duration::ZERO, |(time, _)| *time);
+        let end = self.cues.iter().find(|(_, cued)| *cued > slide);
+        if elapsed < start {
+            Schedule::Ahead(start - elapsed)
+        } else if let Some((end, _)) = end.filter(|(end, _)| elapsed > *end) {
+            Schedule::Behind(elapsed - *end)
+        } else {
+            Schedule::OnTime
+        }
+    }
+}
+
+/// The cues of a running presentation.
+pub struct CueTimer {
+    cues: Cues,
+    started: Instant,
+    /// Go to the slides when their cue is due
+    follow: bool,
+    /// The number of cues that were due the last time they were checked
+    reached: usize,
+}
+
+impl CueTimer {
+    pub fn new(cues: Cues, follow: bool) -> CueTimer {
+        CueTimer {
+            reached: cues.due(Duration::ZERO),
+            cues,
+            started: Instant::now(),
+            follow,
+        }
+    }
+
+    pub fn schedule(&self, slide: usize) -> Schedule {
+        self.cues.schedule(slide, self.started.elapsed())
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.cues.remaining(self.started.elapsed())
+    }
+
+    /// Whether the slide is behind its schedule or the talk went over its
+    /// length.
+    pub fn over_time(&self, slide: usize) -> bool {
+        let elapsed = self.started.elapsed();
+        matches!(self.cues.schedule(slide, elapsed), Schedule::Behind(_))
+            || self.cues.end.is_some_and(|end| elapsed >= end)
+    }
+
+    /// The slide to go to when following the cues and a cue became due since
+    /// the last call. Navigating by hand in between is left alone.
+    pub fn due_slide(&mut self) -> Option<usize> {
+        let due = self.cues.due(self.started.elapsed());
+        if due == self.reached {
+            return None;
+        }
+        self.reached = due;
+        self.follow.then(|| self.cues.cues[due - 1].1)
+    }
+}
+
+/// Parses a duration like `5m`, `90s` or `1m30s`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' if !number.is_empty() => {
+                let factor = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                seconds += number.parse::<u64>().ok()? * factor;
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    (number.is_empty() && seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+/// Parses `m:ss` or `h:mm:ss`.
+fn parse_timestamp(text: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    let parts: Vec<&str> = text.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    for (index, part) in parts.iter().enumerate() {
+        let value: u64 = part.parse().ok()?;
+        if index > 0 && (value >= 60 || part.len() != 2) {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues() -> Cues {
+        Cues::parse("# intro\n0:00 1\n0:30 2\n\n1:15 4\n").unwrap()
+    }
+
+    #[test]
+    fn test_parse_cues() {
+        assert_eq!(
+            cues().cues,
+            vec![
+                (Duration::ZERO, 0),
+                (Duration::from_secs(30), 1),
+                (Duration::from_secs(75), 3)
+            ]
+        );
+        assert_eq!(parse_timestamp("1:02:03"), Some(Duration::from_secs(3723)));
+        assert!(Cues::parse("0:30 2\n0:10 3\n").is_err());
+        assert!(Cues::parse("0:61 2\n").is_err());
+        assert!(Cues::parse("0:30\n").is_err());
+        assert!(Cues::parse("\n").is_err());
+    }
+
+    #[test]
+    fn test_schedule() {
+        let cues = cues();
+        let at = Duration::from_secs;
+        assert_eq!(cues.schedule(0, at(10)), Schedule::OnTime);
+        assert_eq!(cues.schedule(0, at(40)), Schedule::Behind(at(10)));
+        assert_eq!(cues.schedule(2, at(40)), Schedule::OnTime);
+        assert_eq!(cues.schedule(3, at(40)), Schedule::Ahead(at(35)));
+        assert_eq!(cues.schedule(3, at(600)), Schedule::OnTime);
+        assert_eq!(Schedule::Behind(at(65)).to_string(), "1:05 behind");
+        assert_eq!(cues.due(at(30)), 2);
+        assert_eq!(cues.remaining(at(20)), Some(at(10)));
+        assert_eq!(cues.remaining(at(80)), None);
+    }
+
+    #[test]
+    fn test_lightning_cues() {
+        let at = Duration::from_secs;
+        assert_eq!(parse_duration("5m"), Some(at(300)));
+        assert_eq!(parse_duration("1m30s"), Some(at(90)));
+        assert_eq!(parse_duration("90"), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("m"), None);
+
+        let cues = Cues::evenly(at(60), &[0, 1, 3]);
+        assert_eq!(cues.cues, vec![(at(0), 0), (at(20), 1), (at(40), 3)]);
+        assert_eq!(cues.remaining(at(45)), Some(at(15)));
+        assert_eq!(cues.remaining(at(60)), None);
+    }
+}
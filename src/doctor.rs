@@ -0,0 +1,209 @@
+use std::env;
+
+use termion::terminal_size;
+
+use crate::{clipboard, glyphs::Glyphs, images, terminal};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Works,
+    Limited,
+    Unknown,
+}
+
+/// What was found out about a capability of the terminal, and what it means
+/// for the presentation.
+#[derive(Debug, PartialEq)]
+struct Finding {
+    capability: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Finding {
+    fn new(capability: &'static str, status: Status, detail: &str) -> Finding {
+        Finding {
+            capability,
+            status,
+            detail: detail.to_string(),
+        }
+    }
+}
+
+/// Prints what the terminal can do and which features will work in it, to set
+/// up an unfamiliar machine before the talk.
+pub fn run() {
+    let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let name = var("TERM_PROGRAM")
+        .or_else(|| var("TERM"))
+        .unwrap_or_else(|| "unknown".to_string());
+    match terminal_size() {
+        Ok((width, height)) => println!("Terminal: {}, {}x{}", name, width, height),
+        Err(_) => println!("Terminal: {}, not attached to stdout", name),
+    }
+    if var("TMUX").is_some() {
+        println!("Running in tmux, which holds back some escape sequences");
+    }
+    println!();
+    for finding in findings(&var, images::graphics_protocol()) {
+        let status = match finding.status {
+            Status::Works => "ok",
+            Status::Limited => "no",
+            Status::Unknown => "?",
+        };
+        println!("{:<11}{:<4}{}", finding.capability, status, finding.detail);
+    }
+    println!();
+    println!("Glyphs:    {}", glyph_sample(&Glyphs::unicode()));
+    println!("If some of them show as boxes or question marks, present with --ascii or");
+    println!("switch to a Nerd Font.");
+}
+
+fn findings(var: &dyn Fn(&str) -> Option<String>, graphics: Option<&str>) -> Vec<Finding> {
+    let term = var("TERM").unwrap_or_default();
+    let colors = match var("COLORTERM").as_deref() {
+        Some("truecolor" | "24bit") => Finding::new(
+            "colors",
+            Status::Works,
+            "24-bit colors, themes look as designed",
+        ),
+        _ if term.contains("256color") => Finding::new(
+            "colors",
+            Status::Unknown,
+            "256 colors or more, images look best with --palette 256",
+        ),
+        _ => Finding::new(
+            "colors",
+            Status::Limited,
+            "Few colors, theme colors may be off and images need --palette 16",
+        ),
+    };
+    let images = match graphics {
+        Some(protocol) => Finding::new(
+            "images",
+            Status::Works,
+            &format!("{}, images are drawn at full resolution", protocol),
+        ),
+        None => Finding::new(
+            "images",
+            Status::Limited,
+            "No graphics protocol, images are drawn with half blocks",
+        ),
+    };
+    let links = match terminal::hyperlinks_supported(var) {
+        true => Finding::new("links", Status::Works, "Links are clickable"),
+        false => Finding::new(
+            "links",
+            Status::Unknown,
+            "Links may not be clickable, their addresses are shown instead",
+        ),
+    };
+    let clipboard = match clipboard::osc52_supported(var) {
+        Some(true) => Finding::new(
+            "clipboard",
+            Status::Works,
+            "Copied slides and code end up on the system clipboard",
+        ),
+        Some(false) => Finding::new(
+            "clipboard",
+            Status::Limited,
+            "The terminal ignores OSC 52, copying slides and code fails over SSH",
+        ),
+        None => Finding::new(
+            "clipboard",
+            Status::Unknown,
+            "OSC 52 may have to be allowed in the settings to copy over SSH",
+        ),
+    };
+    // The first of these that is set decides the character encoding
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(var)
+        .unwrap_or_default()
+        .to_lowercase();
+    let unicode = match locale.contains("utf-8") || locale.contains("utf8") {
+        true => Finding::new(
+            "unicode",
+            Status::Works,
+            "UTF-8 locale, borders, bullets and emoji can be drawn",
+        ),
+        false => Finding::new(
+            "unicode",
+            Status::Limited,
+            "No UTF-8 locale, present with --ascii",
+        ),
+    };
+    vec![colors, images, links, clipboard, unicode]
+}
+
+/// The glyphs of the Unicode set, to look for ones the font doesn't have.
+fn glyph_sample(glyphs: &Glyphs) -> String {
+    let chars: String = [glyphs.quote_bar, glyphs.progress, glyphs.pointer]
+        .into_iter()
+        .chain(glyphs.quotes)
+        .chain(glyphs.corners)
+        .chain(glyphs.meter)
+        .chain(glyphs.scroll)
+        .chain(glyphs.blocks)
+        .chain(glyphs.icons)
+        .collect();
+    [
+        glyphs.bullets.concat(),
+        glyphs.border.iter().collect(),
+        chars,
+        glyphs.checkboxes.concat(),
+        "🚀✅".to_string(),
+    ]
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(vars: &[(&str, &str)], graphics: Option<&str>) -> Vec<Status> {
+        let var = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        };
+        findings(&var, graphics)
+            .iter()
+            .map(|finding| finding.status)
+            .collect()
+    }
+
+    #[test]
+    fn test_findings() {
+        let kitty = [
+            ("TERM", "xterm-kitty"),
+            ("COLORTERM", "truecolor"),
+            ("LANG", "en_US.UTF-8"),
+        ];
+        assert_eq!(statuses(&kitty, Some("kitty graphics")), [Status::Works; 5]);
+        let gnome = [
+            ("TERM", "xterm-256color"),
+            ("VTE_VERSION", "4800"),
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8"),
+        ];
+        assert_eq!(
+            statuses(&gnome, None),
+            [
+                Status::Unknown,
+                Status::Limited,
+                Status::Unknown,
+                Status::Limited,
+                Status::Limited
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glyph_sample() {
+        assert_eq!(
+            glyph_sample(&Glyphs::ascii()),
+            "*-+ -|+++++++++ |=>\"\"++++#.^v#\",i+*!x [ ][x] 🚀✅"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+/// Replaces `:rocket:` style shortcodes with their emoji. Shortcodes that
+/// aren't known, like the `:30:` of a time, are kept, and code is left alone.
+pub fn replace_shortcodes(text: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut in_code_block = false;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        match in_code_block || line.trim_start().starts_with("```") {
+            true => replaced.push_str(line),
+            false => replace_in_line(line, &mut replaced),
+        }
+    }
+    replaced
+}
+
+fn replace_in_line(line: &str, replaced: &mut String) {
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            // Code spans are kept up to their closing backtick
+            '`' if rest[1..].contains('`') => {
+                let end = rest[1..].find('`').unwrap() + 2;
+                replaced.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            ':' => match shortcode(rest) {
+                Some((emoji, length)) => {
+                    replaced.push_str(emoji);
+                    rest = &rest[length..];
+                }
+                None => {
+                    replaced.push(':');
+                    rest = &rest[1..];
+                }
+            },
+            c => {
+                replaced.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+}
+
+/// The emoji of the shortcode at the start of the text, and the length of the
+/// shortcode with its colons.
+fn shortcode(text: &str) -> Option<(&'static str, usize)> {
+    let (name, _) = text[1..].split_once(':')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'));
+    let emoji = emojis::get_by_shortcode(name).filter(|_| valid)?;
+    Some((emoji.as_str(), name.len() + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_shortcodes() {
+        assert_eq!(
+            replace_shortcodes("# Launch :rocket:\nAt 10:30:00 :nope: :+1:\n"),
+            "# Launch 🚀\nAt 10:30:00 :nope: 👍\n"
+        );
+        assert_eq!(
+            replace_shortcodes("`:tada:` :tada:\n```\n:tada:\n```\n"),
+            "`:tada:` 🎉\n```\n:tada:\n```\n"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Problems that end a running presentation. They are printed once the
+/// terminal has been restored.
+#[derive(Debug)]
+pub enum Error {
+    /// An image referenced by the presentation doesn't exist
+    MissingImage(PathBuf),
+    /// An image couldn't be read or shown
+    Image(PathBuf, String),
+    /// The syntax highlighting query of a language is invalid
+    Query(String, String),
+    /// The terminal couldn't be set up or written to
+    Terminal(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::MissingImage(path) => write!(f, "The image {} does not exist", path.display()),
+            Error::Image(path, message) => {
+                write!(f, "Could not show {}: {}", path.display(), message)
+            }
+            Error::Query(language, message) => write!(
+                f,
+                "Invalid highlighting query for {}: {}",
+                language, message
+            ),
+            Error::Terminal(err) => write!(f, "Terminal error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Terminal(err)
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+/// Something that happened during a talk, written to the `--event-log` file.
+#[derive(Debug, PartialEq)]
+pub enum LoggedEvent<'a> {
+    Start {
+        presentation: &'a str,
+        slides: usize,
+    },
+    /// A slide came up, numbered from 1 like the deck it belongs to
+    Slide {
+        deck: usize,
+        slide: usize,
+        title: Option<&'a str>,
+    },
+    /// The code blocks of a slide were run
+    Exec {
+        slide: usize,
+        language: &'a str,
+    },
+    Quit,
+}
+
+enum Value<'a> {
+    Text(&'a str),
+    Number(usize),
+}
+
+impl LoggedEvent<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            LoggedEvent::Start { .. } => "start",
+            LoggedEvent::Slide { .. } => "slide",
+            LoggedEvent::Exec { .. } => "exec",
+            LoggedEvent::Quit => "quit",
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, Value<'_>)> {
+        match self {
+            LoggedEvent::Start {
+                presentation,
+                slides,
+            } => vec![
+                ("presentation", Value::Text(presentation)),
+                ("slides", Value::Number(*slides)),
+            ],
+            LoggedEvent::Slide { deck, slide, title } => {
+                let mut fields = vec![
+                    ("deck", Value::Number(*deck)),
+                    ("slide", Value::Number(*slide)),
+                ];
+                fields.extend(title.map(|title| ("title", Value::Text(title))));
+                fields
+            }
+            LoggedEvent::Exec { slide, language } => vec![
+                ("slide", Value::Number(*slide)),
+                ("language", Value::Text(language)),
+            ],
+            LoggedEvent::Quit => Vec::new(),
+        }
+    }
+
+    /// The event as a line of JSON, without the line break.
+    fn to_json(&self, time: DateTime<Local>) -> String {
+        let time = time.to_rfc3339_opts(SecondsFormat::Millis, false);
+        let mut json = format!(
+            "{{\"time\":{},\"event\":{}",
+            json_string(&time),
+            json_string(self.name())
+        );
+        for (key, value) in self.fields() {
+            let value = match value {
+                Value::Text(text) => json_string(text),
+                Value::Number(number) => number.to_string(),
+            };
+            json.push_str(&format!(",{}:{}", json_string(key), value));
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A file the events of a talk are appended to, one JSON object per line.
+#[derive(Debug)]
+pub struct EventLog {
+    file: File,
+    /// The deck and slide of the last slide event, so that redrawing the
+    /// same slide isn't logged again
+    shown: Option<(usize, usize)>,
+}
+
+impl EventLog {
+    pub fn open(path: &str) -> io::Result<EventLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(EventLog { file, shown: None })
+    }
+
+    pub fn record(&mut self, event: LoggedEvent) -> io::Result<()> {
+        if let LoggedEvent::Slide { deck, slide, .. } = event {
+            if self.shown == Some((deck, slide)) {
+                return Ok(());
+            }
+            self.shown = Some((deck, slide));
+        }
+        writeln!(self.file, "{}", event.to_json(Local::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let time = Local.with_ymd_and_hms(2024, 5, 17, 9, 30, 0).unwrap();
+        let json = LoggedEvent::Slide {
+            deck: 1,
+            slide: 3,
+            title: Some("Say \"hi\"\\"),
+        }
+        .to_json(time);
+        assert!(json.starts_with("{\"time\":\"2024-05-17T09:30:00.000"));
+        assert!(json.ends_with(
+            "\"event\":\"slide\",\"deck\":1,\"slide\":3,\"title\":\"Say \\\"hi\\\"\\\\\"}"
+        ));
+        assert!(LoggedEvent::Quit
+            .to_json(time)
+            .ends_with(",\"event\":\"quit\"}"));
+        assert_eq!(json_string("a\u{1}\n"), "\"a\\u0001\\n\"");
+    }
+}
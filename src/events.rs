@@ -0,0 +1,161 @@
+use std::{
+    fs::File,
+    io::{stdin, Read},
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use termion::{event::Key, input::TermRead};
+use tokio::{
+    io::unix::AsyncFd,
+    signal::unix::{signal, SignalKind},
+    sync::{mpsc::UnboundedSender, watch},
+    time::{self, MissedTickBehavior},
+};
+
+/// Everything the presentation reacts to, delivered to the event loop in the
+/// order it happened.
+pub enum Event {
+    Key(Key),
+    PresentationChanged,
+    NotificationExpired,
+    Resized,
+    /// A second passed, for clocks and schedules
+    Tick,
+    /// Someone in the audience voted in a poll
+    PollVoted,
+    /// Someone in the audience asked a question
+    QuestionAsked,
+}
+
+/// Forwards key presses from stdin until the receiving side is gone. Stdin is
+/// only read once it has input, so that reading can be paused while another
+/// program, like an editor, uses the terminal.
+pub fn spawn_key_reader(sender: UnboundedSender<Event>) -> KeyReader {
+    let (paused, mut receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        // A handle of its own, without the buffer of `Stdin` that would keep
+        // input the readiness of the file doesn't tell about
+        let Ok(stdin) = stdin()
+            .as_fd()
+            .try_clone_to_owned()
+            .map(File::from)
+            .and_then(AsyncFd::new)
+        else {
+            return;
+        };
+        // As much as the terminal holds, so that a single read takes all of it
+        let mut buffer = [0; 4096];
+        loop {
+            while *receiver.borrow_and_update() {
+                if receiver.changed().await.is_err() {
+                    return;
+                }
+            }
+            let mut ready = tokio::select! {
+                ready = stdin.readable() => match ready {
+                    Ok(ready) => ready,
+                    Err(_) => return,
+                },
+                _ = receiver.changed() => continue,
+            };
+            let count = match ready.try_io(|stdin| stdin.get_ref().read(&mut buffer)) {
+                Ok(Ok(0) | Err(_)) => return,
+                Ok(Ok(count)) => count,
+                Err(_would_block) => continue,
+            };
+            // Stdin blocks, so it must not be read again before it has new
+            // input
+            ready.clear_ready();
+            for key in buffer[..count].keys().flatten() {
+                if sender.send(Event::Key(key)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    KeyReader { paused }
+}
+
+/// Pauses and resumes reading the keys.
+pub struct KeyReader {
+    paused: watch::Sender<bool>,
+}
+
+impl KeyReader {
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+}
+
+/// Sends a `NotificationExpired` event once a notification has been shown for
+/// the given duration, so that the corner can be redrawn without it.
+pub fn spawn_notification_timer(sender: UnboundedSender<Event>, duration: Duration) {
+    tokio::spawn(async move {
+        time::sleep(duration).await;
+        let _ = sender.send(Event::NotificationExpired);
+    });
+}
+
+/// Sends a `Tick` event every second until the receiving side is gone.
+pub fn spawn_ticker(sender: UnboundedSender<Event>) {
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(1));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes right away
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if sender.send(Event::Tick).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Sends a `Resized` event whenever the terminal reports a new size, so that
+/// the slide is laid out for the new size right away.
+pub fn spawn_resize_watcher(sender: UnboundedSender<Event>) {
+    tokio::spawn(async move {
+        let Ok(mut resizes) = signal(SignalKind::window_change()) else {
+            return;
+        };
+        while resizes.recv().await.is_some() {
+            if sender.send(Event::Resized).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Watches the presentation file for changes. The parent directory is watched
+/// because many editors save by replacing the file. The returned watcher has to
+/// be kept alive for as long as events should be delivered.
+pub fn watch_presentation(
+    presentation_file: &str,
+    sender: UnboundedSender<Event>,
+) -> notify::Result<RecommendedWatcher> {
+    let path = Path::new(presentation_file);
+    let file_name = PathBuf::from(path.file_name().unwrap_or_default());
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+            if changed && event.paths.iter().any(|path| path.ends_with(&file_name)) {
+                let _ = sender.send(Event::PresentationChanged);
+            }
+        }
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Runs `+exec` code blocks and remembers their output, both for the current
+/// session and on disk so slow demos don't run again on the next start.
+pub struct ExecCache {
+    directory: Option<PathBuf>,
+    refresh: bool,
+    outputs: HashMap<u64, String>,
+}
+
+impl ExecCache {
+    pub fn new(refresh: bool) -> ExecCache {
+        ExecCache {
+            directory: cache_directory(),
+            refresh,
+            outputs: HashMap::new(),
+        }
+    }
+
+    pub fn output(&self, language: &str, content: &str) -> Option<&str> {
+        self.outputs
+            .get(&snippet_hash(language, content))
+            .map(|output| output.as_str())
+    }
+
+    /// Returns the output of the snippet, running it only if neither this
+    /// session nor the disk cache (unless refreshing) has a result for it.
+    pub fn execute(&mut self, language: &str, content: &str) -> &str {
+        let hash = snippet_hash(language, content);
+        if !self.outputs.contains_key(&hash) {
+            let output = match self.read_cached(hash) {
+                Some(output) => output,
+                None => {
+                    let output = run_snippet(language, content);
+                    self.write_cached(hash, &output);
+                    output
+                }
+            };
+            self.outputs.insert(hash, output);
+        }
+        &self.outputs[&hash]
+    }
+
+    /// Runs the snippet even if its output is cached and caches the new
+    /// output. Returns it with the output cached before, if there was one.
+    pub fn rerun(&mut self, language: &str, content: &str) -> (&str, Option<String>) {
+        let hash = snippet_hash(language, content);
+        let cached = self.read_cached(hash);
+        let output = run_snippet(language, content);
+        self.write_cached(hash, &output);
+        self.outputs.insert(hash, output);
+        (&self.outputs[&hash], cached)
+    }
+
+    fn read_cached(&self, hash: u64) -> Option<String> {
+        if self.refresh {
+            return None;
+        }
+        let directory = self.directory.as_ref()?;
+        fs::read_to_string(directory.join(format!("{:016x}", hash))).ok()
+    }
+
+    fn write_cached(&self, hash: u64, output: &str) {
+        if let Some(directory) = &self.directory {
+            // A failing cache write only means the snippet runs again next time.
+            let _ = fs::create_dir_all(directory)
+                .and_then(|_| fs::write(directory.join(format!("{:016x}", hash)), output));
+        }
+    }
+}
+
+fn cache_directory() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("term_deck").join("exec"))
+}
+
+/// FNV-1a hash of language and content, stable across builds so it can be
+/// used as a cache key on disk.
+pub fn snippet_hash(language: &str, content: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in language.bytes().chain([0]).chain(content.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn interpreter(language: &str) -> Option<(&'static str, &'static str)> {
+    match language {
+        "bash" => Some(("bash", "-c")),
+        "sh" => Some(("sh", "-c")),
+        "zsh" => Some(("zsh", "-c")),
+        "python" => Some(("python3", "-c")),
+        "ruby" => Some(("ruby", "-e")),
+        "javascript" => Some(("node", "-e")),
+        _ => None,
+    }
+}
+
+fn run_snippet(language: &str, content: &str) -> String {
+    let Some((program, flag)) = interpreter(language) else {
+        return format!("Don't know how to run {} snippets", language);
+    };
+    match Command::new(program).arg(flag).arg(content).output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            text
+        }
+        Err(err) => format!("Failed to run {}: {}", program, err),
+    }
+}
+
+/// Replaces every `<!-- exec_include: <command> -->` line with the output of
+/// the command, run by `sh` in the directory of the presentation. Commands
+/// that fail are left out and reported in the returned errors.
+pub fn expand_includes(content: &str, directory: &Path) -> (String, Vec<String>) {
+    let mut expanded = String::new();
+    let mut errors = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let command = line
+            .trim()
+            .strip_prefix("<!--")
+            .and_then(|line| line.strip_suffix("-->"))
+            .and_then(|directive| directive.trim().strip_prefix("exec_include:"));
+        let Some(command) = command.map(str::trim) else {
+            expanded.push_str(line);
+            continue;
+        };
+        match run_include(command, directory) {
+            Ok(output) => {
+                expanded.push_str(&output);
+                if !output.is_empty() && !output.ends_with('\n') {
+                    expanded.push('\n');
+                }
+            }
+            Err(err) => errors.push(format!("exec_include '{}': {}", command, err)),
+        }
+    }
+    (expanded, errors)
+}
+
+fn run_include(command: &str, directory: &Path) -> Result<String, String> {
+    // A presentation in the working directory has an empty parent
+    let directory = if directory.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        directory
+    };
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(directory)
+        .output()
+        .map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().next() {
+            Some(line) => line.to_string(),
+            None => output.status.to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_hash_depends_on_language_and_content() {
+        assert_eq!(snippet_hash("bash", "ls"), snippet_hash("bash", "ls"));
+        assert_ne!(snippet_hash("bash", "ls"), snippet_hash("sh", "ls"));
+        assert_ne!(snippet_hash("bash", "ls"), snippet_hash("bash", "ls -l"));
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        assert_eq!(
+            run_snippet("cobol", "DISPLAY 'HI'"),
+            "Don't know how to run cobol snippets"
+        );
+    }
+
+    #[test]
+    fn test_expand_includes() {
+        let content = "# Agenda\n<!-- exec_include: printf '* one\\n* two' -->\ntext\n";
+        let (expanded, errors) = expand_includes(content, Path::new(""));
+        assert_eq!(expanded, "# Agenda\n* one\n* two\ntext\n");
+        assert!(errors.is_empty());
+
+        let (expanded, errors) =
+            expand_includes("<!-- exec_include: exit 3 -->\nrest\n", Path::new("/"));
+        assert_eq!(expanded, "rest\n");
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+/// The content of a slide with its footnote definitions taken out and the
+/// `[^label]` references replaced by superscript numbers.
+#[derive(Debug, PartialEq)]
+pub struct Footnoted {
+    pub lines: Vec<String>,
+    /// The text of the footnotes, in the order of their numbers
+    pub notes: Vec<String>,
+}
+
+/// Numbers the footnotes of a slide in the order they are first referenced.
+/// Footnotes that are never referenced come last, references to undefined
+/// footnotes and anything in code blocks are left alone.
+pub fn extract_footnotes(lines: &[&str]) -> Footnoted {
+    let mut definitions: Vec<(&str, &str)> = Vec::new();
+    let mut content = Vec::new();
+    let mut in_code_block = false;
+    for line in lines {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        match parse_definition(line).filter(|_| !in_code_block) {
+            Some(definition) => definitions.push(definition),
+            None => content.push((*line, in_code_block)),
+        }
+    }
+    let mut order = Vec::new();
+    let lines = content
+        .into_iter()
+        .map(|(line, in_code_block)| match in_code_block {
+            true => line.to_string(),
+            false => replace_references(line, &definitions, &mut order),
+        })
+        .collect();
+    for (label, _) in &definitions {
+        if !order.contains(label) {
+            order.push(label);
+        }
+    }
+    let notes = order
+        .iter()
+        .filter_map(|label| {
+            definitions
+                .iter()
+                .find(|(defined, _)| defined == label)
+                .map(|(_, text)| text.to_string())
+        })
+        .collect();
+    Footnoted { lines, notes }
+}
+
+/// Parses a `[^label]: text` line.
+fn parse_definition(line: &str) -> Option<(&str, &str)> {
+    let (label, text) = line.strip_prefix("[^")?.split_once("]:")?;
+    (!label.is_empty() && !label.contains(char::is_whitespace)).then_some((label, text.trim()))
+}
+
+fn replace_references<'a>(
+    line: &str,
+    definitions: &[(&'a str, &str)],
+    order: &mut Vec<&'a str>,
+) -> String {
+    let mut replaced = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("[^") {
+        replaced.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let definition = after
+            .split_once(']')
+            .and_then(|(label, _)| definitions.iter().find(|(defined, _)| *defined == label));
+        match definition {
+            Some((label, _)) => {
+                let number = match order.iter().position(|known| known == label) {
+                    Some(index) => index + 1,
+                    None => {
+                        order.push(label);
+                        order.len()
+                    }
+                };
+                replaced.push_str(&superscript(number));
+                rest = &after[label.len() + 1..];
+            }
+            None => {
+                replaced.push_str("[^");
+                rest = after;
+            }
+        }
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+/// The number in superscript digits, like `¹²`.
+pub fn superscript(number: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    number
+        .to_string()
+        .chars()
+        .filter_map(|digit| digit.to_digit(10))
+        .map(|digit| DIGITS[digit as usize])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_footnotes() {
+        let lines = [
+            "Rust[^rust] is fast[^speed], really[^speed].",
+            "[^speed]: Measured on my laptop.",
+            "Unknown[^none] stays",
+            "```",
+            "let x = a[^speed];",
+            "```",
+            "[^rust]: https://rust-lang.org",
+            "[^unused]: Never referenced",
+        ];
+        assert_eq!(
+            extract_footnotes(&lines),
+            Footnoted {
+                lines: vec![
+                    "Rust¹ is fast², really².".to_string(),
+                    "Unknown[^none] stays".to_string(),
+                    "```".to_string(),
+                    "let x = a[^speed];".to_string(),
+                    "```".to_string(),
+                ],
+                notes: vec![
+                    "https://rust-lang.org".to_string(),
+                    "Measured on my laptop.".to_string(),
+                    "Never referenced".to_string(),
+                ],
+            }
+        );
+        assert_eq!(superscript(12), "¹²");
+    }
+}
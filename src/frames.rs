@@ -0,0 +1,75 @@
+use std::{cell::RefCell, collections::HashMap};
+
+/// What a slide was drawn for: the slide, its theme, the size of the stage and
+/// the state of the slide that changes what is shown of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FrameKey {
+    pub slide: usize,
+    pub theme: String,
+    pub size: (u16, u16),
+    pub revealed_steps: usize,
+    pub scrolled: usize,
+    pub search: Option<String>,
+    pub show_notes: bool,
+}
+
+/// The output of drawing a slide and whether its content ran past the bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedFrame {
+    pub output: Vec<u8>,
+    pub overflowing: bool,
+}
+
+/// The slides drawn so far, to show them again without laying them out and
+/// highlighting their code. It is cleared whenever what the slides show
+/// changes, like when the presentation is reloaded or the terminal resized.
+#[derive(Debug, Default)]
+pub struct FrameCache {
+    frames: RefCell<HashMap<FrameKey, CachedFrame>>,
+}
+
+impl FrameCache {
+    pub fn get(&self, key: &FrameKey) -> Option<CachedFrame> {
+        self.frames.borrow().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: FrameKey, frame: CachedFrame) {
+        self.frames.borrow_mut().insert(key, frame);
+    }
+
+    pub fn clear(&self) {
+        self.frames.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_cache() {
+        let key = FrameKey {
+            slide: 2,
+            theme: "dark".to_string(),
+            size: (80, 24),
+            revealed_steps: 0,
+            scrolled: 0,
+            search: None,
+            show_notes: false,
+        };
+        let frame = CachedFrame {
+            output: b"\x1b[1;1HTitle".to_vec(),
+            overflowing: false,
+        };
+        let cache = FrameCache::default();
+        cache.insert(key.clone(), frame.clone());
+        assert_eq!(cache.get(&key), Some(frame));
+        let resized = FrameKey {
+            size: (100, 30),
+            ..key.clone()
+        };
+        assert_eq!(cache.get(&resized), None);
+        cache.clear();
+        assert_eq!(cache.get(&key), None);
+    }
+}
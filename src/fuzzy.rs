@@ -0,0 +1,43 @@
+/// Scores how well the query matches the text, ignoring case. All characters
+/// of the query have to appear in the text in order. Higher scores are better
+/// matches: runs of consecutive characters and characters at the start of a
+/// word count extra, and so do matches near the start of the text.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut first: Option<usize> = None;
+    let mut previous: Option<usize> = None;
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        if query_char.is_whitespace() {
+            continue;
+        }
+        let start = previous.map_or(0, |previous| previous + 1);
+        let found = start + text[start..].iter().position(|c| *c == query_char)?;
+        score += 10;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 15;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        first.get_or_insert(found);
+        previous = Some(found);
+    }
+    Some(score - first.unwrap_or(0).min(50) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert!(score("trt", "Traits").is_some());
+        assert_eq!(score("xyz", "Traits"), None);
+        assert_eq!(score("st", "Traits"), None);
+        // Consecutive and word start matches rank higher
+        assert!(score("trait", "Traits") > score("trait", "the rat is tired"));
+        assert!(score("gen", "Generics") > score("gen", "Using generics"));
+        assert_eq!(score("", "Anything"), Some(0));
+    }
+}
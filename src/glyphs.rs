@@ -0,0 +1,237 @@
+use serde::Deserialize;
+
+/// The characters drawn around and in front of the content: list bullets,
+/// borders, bars and markers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyphs {
+    /// The markers of unordered list items, by nesting depth
+    pub bullets: Vec<String>,
+    /// The bar in front of quoted lines
+    pub quote_bar: char,
+    /// The opening and closing quotation marks of quote slides
+    pub quotes: [char; 2],
+    /// The lines of tables and panels: horizontal, vertical, then the corners
+    /// and junctions of the top, middle and bottom border from left to right
+    pub border: [char; 11],
+    /// The corners of boxes: top left, top right, bottom left, bottom right
+    pub corners: [char; 4],
+    /// The progress bar in the footer
+    pub progress: char,
+    /// The filled and the empty part of the bars of poll results
+    pub meter: [char; 2],
+    /// The markers of content hidden above and below the stage
+    pub scroll: [char; 2],
+    /// The marker of the selected entry of a list to pick from
+    pub pointer: char,
+    /// The full, upper half and lower half blocks banner letters are drawn
+    /// with
+    pub blocks: [char; 3],
+    /// The icons in the titles of note, tip, important, warning and caution
+    /// boxes
+    pub icons: [char; 5],
+    /// The open and the checked box of task list items
+    pub checkboxes: [String; 2],
+}
+
+impl Glyphs {
+    pub fn unicode() -> Glyphs {
+        Glyphs {
+            bullets: ["•", "◦", "▪", "▫"].map(String::from).to_vec(),
+            quote_bar: '┃',
+            quotes: ['“', '”'],
+            border: chars("─│┌┬┐├┼┤└┴┘"),
+            corners: chars("╭╮╰╯"),
+            progress: '\u{eacc}',
+            meter: ['█', '░'],
+            scroll: ['▲', '▼'],
+            pointer: '›',
+            blocks: ['█', '▀', '▄'],
+            icons: chars("ℹ✓✱⚠✖"),
+            checkboxes: ["☐", "☑"].map(String::from),
+        }
+    }
+
+    /// Glyphs for fonts and terminals that only have the ASCII characters.
+    pub fn ascii() -> Glyphs {
+        Glyphs {
+            bullets: ["*", "-", "+"].map(String::from).to_vec(),
+            quote_bar: '|',
+            quotes: ['"', '"'],
+            border: chars("-|+++++++++"),
+            corners: chars("++++"),
+            progress: '=',
+            meter: ['#', '.'],
+            scroll: ['^', 'v'],
+            pointer: '>',
+            blocks: ['#', '"', ','],
+            icons: chars("i+*!x"),
+            checkboxes: ["[ ]", "[x]"].map(String::from),
+        }
+    }
+
+    /// The glyph shown in front of an unordered item at the given nesting
+    /// depth.
+    pub fn bullet(&self, depth: usize) -> &str {
+        &self.bullets[depth % self.bullets.len()]
+    }
+
+    /// A border line of the given widths, separated by junctions: `row` 0 is
+    /// the top border, 1 a border between rows and 2 the bottom border.
+    pub fn border_line(&self, row: usize, widths: &[usize]) -> String {
+        let [left, middle, right] = [0, 1, 2].map(|index| self.border[2 + row * 3 + index]);
+        let segments: Vec<String> = widths.iter().map(|width| self.horizontal(*width)).collect();
+        format!("{}{}{}", left, segments.join(&middle.to_string()), right)
+    }
+
+    /// A horizontal line of the given width.
+    pub fn horizontal(&self, width: usize) -> String {
+        self.border[0].to_string().repeat(width)
+    }
+
+    pub fn vertical(&self) -> char {
+        self.border[1]
+    }
+}
+
+fn chars<const N: usize>(text: &str) -> [char; N] {
+    text.chars().collect::<Vec<_>>().try_into().unwrap()
+}
+
+/// A set of glyphs to start from in a `[glyphs]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSet {
+    Unicode,
+    Ascii,
+}
+
+/// The `[glyphs]` table of a theme or of the configuration, replacing some or
+/// all of the glyphs:
+///
+/// ```toml
+/// [glyphs]
+/// set = "ascii"
+/// bullets = ["-", "~"]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GlyphOverrides {
+    set: Option<GlyphSet>,
+    bullets: Option<Bullets>,
+    quote_bar: Option<char>,
+    quotes: Option<Chars<2>>,
+    border: Option<Chars<11>>,
+    corners: Option<Chars<4>>,
+    progress: Option<char>,
+    meter: Option<Chars<2>>,
+    scroll: Option<Chars<2>>,
+    pointer: Option<char>,
+    blocks: Option<Chars<3>>,
+    icons: Option<Chars<5>>,
+    checkboxes: Option<[String; 2]>,
+}
+
+impl GlyphOverrides {
+    /// The glyphs with the overrides applied, starting from the set if one is
+    /// given.
+    pub fn apply(&self, glyphs: &Glyphs) -> Glyphs {
+        let base = match self.set {
+            Some(GlyphSet::Unicode) => Glyphs::unicode(),
+            Some(GlyphSet::Ascii) => Glyphs::ascii(),
+            None => glyphs.clone(),
+        };
+        Glyphs {
+            bullets: self
+                .bullets
+                .clone()
+                .map_or(base.bullets, |bullets| bullets.0),
+            quote_bar: self.quote_bar.unwrap_or(base.quote_bar),
+            quotes: self.quotes.map_or(base.quotes, |quotes| quotes.0),
+            border: self.border.map_or(base.border, |border| border.0),
+            corners: self.corners.map_or(base.corners, |corners| corners.0),
+            progress: self.progress.unwrap_or(base.progress),
+            meter: self.meter.map_or(base.meter, |meter| meter.0),
+            scroll: self.scroll.map_or(base.scroll, |scroll| scroll.0),
+            pointer: self.pointer.unwrap_or(base.pointer),
+            blocks: self.blocks.map_or(base.blocks, |blocks| blocks.0),
+            icons: self.icons.map_or(base.icons, |icons| icons.0),
+            checkboxes: self.checkboxes.clone().unwrap_or(base.checkboxes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+struct Bullets(Vec<String>);
+
+impl TryFrom<Vec<String>> for Bullets {
+    type Error = String;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        match value.is_empty() {
+            true => Err("Expected at least one bullet".to_string()),
+            false => Ok(Bullets(value)),
+        }
+    }
+}
+
+/// A fixed number of glyphs written as a string like `"++++"`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+struct Chars<const N: usize>([char; N]);
+
+impl<const N: usize> TryFrom<String> for Chars<N> {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let chars: Vec<char> = value.chars().collect();
+        chars
+            .try_into()
+            .map(Chars)
+            .map_err(|_| format!("Expected {} characters in '{}'", N, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_border_line() {
+        let glyphs = Glyphs::unicode();
+        assert_eq!(glyphs.border_line(0, &[2, 1]), "┌──┬─┐");
+        assert_eq!(glyphs.border_line(2, &[3]), "└───┘");
+        assert_eq!(Glyphs::ascii().border_line(1, &[1, 1]), "+-+-+");
+        assert_eq!(glyphs.bullet(5), "◦");
+    }
+
+    #[test]
+    fn test_ascii_glyphs() {
+        let glyphs = Glyphs::ascii();
+        let chars: String = [&glyphs.quotes[..], &glyphs.border, &glyphs.corners]
+            .concat()
+            .into_iter()
+            .chain([glyphs.quote_bar, glyphs.progress, glyphs.pointer])
+            .chain(glyphs.meter.into_iter().chain(glyphs.scroll))
+            .chain(glyphs.blocks.into_iter().chain(glyphs.icons))
+            .chain(glyphs.bullets.concat().chars())
+            .chain(glyphs.checkboxes.concat().chars())
+            .collect();
+        assert!(chars.is_ascii());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides: GlyphOverrides =
+            toml::from_str("set = \"ascii\"\nbullets = [\"-\"]\nprogress = \"#\"\n").unwrap();
+        let glyphs = overrides.apply(&Glyphs::unicode());
+        assert_eq!(glyphs.bullets, ["-"]);
+        assert_eq!(glyphs.progress, '#');
+        assert_eq!(glyphs.border, Glyphs::ascii().border);
+        let corners: GlyphOverrides = toml::from_str("corners = \"┌┐└┘\"\n").unwrap();
+        assert_eq!(corners.apply(&Glyphs::unicode()).meter, ['█', '░']);
+        assert!(toml::from_str::<GlyphOverrides>("corners = \"++\"\n").is_err());
+        assert!(toml::from_str::<GlyphOverrides>("bullets = []\n").is_err());
+        assert!(toml::from_str::<GlyphOverrides>("set = \"emoji\"\n").is_err());
+    }
+}
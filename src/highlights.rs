@@ -0,0 +1,62 @@
+/// The lines of a code block to emphasize, given in braces after the language
+/// as in ```` ```rust {3,5-7} ````. The other lines are dimmed.
+///
+/// Groups separated by `|`, as in ```` ```rust {1-3|5} ````, are emphasized one
+/// after the other, moving on to the next one like to the next pause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineSelection {
+    /// Inclusive ranges of line numbers, counting from 1
+    ranges: Vec<(usize, usize)>,
+}
+
+impl LineSelection {
+    pub fn parse(spec: &str) -> Option<LineSelection> {
+        let ranges = spec
+            .split(',')
+            .map(|part| {
+                let part = part.trim();
+                let (start, end) = part.split_once('-').unwrap_or((part, part));
+                let range: (usize, usize) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+                (range.0 > 0 && range.0 <= range.1).then_some(range)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(LineSelection { ranges })
+    }
+
+    /// Parses the groups of lines to emphasize one after the other.
+    pub fn parse_steps(spec: &str) -> Option<Vec<LineSelection>> {
+        spec.split('|').map(LineSelection::parse).collect()
+    }
+
+    /// Whether the line, counting from 1, is emphasized.
+    pub fn contains(&self, line: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&line))
+    }
+
+    /// The highest line number selected.
+    pub fn last(&self) -> usize {
+        self.ranges.iter().map(|(_, end)| *end).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_selection() {
+        let selection = LineSelection::parse("3, 5-7").unwrap();
+        let selected: Vec<usize> = (1..=8).filter(|line| selection.contains(*line)).collect();
+        assert_eq!(selected, [3, 5, 6, 7]);
+        assert_eq!(selection.last(), 7);
+        for invalid in ["", "0", "7-5", "3,", "a-b", "1-2-3"] {
+            assert_eq!(LineSelection::parse(invalid), None, "{}", invalid);
+        }
+        let steps = LineSelection::parse_steps("1-3 | 5").unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps[1].contains(5) && !steps[1].contains(3));
+        assert_eq!(LineSelection::parse_steps("1||2"), None);
+    }
+}
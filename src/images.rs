@@ -0,0 +1,465 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use viuer::{get_kitty_support, is_iterm_supported, KittySupport};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub fn from_name(name: &str) -> Option<Dither> {
+        match name {
+            "none" => Some(Dither::None),
+            "ordered" => Some(Dither::Ordered),
+            "floyd-steinberg" | "fs" => Some(Dither::FloydSteinberg),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Palette {
+    Ansi8,
+    Ansi16,
+    Xterm256,
+}
+
+impl Palette {
+    pub fn from_size(size: &str) -> Option<Palette> {
+        match size {
+            "8" => Some(Palette::Ansi8),
+            "16" => Some(Palette::Ansi16),
+            "256" => Some(Palette::Xterm256),
+            _ => None,
+        }
+    }
+
+    fn colors(&self) -> Vec<[u8; 3]> {
+        let ansi = [
+            [0, 0, 0],
+            [205, 0, 0],
+            [0, 205, 0],
+            [205, 205, 0],
+            [0, 0, 238],
+            [205, 0, 205],
+            [0, 205, 205],
+            [229, 229, 229],
+            [127, 127, 127],
+            [255, 0, 0],
+            [0, 255, 0],
+            [255, 255, 0],
+            [92, 92, 255],
+            [255, 0, 255],
+            [0, 255, 255],
+            [255, 255, 255],
+        ];
+        match self {
+            Palette::Ansi8 => ansi[..8].to_vec(),
+            Palette::Ansi16 => ansi.to_vec(),
+            Palette::Xterm256 => {
+                let levels = [0, 95, 135, 175, 215, 255];
+                let mut colors = ansi.to_vec();
+                for r in levels {
+                    for g in levels {
+                        for b in levels {
+                            colors.push([r, g, b]);
+                        }
+                    }
+                }
+                for i in 0..24 {
+                    let gray = 8 + i * 10;
+                    colors.push([gray, gray, gray]);
+                }
+                colors
+            }
+        }
+    }
+
+    /// Rough distance between neighbouring palette entries, used to scale the
+    /// ordered dither threshold.
+    fn spread(&self) -> f32 {
+        match self {
+            Palette::Ansi8 | Palette::Ansi16 => 128.0,
+            Palette::Xterm256 => 40.0,
+        }
+    }
+}
+
+/// An image on a line of its own, like `![alt](path "width=60% align=center")`.
+/// Attributes go into the title, so other Markdown renderers still show the
+/// image.
+#[derive(Debug, PartialEq)]
+pub struct Image<'a> {
+    pub alt: &'a str,
+    pub path: &'a str,
+    pub title: Option<&'a str>,
+}
+
+impl<'a> Image<'a> {
+    pub fn parse(line: &'a str) -> Option<Image<'a>> {
+        let rest = line.strip_prefix("![")?.strip_suffix(')')?;
+        let (alt, target) = rest.split_once("](")?;
+        let (path, title) = match target.split_once(" \"") {
+            Some((path, title)) => (path, Some(title.strip_suffix('"')?)),
+            None => (target, None),
+        };
+        Some(Image {
+            alt,
+            path: path.trim(),
+            title,
+        })
+    }
+
+    /// The `key=value` pairs of the title. Other words are taken as a plain
+    /// title and ignored.
+    pub fn attributes(&self) -> Result<ImageAttributes, String> {
+        let mut attributes = ImageAttributes::default();
+        let pairs = self
+            .title
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|word| word.split_once('='));
+        for (key, value) in pairs {
+            match key {
+                "width" => {
+                    let width = match value.strip_suffix('%') {
+                        Some(percent) => percent
+                            .parse()
+                            .ok()
+                            .filter(|percent| (1..=100).contains(percent))
+                            .map(ImageWidth::Percent),
+                        None => value
+                            .parse()
+                            .ok()
+                            .filter(|columns| *columns > 0)
+                            .map(ImageWidth::Columns),
+                    };
+                    attributes.width = Some(width.ok_or(format!(
+                        "Invalid image width '{}', expected columns or a percentage",
+                        value
+                    ))?);
+                }
+                "align" => {
+                    attributes.align = match value {
+                        "left" => Align::Left,
+                        "center" => Align::Center,
+                        "right" => Align::Right,
+                        _ => {
+                            return Err(format!(
+                                "Invalid image alignment '{}', expected left, center or right",
+                                value
+                            ))
+                        }
+                    };
+                }
+                _ => return Err(format!("Unknown image attribute '{}'", key)),
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// How an image is laid out, given in the title of the image.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImageAttributes {
+    pub width: Option<ImageWidth>,
+    pub align: Align,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageWidth {
+    Columns(u16),
+    /// Of the width of the slide
+    Percent(u16),
+}
+
+impl ImageWidth {
+    pub fn columns(&self, available: u16) -> u16 {
+        match self {
+            ImageWidth::Columns(columns) => (*columns).min(available),
+            ImageWidth::Percent(percent) => {
+                (available as u32 * *percent as u32 / 100).max(1) as u16
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Options applied to images when the terminal has no graphics protocol and
+/// viuer falls back to block characters.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageOptions {
+    pub dither: Dither,
+    pub palette: Palette,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            dither: Dither::None,
+            palette: Palette::Xterm256,
+        }
+    }
+}
+
+impl ImageOptions {
+    pub fn quantization_enabled(&self) -> bool {
+        self.dither != Dither::None
+    }
+}
+
+pub fn uses_block_fallback() -> bool {
+    graphics_protocol().is_none()
+}
+
+/// The protocol images are drawn with at full resolution, if the terminal has
+/// one. Without, they are drawn with half blocks.
+pub fn graphics_protocol() -> Option<&'static str> {
+    match get_kitty_support() {
+        KittySupport::Local | KittySupport::Remote => Some("kitty graphics"),
+        KittySupport::None => is_iterm_supported().then_some("iTerm2 inline images"),
+    }
+}
+
+/// Resizes the image to the block printer's resolution and reduces it to the
+/// configured palette, so the dithering pattern survives the final scaling.
+pub fn quantize(
+    image: &DynamicImage,
+    options: &ImageOptions,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> DynamicImage {
+    let resized = viuer::resize(image, width, height);
+    let mut buffer = resized.to_rgba8();
+    let palette = options.palette.colors();
+    match options.dither {
+        Dither::None => {}
+        Dither::Ordered => ordered_dither(&mut buffer, &palette, options.palette.spread()),
+        Dither::FloydSteinberg => floyd_steinberg_dither(&mut buffer, &palette),
+    }
+    DynamicImage::ImageRgba8(buffer)
+}
+
+/// Crops the image around its center to the aspect ratio of a `columns` x
+/// `rows` cell area, so printing it at that size covers the area completely.
+/// Terminal cells are assumed to be twice as high as they are wide.
+pub fn crop_to_cover(image: &DynamicImage, columns: u16, rows: u16) -> DynamicImage {
+    let target_ratio = columns as f32 / (rows as f32 * 2.0);
+    let (width, height) = (image.width(), image.height());
+    if width as f32 / height as f32 > target_ratio {
+        let cropped_width = (height as f32 * target_ratio) as u32;
+        image.crop_imm((width - cropped_width) / 2, 0, cropped_width, height)
+    } else {
+        let cropped_height = (width as f32 / target_ratio) as u32;
+        image.crop_imm(0, (height - cropped_height) / 2, width, cropped_height)
+    }
+}
+
+/// Darkens the image by scaling every channel with `brightness` (0.0 - 1.0).
+pub fn dim(image: &DynamicImage, brightness: f32) -> DynamicImage {
+    let mut buffer = image.to_rgba8();
+    for pixel in buffer.pixels_mut() {
+        for channel in 0..3 {
+            pixel[channel] = (pixel[channel] as f32 * brightness) as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(buffer)
+}
+
+fn nearest_color(palette: &[[u8; 3]], color: [f32; 3]) -> [u8; 3] {
+    *palette
+        .iter()
+        .min_by(|a, b| {
+            color_distance(a, color)
+                .partial_cmp(&color_distance(b, color))
+                .unwrap()
+        })
+        .unwrap()
+}
+
+fn color_distance(entry: &[u8; 3], color: [f32; 3]) -> f32 {
+    (0..3)
+        .map(|i| {
+            let delta = entry[i] as f32 - color[i];
+            delta * delta
+        })
+        .sum()
+}
+
+fn ordered_dither(buffer: &mut RgbaImage, palette: &[[u8; 3]], spread: f32) {
+    const BAYER: [[f32; 4]; 4] = [
+        [0.0, 8.0, 2.0, 10.0],
+        [12.0, 4.0, 14.0, 6.0],
+        [3.0, 11.0, 1.0, 9.0],
+        [15.0, 7.0, 13.0, 5.0],
+    ];
+    for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+        let threshold = (BAYER[y as usize % 4][x as usize % 4] + 0.5) / 16.0 - 0.5;
+        let offset = threshold * spread;
+        let color = [
+            pixel[0] as f32 + offset,
+            pixel[1] as f32 + offset,
+            pixel[2] as f32 + offset,
+        ];
+        let [r, g, b] = nearest_color(palette, color);
+        *pixel = Rgba([r, g, b, pixel[3]]);
+    }
+}
+
+fn floyd_steinberg_dither(buffer: &mut RgbaImage, palette: &[[u8; 3]]) {
+    let (width, height) = buffer.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    let mut errors = vec![[0.0f32; 3]; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = buffer.get_pixel(x as u32, y as u32);
+            let error = errors[y * width + x];
+            let color = [
+                (pixel[0] as f32 + error[0]).clamp(0.0, 255.0),
+                (pixel[1] as f32 + error[1]).clamp(0.0, 255.0),
+                (pixel[2] as f32 + error[2]).clamp(0.0, 255.0),
+            ];
+            let chosen = nearest_color(palette, color);
+            let alpha = pixel[3];
+            buffer.put_pixel(
+                x as u32,
+                y as u32,
+                Rgba([chosen[0], chosen[1], chosen[2], alpha]),
+            );
+
+            let residual = [
+                color[0] - chosen[0] as f32,
+                color[1] - chosen[1] as f32,
+                color[2] - chosen[2] as f32,
+            ];
+            let mut diffuse = |dx: isize, dy: usize, weight: f32| {
+                let nx = x as isize + dx;
+                let ny = y + dy;
+                if nx < 0 || nx as usize >= width || ny >= height {
+                    return;
+                }
+                let target = &mut errors[ny * width + nx as usize];
+                for channel in 0..3 {
+                    target[channel] += residual[channel] * weight;
+                }
+            };
+            diffuse(1, 0, 7.0 / 16.0);
+            diffuse(-1, 1, 3.0 / 16.0);
+            diffuse(0, 1, 5.0 / 16.0);
+            diffuse(1, 1, 1.0 / 16.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray_image(width: u32, height: u32, gray: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([gray, gray, gray, 255]))
+    }
+
+    fn pixels(buffer: &RgbaImage) -> Vec<[u8; 4]> {
+        buffer.pixels().map(|pixel| pixel.0).collect()
+    }
+
+    #[test]
+    fn test_quantize() {
+        let image = DynamicImage::ImageRgba8(gray_image(4, 4, 250));
+        let options = ImageOptions {
+            dither: Dither::Ordered,
+            palette: Palette::Ansi8,
+        };
+        let quantized = quantize(&image, &options, Some(2), Some(2)).to_rgba8();
+        assert_eq!(quantized.dimensions(), (2, 4));
+        let colors = Palette::Ansi8.colors();
+        for pixel in quantized.pixels() {
+            assert!(colors.contains(&[pixel[0], pixel[1], pixel[2]]));
+        }
+    }
+
+    #[test]
+    fn test_ordered_dither() {
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let mut buffer = gray_image(4, 4, 128);
+        ordered_dither(&mut buffer, &palette, 255.0);
+        let white = pixels(&buffer)
+            .iter()
+            .filter(|pixel| **pixel == [255, 255, 255, 255])
+            .count();
+        assert_eq!(white, 8);
+
+        let mut buffer = gray_image(4, 4, 10);
+        ordered_dither(&mut buffer, &palette, 0.0);
+        assert!(pixels(&buffer).iter().all(|pixel| *pixel == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_floyd_steinberg_dither() {
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let mut buffer = gray_image(2, 1, 128);
+        floyd_steinberg_dither(&mut buffer, &palette);
+        // The first pixel rounds up, its error pushes the second one down.
+        assert_eq!(pixels(&buffer), vec![[255, 255, 255, 255], [0, 0, 0, 255]]);
+    }
+
+    #[test]
+    fn test_crop_to_cover() {
+        let image = DynamicImage::ImageRgba8(gray_image(100, 100, 0));
+        let cropped = crop_to_cover(&image, 40, 10);
+        assert_eq!((cropped.width(), cropped.height()), (100, 50));
+        let cropped = crop_to_cover(&image, 10, 10);
+        assert_eq!((cropped.width(), cropped.height()), (50, 100));
+    }
+
+    #[test]
+    fn test_dim() {
+        let mut buffer = gray_image(1, 1, 200);
+        buffer.put_pixel(0, 0, Rgba([200, 100, 50, 128]));
+        let dimmed = dim(&DynamicImage::ImageRgba8(buffer), 0.5).to_rgba8();
+        assert_eq!(pixels(&dimmed), vec![[100, 50, 25, 128]]);
+    }
+
+    #[test]
+    fn test_parse_image() {
+        let image = Image::parse("![A crab](img/ferris.png \"width=60% align=center\")").unwrap();
+        assert_eq!(image.alt, "A crab");
+        assert_eq!(image.path, "img/ferris.png");
+        assert_eq!(
+            image.attributes(),
+            Ok(ImageAttributes {
+                width: Some(ImageWidth::Percent(60)),
+                align: Align::Center,
+            })
+        );
+        let image = Image::parse("![](ferris.png)").unwrap();
+        assert_eq!(image.title, None);
+        assert_eq!(image.attributes(), Ok(ImageAttributes::default()));
+        // A plain title is no attribute
+        let image = Image::parse("![](ferris.png \"Ferris width=20\")").unwrap();
+        assert_eq!(
+            image.attributes().unwrap().width,
+            Some(ImageWidth::Columns(20))
+        );
+        assert!(Image::parse("![](ferris.png \"width=0\")")
+            .unwrap()
+            .attributes()
+            .is_err());
+        assert!(Image::parse("![](ferris.png \"align=middle\")")
+            .unwrap()
+            .attributes()
+            .is_err());
+        assert_eq!(Image::parse("text ![](ferris.png)"), None);
+        assert_eq!(ImageWidth::Percent(50).columns(81), 40);
+        assert_eq!(ImageWidth::Columns(100).columns(80), 80);
+    }
+}
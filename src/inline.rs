@@ -0,0 +1,367 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::width::{char_width, text_width};
+
+/// Formatting of a piece of inline markdown text.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strikethrough: bool,
+    /// The text of a `[text](url)` link
+    pub link: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+    /// The target of a link the terminal makes clickable
+    pub url: Option<String>,
+}
+
+/// Whether the terminal makes links clickable, see `set_hyperlinks`.
+static HYPERLINKS: AtomicBool = AtomicBool::new(false);
+
+/// Has links written as OSC 8 hyperlinks, instead of showing their URL in
+/// parentheses after the text.
+pub fn set_hyperlinks(enabled: bool) {
+    HYPERLINKS.store(enabled, Ordering::Relaxed);
+}
+
+/// Splits a line into spans of `**bold**`, `*italic*`, `` `code` ``,
+/// `~~strikethrough~~` and `[linked](url)` text. Markers without a closing
+/// counterpart are kept as literal text.
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    parse_spans(text, HYPERLINKS.load(Ordering::Relaxed))
+}
+
+fn parse_spans(text: &str, hyperlinks: bool) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut style = SpanStyle::default();
+    let mut current = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        // Images are drawn on their own, `![alt](path)` isn't a link
+        if let Some((label, url, length)) = parse_link(rest).filter(|_| !current.ends_with('!')) {
+            push_span(&mut spans, &mut current, style);
+            for span in parse_spans(label, false) {
+                let span_style = SpanStyle {
+                    bold: style.bold || span.style.bold,
+                    italic: style.italic || span.style.italic,
+                    code: span.style.code,
+                    strikethrough: style.strikethrough || span.style.strikethrough,
+                    link: true,
+                };
+                spans.push(Span {
+                    text: span.text,
+                    style: span_style,
+                    url: hyperlinks.then(|| url.to_string()),
+                });
+            }
+            if !hyperlinks {
+                current.push_str(&format!(" ({})", url));
+            }
+            rest = &rest[length..];
+            continue;
+        }
+        let marker = if rest.starts_with("**") {
+            Some(("**", style.bold))
+        } else if rest.starts_with("~~") {
+            Some(("~~", style.strikethrough))
+        } else if c == '*' {
+            Some(("*", style.italic))
+        } else if c == '`' {
+            Some(("`", false))
+        } else {
+            None
+        };
+
+        match marker {
+            // Code spans are taken verbatim up to the closing backtick
+            Some(("`", _)) if rest[1..].contains('`') => {
+                let end = rest[1..].find('`').unwrap() + 1;
+                push_span(&mut spans, &mut current, style);
+                current.push_str(&rest[1..end]);
+                push_span(
+                    &mut spans,
+                    &mut current,
+                    SpanStyle {
+                        code: true,
+                        ..style
+                    },
+                );
+                rest = &rest[end + 1..];
+            }
+            Some((marker, active)) if marker != "`" && (active || closes(rest, marker)) => {
+                push_span(&mut spans, &mut current, style);
+                match marker {
+                    "**" => style.bold = !style.bold,
+                    "~~" => style.strikethrough = !style.strikethrough,
+                    _ => style.italic = !style.italic,
+                }
+                rest = &rest[marker.len()..];
+            }
+            _ => {
+                current.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    push_span(&mut spans, &mut current, style);
+    spans
+}
+
+/// Parses a `[label](url)` link at the start of the text, returning the label,
+/// the URL and the length of the link.
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let (label, rest) = text.strip_prefix('[')?.split_once("](")?;
+    let (url, _) = rest.split_once(')')?;
+    let valid = !label.is_empty()
+        && !label.contains(']')
+        && !url.is_empty()
+        && !url.contains(char::is_whitespace);
+    valid.then_some((label, url, label.len() + url.len() + 4))
+}
+
+/// The formatting and link target of a character being wrapped.
+type Format<'a> = (SpanStyle, Option<&'a String>);
+
+/// Wraps the words of the spans into lines at most `width` columns wide,
+/// breaking up words that are wider than a line. Spans that fit are kept as
+/// they are, including their spacing.
+pub fn wrap_spans(spans: Vec<Span>, width: usize) -> Vec<Vec<Span>> {
+    let total: usize = spans.iter().map(|span| text_width(&span.text)).sum();
+    if total <= width {
+        return vec![spans];
+    }
+    let width = width.max(1);
+    let chars: Vec<(char, Format)> = spans
+        .iter()
+        .flat_map(|span| {
+            span.text
+                .chars()
+                .map(|c| (c, (span.style, span.url.as_ref())))
+        })
+        .collect();
+    let mut lines: Vec<Vec<(char, Format)>> = Vec::new();
+    let mut line: Vec<(char, Format)> = Vec::new();
+    let mut line_width = 0;
+    for word in chars.split(|(c, _)| c.is_whitespace()) {
+        let word_width: usize = word.iter().map(|(c, _)| char_width(*c)).sum();
+        if word.is_empty() {
+            continue;
+        }
+        if !line.is_empty() && line_width + 1 + word_width > width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if !line.is_empty() {
+            // The space only continues a style, or a link, that the next
+            // word goes on with
+            let previous = line.last().unwrap().1;
+            let style = match word[0].1 == previous {
+                true => previous,
+                false => (SpanStyle::default(), None),
+            };
+            line.push((' ', style));
+            line_width += 1;
+        }
+        for &(c, style) in word {
+            if line_width + char_width(c) > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            line.push((c, style));
+            line_width += char_width(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut spans: Vec<Span> = Vec::new();
+            for (c, (style, url)) in line {
+                match spans.last_mut() {
+                    Some(span) if span.style == style && span.url.as_ref() == url => {
+                        span.text.push(c)
+                    }
+                    _ => spans.push(Span {
+                        text: c.to_string(),
+                        style,
+                        url: url.cloned(),
+                    }),
+                }
+            }
+            spans
+        })
+        .collect()
+}
+
+fn closes(rest: &str, marker: &str) -> bool {
+    let after = &rest[marker.len()..];
+    match marker {
+        // A single `*` must not be satisfied by the first half of a `**`
+        "*" => after
+            .match_indices('*')
+            .any(|(index, _)| !after[index..].starts_with("**") && index > 0),
+        _ => after.find(marker).is_some_and(|index| index > 0),
+    }
+}
+
+fn push_span(spans: &mut Vec<Span>, current: &mut String, style: SpanStyle) {
+    if !current.is_empty() {
+        spans.push(Span {
+            text: std::mem::take(current),
+            style,
+            url: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(text: &str, style: SpanStyle) -> Span {
+        Span {
+            text: text.to_string(),
+            style,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            parse_inline("just text"),
+            vec![styled("just text", SpanStyle::default())]
+        );
+    }
+
+    #[test]
+    fn test_bold_italic_code_and_strikethrough() {
+        let spans = parse_inline("a **b** *c* `d*` ~~e~~");
+        assert_eq!(
+            spans,
+            vec![
+                styled("a ", SpanStyle::default()),
+                styled(
+                    "b",
+                    SpanStyle {
+                        bold: true,
+                        ..Default::default()
+                    }
+                ),
+                styled(" ", SpanStyle::default()),
+                styled(
+                    "c",
+                    SpanStyle {
+                        italic: true,
+                        ..Default::default()
+                    }
+                ),
+                styled(" ", SpanStyle::default()),
+                styled(
+                    "d*",
+                    SpanStyle {
+                        code: true,
+                        ..Default::default()
+                    }
+                ),
+                styled(" ", SpanStyle::default()),
+                styled(
+                    "e",
+                    SpanStyle {
+                        strikethrough: true,
+                        ..Default::default()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_markers_are_literal() {
+        assert_eq!(
+            parse_inline("2 * 3 = 6 and a ` tick"),
+            vec![styled("2 * 3 = 6 and a ` tick", SpanStyle::default())]
+        );
+    }
+
+    #[test]
+    fn test_wrap_spans() {
+        let text = |line: &Vec<Span>| {
+            line.iter()
+                .map(|span| span.text.as_str())
+                .collect::<String>()
+        };
+        let lines = wrap_spans(parse_inline("keep   spacing"), 20);
+        assert_eq!(
+            lines.iter().map(text).collect::<Vec<_>>(),
+            ["keep   spacing"]
+        );
+        let lines = wrap_spans(parse_inline("a **bold move** here"), 8);
+        assert_eq!(
+            lines.iter().map(text).collect::<Vec<_>>(),
+            ["a bold", "move", "here"]
+        );
+        assert!(lines[1][0].style.bold);
+        assert_eq!(lines[0][0], styled("a ", SpanStyle::default()));
+        let lines = wrap_spans(parse_inline("ideographs 日本語日本語"), 8);
+        assert_eq!(
+            lines.iter().map(text).collect::<Vec<_>>(),
+            ["ideograp", "hs", "日本語日", "本語"]
+        );
+    }
+
+    #[test]
+    fn test_nested_bold_italic() {
+        let spans = parse_inline("***both***");
+        assert_eq!(
+            spans,
+            vec![styled(
+                "both",
+                SpanStyle {
+                    bold: true,
+                    italic: true,
+                    ..Default::default()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let link = SpanStyle {
+            link: true,
+            ..Default::default()
+        };
+        let spans = parse_spans("See [the **docs**](https://docs.rs) now", true);
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[1].text, "the ");
+        assert_eq!(spans[2].url.as_deref(), Some("https://docs.rs"));
+        assert!(spans[2].style.bold && spans[2].style.link);
+        assert_eq!(
+            parse_spans("[rust](https://rust-lang.org)!", false),
+            vec![
+                styled("rust", link),
+                styled(" (https://rust-lang.org)!", SpanStyle::default())
+            ]
+        );
+        assert_eq!(
+            parse_spans("![logo](logo.png) [a] (b) [](c)", true),
+            vec![styled(
+                "![logo](logo.png) [a] (b) [](c)",
+                SpanStyle::default()
+            )]
+        );
+        let wrapped = wrap_spans(parse_spans("a [b c](u) d", true), 3);
+        assert_eq!(wrapped[1][0].url.as_deref(), Some("u"));
+        assert_eq!(wrapped[1][0].text, "c");
+    }
+}
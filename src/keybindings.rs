@@ -0,0 +1,606 @@
+use std::collections::BTreeMap;
+
+use termion::event::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Next,
+    Previous,
+    FirstSlide,
+    LastSlide,
+    ScrollDown,
+    ScrollUp,
+    GoToSlide,
+    Overview,
+    PickSlide,
+    Search,
+    FindSymbol,
+    ToggleNotes,
+    ToggleClock,
+    Execute,
+    CycleTheme,
+    ToggleLog,
+    ToggleQuestions,
+    LargeType,
+    CopySlide,
+    CopyCode,
+    EditSlide,
+    Lock,
+    NextDeck,
+    Help,
+    Quit,
+    /// Jumps to the target of the presentation's shortcut with this index
+    Jump(usize),
+}
+
+/// The actions by the name they are configured with.
+const ACTIONS: [(&str, Action); 25] = [
+    ("next", Action::Next),
+    ("previous", Action::Previous),
+    ("first_slide", Action::FirstSlide),
+    ("last_slide", Action::LastSlide),
+    ("scroll_down", Action::ScrollDown),
+    ("scroll_up", Action::ScrollUp),
+    ("go_to_slide", Action::GoToSlide),
+    ("overview", Action::Overview),
+    ("pick_slide", Action::PickSlide),
+    ("search", Action::Search),
+    ("find_symbol", Action::FindSymbol),
+    ("toggle_notes", Action::ToggleNotes),
+    ("toggle_clock", Action::ToggleClock),
+    ("execute", Action::Execute),
+    ("cycle_theme", Action::CycleTheme),
+    ("toggle_log", Action::ToggleLog),
+    ("toggle_questions", Action::ToggleQuestions),
+    ("large_type", Action::LargeType),
+    ("copy_slide", Action::CopySlide),
+    ("copy_code", Action::CopyCode),
+    ("edit_slide", Action::EditSlide),
+    ("lock", Action::Lock),
+    ("next_deck", Action::NextDeck),
+    ("help", Action::Help),
+    ("quit", Action::Quit),
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .find(|(action_name, _)| *action_name == name)
+            .map(|(_, action)| *action)
+    }
+
+    /// Whether the action runs code, changes the look of the slides, opens the
+    /// presentation in an editor or starts the web server, which
+    /// `--read-only` disallows.
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            Action::Execute | Action::CycleTheme | Action::EditSlide | Action::ToggleQuestions
+        )
+    }
+
+    /// The name of the action in the config, none for the jumps of a
+    /// presentation's shortcuts.
+    pub fn name(&self) -> Option<&'static str> {
+        ACTIONS
+            .iter()
+            .find(|(_, action)| action == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+/// What a shortcut declared in the frontmatter of a presentation does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeckAction {
+    Action(Action),
+    /// Jump to a slide number or to a `#heading`
+    GoTo(String),
+}
+
+impl DeckAction {
+    /// Parses `goto <slide>`, `exec slide` or the name of an action.
+    pub fn parse(command: &str) -> Option<DeckAction> {
+        let command = command.trim();
+        if let Some(target) = command.strip_prefix("goto ") {
+            return Some(DeckAction::GoTo(target.trim().to_string()));
+        }
+        if command == "exec slide" {
+            return Some(DeckAction::Action(Action::Execute));
+        }
+        Action::from_name(command).map(DeckAction::Action)
+    }
+}
+
+/// Maps keys, or sequences of keys like `gg`, to the actions they trigger.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = KeyBindings {
+            bindings: Vec::new(),
+        };
+        for (action, keys) in [
+            (
+                Action::Next,
+                &["l", "Right", "Space", "Enter", "PageDown"][..],
+            ),
+            (Action::Previous, &["h", "Left", "Backspace", "PageUp"]),
+            (Action::FirstSlide, &["gg", "Home"]),
+            (Action::LastSlide, &["G", "End"]),
+            (Action::ScrollDown, &["j", "Down"]),
+            (Action::ScrollUp, &["k", "Up"]),
+            (Action::GoToSlide, &[":"]),
+            (Action::Overview, &["o"]),
+            (Action::PickSlide, &["f"]),
+            (Action::Search, &["/"]),
+            (Action::FindSymbol, &["s"]),
+            (Action::ToggleNotes, &["n"]),
+            (Action::ToggleClock, &["c"]),
+            (Action::Execute, &["e"]),
+            (Action::CycleTheme, &["t"]),
+            (Action::ToggleLog, &["m"]),
+            (Action::ToggleQuestions, &["a"]),
+            (Action::LargeType, &["L"]),
+            (Action::CopySlide, &["Y"]),
+            (Action::CopyCode, &["y"]),
+            (Action::EditSlide, &["E"]),
+            (Action::Lock, &["Ctrl-l"]),
+            (Action::NextDeck, &["Tab"]),
+            (Action::Help, &["?"]),
+            (Action::Quit, &["q"]),
+        ] {
+            for key in keys {
+                let keys = parse_keys(key).unwrap();
+                bindings.bindings.push((keys, action));
+            }
+        }
+        bindings
+    }
+}
+
+impl KeyBindings {
+    /// Replaces the keys of the given actions. Keys taken from another action
+    /// are removed from it. Returns warnings for keys given to more than one of
+    /// the actions, which the last of them by name keeps.
+    pub fn apply_overrides(
+        &mut self,
+        overrides: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        let mut overridden: Vec<(Vec<Key>, &str)> = Vec::new();
+        for (name, keys) in overrides {
+            let action =
+                Action::from_name(name).ok_or(format!("Unknown action '{}' in [keys]", name))?;
+            let keys = keys
+                .iter()
+                .map(|key| parse_keys(key).ok_or(format!("Unknown key '{}' for {}", key, name)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in &keys {
+                if let Some((_, other)) = overridden.iter().find(|(bound, _)| bound == key) {
+                    warnings.push(format!(
+                        "Key {} is configured for both {} and {}, it is used for {}",
+                        key_names(key),
+                        other,
+                        name,
+                        name
+                    ));
+                }
+            }
+            overridden.retain(|(bound, _)| !keys.contains(bound));
+            overridden.extend(keys.iter().map(|key| (key.clone(), name.as_str())));
+            self.replace(action, keys);
+        }
+        Ok(warnings)
+    }
+
+    /// The default bindings with the navigation keys of a preset: `vim` moves
+    /// with h and l and scrolls with j, k, Ctrl-e and Ctrl-y, `emacs` uses
+    /// Ctrl-n, Ctrl-p and friends, and `arrows` leaves only the arrow and page
+    /// keys to move between slides.
+    pub fn preset(name: &str) -> Option<KeyBindings> {
+        let navigation: &[(Action, &[&str])] = match name {
+            "vim" => &[
+                (Action::Next, &["l", "Right", "Space", "Enter", "PageDown"]),
+                (Action::Previous, &["h", "Left", "Backspace", "PageUp"]),
+                (Action::ScrollDown, &["j", "Down", "Ctrl-e"]),
+                (Action::ScrollUp, &["k", "Up", "Ctrl-y"]),
+            ],
+            "emacs" => &[
+                (
+                    Action::Next,
+                    &["Ctrl-n", "Ctrl-f", "Right", "Space", "Enter", "PageDown"],
+                ),
+                (
+                    Action::Previous,
+                    &["Ctrl-p", "Ctrl-b", "Left", "Backspace", "PageUp"],
+                ),
+                (Action::FirstSlide, &["Alt-<", "Home"]),
+                (Action::LastSlide, &["Alt->", "End"]),
+                (Action::ScrollDown, &["Ctrl-v", "Down"]),
+                (Action::ScrollUp, &["Alt-v", "Up"]),
+                (Action::GoToSlide, &["Alt-g"]),
+                (Action::Search, &["Ctrl-s"]),
+            ],
+            "arrows" => &[
+                (
+                    Action::Next,
+                    &["Right", "Down", "Space", "Enter", "PageDown"],
+                ),
+                (Action::Previous, &["Left", "Up", "Backspace", "PageUp"]),
+                (Action::FirstSlide, &["Home"]),
+                (Action::LastSlide, &["End"]),
+                (Action::ScrollDown, &["j"]),
+                (Action::ScrollUp, &["k"]),
+            ],
+            _ => return None,
+        };
+        // The keys of the other actions stay as they are, a preset giving one
+        // of them to its actions would leave it bound twice
+        let mut bindings = KeyBindings::default();
+        bindings
+            .bindings
+            .retain(|(_, action)| navigation.iter().all(|(rebound, _)| rebound != action));
+        for (action, keys) in navigation {
+            for key in *keys {
+                bindings.bindings.push((parse_keys(key).unwrap(), *action));
+            }
+        }
+        Some(bindings)
+    }
+
+    /// Binds the keys to the action instead of its current keys, taking them
+    /// from any other action.
+    fn replace(&mut self, action: Action, keys: Vec<Vec<Key>>) {
+        self.bindings
+            .retain(|(bound, bound_action)| *bound_action != action && !keys.contains(bound));
+        self.bindings
+            .extend(keys.into_iter().map(|keys| (keys, action)));
+    }
+
+    /// Binds the keys to the action, taking them from any other action.
+    pub fn bind(&mut self, keys: &str, action: Action) -> Result<(), String> {
+        let keys = parse_keys(keys).ok_or(format!("Unknown key '{}'", keys))?;
+        self.bindings.retain(|(bound, _)| *bound != keys);
+        self.bindings.push((keys, action));
+        Ok(())
+    }
+
+    /// Adds a key to the keys typed so far and returns the action once they
+    /// form a complete binding. Keys that can't be completed any more are
+    /// dropped, retrying the last one on its own.
+    pub fn feed(&self, pending: &mut Vec<Key>, key: Key) -> Option<Action> {
+        pending.push(key);
+        if let Some(action) = self.action(pending) {
+            pending.clear();
+            return Some(action);
+        }
+        if self.is_prefix(pending) {
+            return None;
+        }
+        let retry = pending.len() > 1;
+        pending.clear();
+        if retry {
+            self.feed(pending, key)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the key starts a binding of one of the actions.
+    pub fn starts(&self, key: Key, actions: &[Action]) -> bool {
+        self.bindings
+            .iter()
+            .any(|(keys, action)| keys[0] == key && actions.contains(action))
+    }
+
+    /// Whether the key is bound on its own or starts a sequence of keys.
+    pub fn is_bound(&self, key: Key) -> bool {
+        self.action(&[key]).is_some() || self.is_prefix(&[key])
+    }
+
+    /// The bound actions with the names of their keys, in the order of the
+    /// config's action names followed by the jumps of the shortcuts.
+    pub fn list(&self) -> Vec<(Action, Vec<String>)> {
+        let mut actions: Vec<Action> = Vec::new();
+        for (_, action) in &self.bindings {
+            if !actions.contains(action) {
+                actions.push(*action);
+            }
+        }
+        actions.sort_by_key(|action| match action {
+            Action::Jump(index) => ACTIONS.len() + index,
+            action => ACTIONS
+                .iter()
+                .position(|(_, named)| named == action)
+                .unwrap_or_default(),
+        });
+        actions
+            .into_iter()
+            .map(|action| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, bound)| *bound == action)
+                    .map(|(keys, _)| key_names(keys))
+                    .collect();
+                (action, keys)
+            })
+            .collect()
+    }
+
+    fn action(&self, keys: &[Key]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == keys)
+            .map(|(_, action)| *action)
+    }
+
+    fn is_prefix(&self, keys: &[Key]) -> bool {
+        self.bindings
+            .iter()
+            .any(|(bound, _)| bound.len() > keys.len() && bound.starts_with(keys))
+    }
+}
+
+/// Parses a key name like `q`, `Space`, `PageDown` or `Ctrl-l`, or a sequence
+/// of characters like `gg`.
+pub fn parse_keys(name: &str) -> Option<Vec<Key>> {
+    if let Some(key) = parse_key(name) {
+        return Some(vec![key]);
+    }
+    // Capitalized names are reserved for named keys, so that a typo like
+    // `Pagedown` isn't taken for a sequence
+    let capitalized = name.starts_with(|c: char| c.is_ascii_uppercase());
+    if name.chars().count() > 1 && !capitalized && !name.contains(char::is_whitespace) {
+        return Some(name.chars().map(Key::Char).collect());
+    }
+    None
+}
+
+/// The name of a key or sequence of keys as it is configured, the reverse of
+/// `parse_keys`.
+pub fn key_names(keys: &[Key]) -> String {
+    keys.iter().map(|key| key_name(*key)).collect()
+}
+
+fn key_name(key: Key) -> String {
+    match key {
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(number) => format!("F{}", number),
+        Key::Esc => "Esc".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PageUp".to_string(),
+        Key::PageDown => "PageDown".to_string(),
+        key => format!("{:?}", key),
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = name.strip_prefix("Ctrl-") {
+        return single(rest).map(|c| Key::Ctrl(c.to_ascii_lowercase()));
+    }
+    if let Some(rest) = name.strip_prefix("Alt-") {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(number) = name.strip_prefix('F').and_then(|rest| rest.parse().ok()) {
+        return (1..=12).contains(&number).then_some(Key::F(number));
+    }
+    match name {
+        "Space" => Some(Key::Char(' ')),
+        "Enter" => Some(Key::Char('\n')),
+        "Tab" => Some(Key::Char('\t')),
+        "Esc" => Some(Key::Esc),
+        "Backspace" => Some(Key::Backspace),
+        "Delete" => Some(Key::Delete),
+        "Left" => Some(Key::Left),
+        "Right" => Some(Key::Right),
+        "Up" => Some(Key::Up),
+        "Down" => Some(Key::Down),
+        "Home" => Some(Key::Home),
+        "End" => Some(Key::End),
+        "PageUp" => Some(Key::PageUp),
+        "PageDown" => Some(Key::PageDown),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let mut pending = Vec::new();
+        let emacs = KeyBindings::preset("emacs").unwrap();
+        assert_eq!(emacs.feed(&mut pending, Key::Ctrl('n')), Some(Action::Next));
+        assert_eq!(emacs.feed(&mut pending, Key::Char('l')), None);
+        assert_eq!(emacs.feed(&mut pending, Key::Char('q')), Some(Action::Quit));
+        let arrows = KeyBindings::preset("arrows").unwrap();
+        assert_eq!(arrows.feed(&mut pending, Key::Char('G')), None);
+        assert_eq!(arrows.feed(&mut pending, Key::End), Some(Action::LastSlide));
+        let vim = KeyBindings::preset("vim").unwrap();
+        assert_eq!(
+            vim.feed(&mut pending, Key::Char('h')),
+            Some(Action::Previous)
+        );
+        assert_eq!(
+            vim.feed(&mut pending, Key::Char('k')),
+            Some(Action::ScrollUp)
+        );
+        assert!(KeyBindings::preset("nano").is_none());
+    }
+
+    #[test]
+    fn test_presets_bind_each_key_once() {
+        for name in ["vim", "emacs", "arrows"] {
+            let preset = KeyBindings::preset(name).unwrap();
+            for (index, (keys, _)) in preset.bindings.iter().enumerate() {
+                assert!(
+                    preset.bindings[index + 1..]
+                        .iter()
+                        .all(|(other, _)| other != keys),
+                    "{} binds {} to two actions",
+                    name,
+                    key_names(keys)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_list() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind("d", Action::Jump(0)).unwrap();
+        bindings.bind("Ctrl-n", Action::Next).unwrap();
+        let list = bindings.list();
+        assert_eq!(
+            list[0],
+            (
+                Action::Next,
+                ["l", "Right", "Space", "Enter", "PageDown", "Ctrl-n"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+        assert_eq!(
+            list[2],
+            (Action::FirstSlide, vec!["gg".into(), "Home".into()])
+        );
+        assert_eq!(list.last().unwrap(), &(Action::Jump(0), vec!["d".into()]));
+        for name in ["q", "Ctrl-l", "F5", "Space", "gg", "Alt-<", "PageDown"] {
+            assert_eq!(key_names(&parse_keys(name).unwrap()), name);
+        }
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(parse_keys("q"), Some(vec![Key::Char('q')]));
+        assert_eq!(parse_keys("Ctrl-L"), Some(vec![Key::Ctrl('l')]));
+        assert_eq!(parse_keys("F5"), Some(vec![Key::F(5)]));
+        assert_eq!(parse_keys("PageDown"), Some(vec![Key::PageDown]));
+        assert_eq!(parse_keys("gg"), Some(vec![Key::Char('g'), Key::Char('g')]));
+        assert_eq!(parse_keys("Ctrl-"), None);
+        assert_eq!(parse_keys("two words"), None);
+        assert_eq!(parse_keys("Pagedown"), None);
+    }
+
+    #[test]
+    fn test_feed_resolves_sequences() {
+        let bindings = KeyBindings::default();
+        let mut pending = Vec::new();
+        assert_eq!(bindings.feed(&mut pending, Key::Char('g')), None);
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('g')),
+            Some(Action::FirstSlide)
+        );
+        assert_eq!(bindings.feed(&mut pending, Key::Char('g')), None);
+        // An incomplete sequence doesn't swallow the next key
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('l')),
+            Some(Action::Next)
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_is_bound() {
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.is_bound(Key::Char('g')) && bindings.is_bound(Key::Char('q')));
+        assert!(!bindings.is_bound(Key::Char('1')));
+        bindings.bind("1", Action::Help).unwrap();
+        assert!(bindings.is_bound(Key::Char('1')));
+    }
+
+    #[test]
+    fn test_deck_actions() {
+        assert_eq!(
+            DeckAction::parse("goto #demo"),
+            Some(DeckAction::GoTo("#demo".to_string()))
+        );
+        assert_eq!(
+            DeckAction::parse("exec slide"),
+            Some(DeckAction::Action(Action::Execute))
+        );
+        assert_eq!(
+            DeckAction::parse("toggle_notes"),
+            Some(DeckAction::Action(Action::ToggleNotes))
+        );
+        assert_eq!(DeckAction::parse("dance"), None);
+
+        let mut bindings = KeyBindings::default();
+        bindings.bind("l", Action::Jump(0)).unwrap();
+        let mut pending = Vec::new();
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('l')),
+            Some(Action::Jump(0))
+        );
+        assert!(bindings.bind("Pagedown", Action::Jump(1)).is_err());
+    }
+
+    #[test]
+    fn test_overrides_take_keys_from_other_actions() {
+        let mut bindings = KeyBindings::default();
+        let overrides = BTreeMap::from([("next".to_string(), vec!["n".to_string()])]);
+        assert!(bindings.apply_overrides(&overrides).unwrap().is_empty());
+        let mut pending = Vec::new();
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('n')),
+            Some(Action::Next)
+        );
+        assert_eq!(bindings.feed(&mut pending, Key::Char('l')), None);
+        assert_eq!(bindings.feed(&mut pending, Key::Right), None);
+
+        let unknown = BTreeMap::from([("fly".to_string(), vec!["f".to_string()])]);
+        assert!(bindings.apply_overrides(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_conflicting_overrides() {
+        let mut bindings = KeyBindings::default();
+        let overrides = BTreeMap::from([
+            ("quit".to_string(), vec!["x".to_string()]),
+            ("next".to_string(), vec!["x".to_string(), "n".to_string()]),
+        ]);
+        let warnings = bindings.apply_overrides(&overrides).unwrap();
+        assert_eq!(
+            warnings,
+            vec!["Key x is configured for both next and quit, it is used for quit"]
+        );
+        let mut pending = Vec::new();
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('x')),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            bindings.feed(&mut pending, Key::Char('n')),
+            Some(Action::Next)
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+/// Maps the labels found on code fences to the language names used for
+/// highlighting, execution and compile checks.
+pub struct LanguageAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl Default for LanguageAliases {
+    fn default() -> Self {
+        let mut aliases = LanguageAliases {
+            aliases: HashMap::new(),
+        };
+        for (alias, language) in [
+            ("rs", "rust"),
+            ("py", "python"),
+            ("python3", "python"),
+            ("js", "javascript"),
+            ("node", "javascript"),
+            ("sh", "bash"),
+            ("shell", "bash"),
+            ("console", "bash"),
+        ] {
+            aliases.insert(alias, language);
+        }
+        aliases
+    }
+}
+
+impl LanguageAliases {
+    pub fn insert(&mut self, alias: &str, language: &str) {
+        self.aliases
+            .insert(alias.to_lowercase(), language.to_lowercase());
+    }
+
+    pub fn normalize(&self, language: &str) -> String {
+        let language = language.to_lowercase();
+        match self.aliases.get(&language) {
+            Some(resolved) => resolved.clone(),
+            None => language,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_aliases_are_case_insensitive() {
+        let aliases = LanguageAliases::default();
+        assert_eq!(aliases.normalize("RS"), "rust");
+        assert_eq!(aliases.normalize("Py"), "python");
+        assert_eq!(aliases.normalize("Java"), "java");
+    }
+
+    #[test]
+    fn test_custom_alias_overrides_builtin() {
+        let mut aliases = LanguageAliases::default();
+        aliases.insert("sh", "zsh");
+        aliases.insert("Kt", "kotlin");
+        assert_eq!(aliases.normalize("sh"), "zsh");
+        assert_eq!(aliases.normalize("kt"), "kotlin");
+    }
+}
@@ -0,0 +1,352 @@
+use serde::Deserialize;
+
+use crate::{images::Image, rendering::extract_directive};
+
+/// A preset arrangement of the content of a slide, picked with
+/// `<!-- layout: name -->`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Everything centered on the stage, without the header
+    Title,
+    /// The content after `<!-- column -->` to the right of the content before
+    TwoColumn,
+    /// The images on the left and the rest of the content on the right
+    ImageLeft,
+    /// The text centered in the middle of the stage, without the header
+    Quote,
+    /// The heading in banner letters centered on the stage, without the header
+    Section,
+}
+
+impl Layout {
+    pub fn parse(name: &str) -> Option<Layout> {
+        match name {
+            "title" => Some(Layout::Title),
+            "two-column" => Some(Layout::TwoColumn),
+            "image-left" => Some(Layout::ImageLeft),
+            "quote" => Some(Layout::Quote),
+            "section" => Some(Layout::Section),
+            _ => None,
+        }
+    }
+
+    /// Whether the content is centered on the stage instead of drawn below the
+    /// header.
+    pub fn is_centered(&self) -> bool {
+        matches!(self, Layout::Title | Layout::Quote | Layout::Section)
+    }
+}
+
+/// Where the content goes between the header and the footer, set with
+/// `<!-- vertical_alignment: center -->`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalAlignment {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+}
+
+impl VerticalAlignment {
+    pub fn from_name(name: &str) -> Option<VerticalAlignment> {
+        match name {
+            "top" => Some(VerticalAlignment::Top),
+            "center" => Some(VerticalAlignment::Center),
+            "bottom" => Some(VerticalAlignment::Bottom),
+            _ => None,
+        }
+    }
+
+    /// The first row of content `rows` high within the rows from `top` to
+    /// `bottom`, starting at `top` if it doesn't fit.
+    pub fn top(&self, rows: u16, (top, bottom): (u16, u16)) -> u16 {
+        let free = (bottom + 1).saturating_sub(top).saturating_sub(rows);
+        match self {
+            VerticalAlignment::Top => top,
+            VerticalAlignment::Center => top + free / 2,
+            VerticalAlignment::Bottom => top + free,
+        }
+    }
+}
+
+/// Splits the lines above the columns, the blank lines and directives up to
+/// and including the first heading, from the rest.
+pub fn split_heading<'a, 'b>(lines: &'b [&'a str]) -> (&'b [&'a str], &'b [&'a str]) {
+    let leading = lines
+        .iter()
+        .take_while(|line| line.trim().is_empty() || line.trim_start().starts_with("<!--"))
+        .count();
+    match lines.get(leading) {
+        Some(line) if line.starts_with('#') => lines.split_at(leading + 1),
+        _ => (&[], lines),
+    }
+}
+
+/// Splits the content below the heading into the lines of the left and of the
+/// right column.
+pub fn split_columns<'a>(layout: Layout, lines: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    match layout {
+        Layout::ImageLeft => lines.iter().partition(|line| Image::parse(line).is_some()),
+        _ => {
+            let mut in_code_block = false;
+            let marker = lines.iter().position(|line| {
+                if line.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                }
+                !in_code_block && line.trim() == "<!-- column -->"
+            });
+            match marker {
+                Some(marker) => (lines[..marker].to_vec(), lines[marker + 1..].to_vec()),
+                None => (lines.to_vec(), Vec::new()),
+            }
+        }
+    }
+}
+
+/// The relative widths of the columns of a slide, set with
+/// `<!-- column_layout: [2, 1] -->`. The content after `<!-- column: 1 -->` goes
+/// into the second column, until the next `column` or `<!-- reset_layout -->`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnLayout(Vec<u16>);
+
+/// The content of a slide with a column layout.
+#[derive(Debug, Default, PartialEq)]
+pub struct Columns<'a> {
+    /// The lines before the first `column` directive, across the whole stage
+    pub above: Vec<&'a str>,
+    pub columns: Vec<Vec<&'a str>>,
+    /// The lines after `reset_layout`, across the whole stage
+    pub below: Vec<&'a str>,
+}
+
+impl ColumnLayout {
+    pub fn parse(text: &str) -> Option<ColumnLayout> {
+        let widths = text.trim().strip_prefix('[')?.strip_suffix(']')?;
+        widths
+            .split(',')
+            .map(|width| width.trim().parse().ok().filter(|width| *width > 0))
+            .collect::<Option<Vec<u16>>>()
+            .map(ColumnLayout)
+    }
+
+    /// The first and last column of each column between `left` and `right`,
+    /// `gap` columns apart.
+    pub fn bounds(&self, (left, right): (u16, u16), gap: u16) -> Vec<(u16, u16)> {
+        let gaps = gap * (self.0.len() as u16 - 1);
+        let available = (right + 1).saturating_sub(left).saturating_sub(gaps) as u32;
+        let total: u32 = self.0.iter().map(|width| *width as u32).sum();
+        self.0
+            .iter()
+            .enumerate()
+            .scan(0, |taken, (index, width)| {
+                let offset = |taken: u32| left + (available * taken / total) as u16;
+                let start = offset(*taken) + gap * index as u16;
+                *taken += *width as u32;
+                let end = offset(*taken) + gap * index as u16;
+                Some((start, end.saturating_sub(1).max(start)))
+            })
+            .collect()
+    }
+
+    /// Splits the lines at the `column` and `reset_layout` directives outside
+    /// of code blocks.
+    pub fn split<'a>(&self, lines: &[&'a str]) -> Result<Columns<'a>, String> {
+        let mut columns = Columns {
+            columns: vec![Vec::new(); self.0.len()],
+            ..Columns::default()
+        };
+        let mut current = None;
+        let mut reset = false;
+        let mut in_code_block = false;
+        for line in lines {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block {
+                if let Some(column) = extract_directive(line, "column") {
+                    let index = column
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|index| *index < self.0.len());
+                    current = Some(index.ok_or_else(|| {
+                        format!(
+                            "Invalid column '{}', expected a number below {}",
+                            column,
+                            self.0.len()
+                        )
+                    })?);
+                    continue;
+                }
+                if line.trim() == "<!-- reset_layout -->" {
+                    reset = true;
+                    continue;
+                }
+            }
+            match current {
+                _ if reset => columns.below.push(line),
+                Some(index) => columns.columns[index].push(line),
+                None => columns.above.push(line),
+            }
+        }
+        Ok(columns)
+    }
+}
+
+/// A slide of nothing but a blockquote and who said it.
+#[derive(Debug, PartialEq)]
+pub struct Quote<'a> {
+    pub paragraphs: Vec<String>,
+    pub attribution: Option<&'a str>,
+}
+
+/// Parses a slide of `>` lines, with the attribution in a last line starting
+/// with a dash like `— Grace Hopper`, inside the quote or below it. Blank lines
+/// and comments are ignored.
+pub fn parse_quote<'a>(lines: &[&'a str]) -> Option<Quote<'a>> {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut attribution = None;
+    let mut flush = |current: &mut String| {
+        if !current.is_empty() {
+            paragraphs.push(std::mem::take(current));
+        }
+    };
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("<!--") {
+            flush(&mut current);
+            continue;
+        }
+        if attribution.is_some() {
+            return None;
+        }
+        let quoted = line.strip_prefix('>').map(str::trim);
+        attribution = quoted.or(Some(line)).and_then(attribution_of);
+        match quoted {
+            _ if attribution.is_some() => {}
+            Some("") => flush(&mut current),
+            Some(text) => {
+                if !current.is_empty() {
+                    current.push(' ');
+                }
+                current.push_str(text);
+            }
+            None => return None,
+        }
+    }
+    flush(&mut current);
+    (!paragraphs.is_empty()).then_some(Quote {
+        paragraphs,
+        attribution,
+    })
+}
+
+/// The name after the dash of an attribution line.
+fn attribution_of(line: &str) -> Option<&str> {
+    ["—", "―", "–", "--"]
+        .iter()
+        .find_map(|dash| line.strip_prefix(dash))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_two_columns() {
+        let lines = [
+            "<!-- layout: two-column -->",
+            "# Before and after",
+            "```markdown",
+            "<!-- column -->",
+            "```",
+            "<!-- column -->",
+            "After",
+        ];
+        let (heading, body) = split_heading(&lines);
+        assert_eq!(heading.len(), 2);
+        let (left, right) = split_columns(Layout::TwoColumn, body);
+        assert_eq!(left, ["```markdown", "<!-- column -->", "```"]);
+        assert_eq!(right, ["After"]);
+    }
+
+    #[test]
+    fn test_split_image_left() {
+        let lines = ["Text", "![Logo](logo.png)", "More text"];
+        let (heading, body) = split_heading(&lines);
+        assert!(heading.is_empty());
+        let (left, right) = split_columns(Layout::ImageLeft, body);
+        assert_eq!(left, ["![Logo](logo.png)"]);
+        assert_eq!(right, ["Text", "More text"]);
+    }
+
+    #[test]
+    fn test_column_layout() {
+        let layout = ColumnLayout::parse("[1, 1]").unwrap();
+        assert_eq!(layout.bounds((1, 80), 2), [(1, 39), (42, 80)]);
+        let layout = ColumnLayout::parse("[2,1]").unwrap();
+        assert_eq!(layout.bounds((1, 62), 2), [(1, 40), (43, 62)]);
+        assert_eq!(ColumnLayout::parse("[]"), None);
+        assert_eq!(ColumnLayout::parse("[1, 0]"), None);
+        let lines = [
+            "# Code and words",
+            "<!-- column: 1 -->",
+            "Words",
+            "<!-- column: 0 -->",
+            "```html",
+            "<!-- column: 1 -->",
+            "```",
+            "<!-- reset_layout -->",
+            "Below",
+        ];
+        assert_eq!(
+            layout.split(&lines),
+            Ok(Columns {
+                above: vec!["# Code and words"],
+                columns: vec![vec!["```html", "<!-- column: 1 -->", "```"], vec!["Words"]],
+                below: vec!["Below"],
+            })
+        );
+        assert!(layout.split(&["<!-- column: 2 -->"]).is_err());
+    }
+
+    #[test]
+    fn test_vertical_alignment() {
+        assert_eq!(VerticalAlignment::Top.top(3, (4, 20)), 4);
+        assert_eq!(VerticalAlignment::Center.top(3, (4, 20)), 11);
+        assert_eq!(VerticalAlignment::Bottom.top(3, (4, 20)), 18);
+        assert_eq!(VerticalAlignment::Bottom.top(30, (4, 20)), 4);
+        assert_eq!(VerticalAlignment::from_name("middle"), None);
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let lines = [
+            "<!-- layout: quote -->",
+            "> Simplicity is prerequisite",
+            "> for reliability.",
+            ">",
+            "> Really.",
+            "",
+            "-- Edsger W. Dijkstra",
+        ];
+        assert_eq!(
+            parse_quote(&lines),
+            Some(Quote {
+                paragraphs: vec![
+                    "Simplicity is prerequisite for reliability.".to_string(),
+                    "Really.".to_string()
+                ],
+                attribution: Some("Edsger W. Dijkstra"),
+            })
+        );
+        let inside = parse_quote(&["> Talk is cheap.", "> — Linus Torvalds"]).unwrap();
+        assert_eq!(inside.attribution, Some("Linus Torvalds"));
+        assert_eq!(parse_quote(&["# Quote", "> Talk is cheap."]), None);
+        assert_eq!(parse_quote(&["> Talk is cheap.", "-- Linus", "More"]), None);
+        assert_eq!(parse_quote(&["-- Linus"]), None);
+    }
+}
@@ -0,0 +1,145 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListMarker {
+    Bullet,
+    Ordered,
+}
+
+/// A single `- item`, `* item`, `+ item` or `1. item` line.
+#[derive(Debug, PartialEq)]
+pub struct ListItem<'a> {
+    pub indent: usize,
+    pub marker: ListMarker,
+    /// Whether the box of a task list item like `- [x] item` is checked
+    pub checked: Option<bool>,
+    pub text: &'a str,
+}
+
+pub fn parse_list_item(line: &str) -> Option<ListItem<'_>> {
+    let trimmed = line.trim_start();
+    let indent = indentation(line);
+    if let Some(text) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+    {
+        let (checked, text) = parse_task(text.trim_start());
+        return Some(ListItem {
+            indent,
+            marker: ListMarker::Bullet,
+            checked,
+            text,
+        });
+    }
+
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = &trimmed[digits..];
+    let text = rest
+        .strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))?;
+    let (checked, text) = parse_task(text.trim_start());
+    (digits > 0).then_some(ListItem {
+        indent,
+        marker: ListMarker::Ordered,
+        checked,
+        text,
+    })
+}
+
+/// Splits the `[ ]` or `[x]` of a task list item from its text.
+fn parse_task(text: &str) -> (Option<bool>, &str) {
+    [("[ ]", false), ("[x]", true), ("[X]", true)]
+        .iter()
+        .find_map(|(checkbox, checked)| {
+            let rest = text.strip_prefix(checkbox)?;
+            (rest.is_empty() || rest.starts_with(' ')).then(|| (Some(*checked), rest.trim_start()))
+        })
+        .unwrap_or((None, text))
+}
+
+/// Leading whitespace of a line in columns, counting a tab as four spaces.
+pub fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Tracks the nesting and numbering of the list currently being rendered.
+#[derive(Default)]
+pub struct ListState {
+    /// Indentation, marker and item count of every open nesting level
+    levels: Vec<(usize, ListMarker, usize)>,
+}
+
+impl ListState {
+    /// Registers an item and returns its nesting depth and its number within
+    /// its level, both starting at 0 and 1 respectively.
+    pub fn push_item(&mut self, item: &ListItem) -> (usize, usize) {
+        while self
+            .levels
+            .last()
+            .is_some_and(|(indent, _, _)| *indent > item.indent)
+        {
+            self.levels.pop();
+        }
+        match self.levels.last_mut() {
+            Some((indent, marker, count)) if *indent == item.indent => {
+                // Switching between bullets and numbers starts a new list
+                if *marker == item.marker {
+                    *count += 1;
+                } else {
+                    *marker = item.marker;
+                    *count = 1;
+                }
+            }
+            _ => self.levels.push((item.indent, item.marker, 1)),
+        }
+        (self.levels.len() - 1, self.levels.last().unwrap().2)
+    }
+
+    pub fn reset(&mut self) {
+        self.levels.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_items() {
+        assert_eq!(
+            parse_list_item("  - nested"),
+            Some(ListItem {
+                indent: 2,
+                marker: ListMarker::Bullet,
+                checked: None,
+                text: "nested"
+            })
+        );
+        let task = parse_list_item("- [x] Ship it").unwrap();
+        assert_eq!((task.checked, task.text), (Some(true), "Ship it"));
+        assert_eq!(parse_list_item("1. [ ]").unwrap().checked, Some(false));
+        assert_eq!(parse_list_item("- [link](url)").unwrap().checked, None);
+        assert_eq!(
+            parse_list_item("12. twelfth").map(|item| item.marker),
+            Some(ListMarker::Ordered)
+        );
+        assert_eq!(parse_list_item("*emphasis*"), None);
+        assert_eq!(parse_list_item("2024 was a year"), None);
+        assert_eq!(parse_list_item("---"), None);
+    }
+
+    #[test]
+    fn test_nesting_and_numbering() {
+        let mut state = ListState::default();
+        let mut push = |line| state.push_item(&parse_list_item(line).unwrap());
+        assert_eq!(push("1. first"), (0, 1));
+        assert_eq!(push("   - child"), (1, 1));
+        assert_eq!(push("   - child"), (1, 2));
+        assert_eq!(push("      1. grandchild"), (2, 1));
+        assert_eq!(push("2. second"), (0, 2));
+        assert_eq!(push("  - shallower child"), (1, 1));
+        assert_eq!(push("- bullet list"), (0, 1));
+        assert_eq!(push("1. new ordered list"), (0, 1));
+    }
+}
@@ -1,155 +1,2659 @@
 use std::{
-    fs,
-    io::{stdin, stdout},
+    cell::Cell,
+    collections::BTreeMap,
+    env, fs,
+    io::Stdout,
+    mem,
+    ops::Range,
     path::Path,
     process,
+    time::{Duration, Instant},
 };
 
-use colors::Theme;
+use alerts::SlideAlert;
+use cli::{Args, Command, Options};
+use colors::{CodeColors, CodeOverrides, Theme};
+use config::{Panes, Sounds, StageWidth};
+use countdown::{Countdown, CountdownTimer};
+use cues::{CueTimer, Cues};
+use error::Error;
+use eventlog::{EventLog, LoggedEvent};
+use events::{Event, KeyReader};
+use exec::ExecCache;
+use frames::FrameCache;
+use glyphs::{GlyphOverrides, Glyphs};
+use keybindings::{Action, DeckAction, KeyBindings};
+use layout::{ColumnLayout, Layout, VerticalAlignment};
+use notifications::{Notifications, Severity, NOTIFICATION_DURATION};
+use paragraphs::LineBreaks;
 use regex::Regex;
-use termion::{input::TermRead, raw::IntoRawMode};
+use rehearsal::Rehearsal;
+use serde::{Deserialize, Deserializer};
+use server::Server;
+use symbols::SymbolIndex;
+use tables::Alignment;
+use terminal::TerminalGuard;
+use termion::{color::Rgb, event::Key, raw::RawTerminal};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
+pub mod admonitions;
+pub mod alerts;
+pub mod archive;
+pub mod bigtext;
+pub mod blockquotes;
+pub mod bundle;
+pub mod check;
+pub mod cli;
+pub mod clipboard;
+pub mod clock;
 pub mod colors;
+pub mod config;
+pub mod countdown;
+pub mod cues;
+pub mod doctor;
+pub mod emoji;
+pub mod error;
+pub mod eventlog;
+pub mod events;
+pub mod exec;
+pub mod footnotes;
+pub mod frames;
+pub mod fuzzy;
+pub mod glyphs;
+pub mod highlights;
+pub mod images;
+pub mod inline;
+pub mod keybindings;
+pub mod languages;
+pub mod layout;
+pub mod lists;
+pub mod notifications;
+pub mod paragraphs;
+pub mod poll;
+pub mod rehearsal;
 pub mod rendering;
+pub mod search;
+pub mod server;
+pub mod sources;
+pub mod state;
+pub mod symbols;
+pub mod tables;
+pub mod terminal;
+pub mod width;
 
-#[derive(Debug)]
+/// The frontmatter of a presentation. Keys that term_deck doesn't know are
+/// ignored, so the same file can carry metadata for other tools.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct Metadata {
-    author: Option<String>,
     title: Option<String>,
     subtitle: Option<String>,
+    /// Given as a single name or as a list
+    #[serde(deserialize_with = "one_or_many")]
+    author: Vec<String>,
+    date: Option<String>,
+    event: Option<String>,
+    /// The name of the theme to start with
+    theme: Option<String>,
+    /// The length of the talk like `30m`, counted down by the clock
+    #[serde(deserialize_with = "duration")]
+    duration: Option<Duration>,
+    /// Shortcuts of this presentation, as keys and the action they trigger
+    keys: BTreeMap<String, String>,
+    /// Syntax highlighting colors replacing those of the theme
+    code_colors: CodeOverrides,
+    options: DeckOptions,
+}
+
+impl Metadata {
+    /// Whether the presentation starts with a slide generated from the
+    /// metadata, which replaces the title shown above every slide.
+    pub fn has_title_slide(&self) -> bool {
+        self.options.title_slide && self.title.is_some()
+    }
+}
+
+/// How the presentation is laid out, set under `options` in the frontmatter.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DeckOptions {
+    title_slide: bool,
+    /// Start a new slide at every `#` and `##` heading
+    split_on_headings: bool,
+    /// How slides without an `alignment` directive align their content
+    alignment: Alignment,
+    vertical_alignment: VerticalAlignment,
+    /// The margins and maximum width of the content, instead of the configured
+    /// ones
+    stage: StageWidth,
+    /// Replace `:rocket:` style shortcodes with their emoji
+    emoji: bool,
+    /// Number the lines of code blocks without a `linenos` flag
+    line_numbers: bool,
+    /// Whether the lines of a paragraph are joined
+    line_breaks: LineBreaks,
+}
+
+impl Default for DeckOptions {
+    fn default() -> Self {
+        DeckOptions {
+            title_slide: true,
+            split_on_headings: false,
+            alignment: Alignment::default(),
+            vertical_alignment: VerticalAlignment::default(),
+            stage: StageWidth::default(),
+            emoji: true,
+            line_numbers: false,
+            line_breaks: LineBreaks::default(),
+        }
+    }
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    cues::parse_duration(&value)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid duration '{}'", value)))
+}
+
+pub struct Slide {
+    content: String,
+    notes: Option<String>,
+    style: SlideStyle,
+    /// Whether the slide is marked as the first of the appendix
+    starts_appendix: bool,
+    /// Hidden slides are skipped unless jumped to
+    hidden: bool,
+    /// The text of the first heading
+    title: Option<String>,
+    /// The line of the presentation file the slide starts at, counting from 1
+    line: usize,
+}
+
+/// Colors of a single slide that differ from the rest of the presentation.
+#[derive(Debug, Default, PartialEq)]
+pub struct SlideStyle {
+    /// Name of the theme used instead of the selected one
+    theme: Option<String>,
+    background: Option<Rgb>,
+    /// Directives, image attributes and code highlights that could not be
+    /// understood
+    warnings: Vec<String>,
+}
+
+impl SlideStyle {
+    fn parse(content: &str, aliases: &languages::LanguageAliases) -> SlideStyle {
+        let mut style = SlideStyle {
+            theme: rendering::extract_directive(content, "theme").map(String::from),
+            ..SlideStyle::default()
+        };
+        if let Some(background) = rendering::extract_directive(content, "background") {
+            match colors::hex_to_rgb(background) {
+                Some(color) => style.background = Some(color),
+                None => style.warnings.push(format!(
+                    "Invalid background color '{}', expected #rrggbb",
+                    background
+                )),
+            }
+        }
+        if let Some(layout) = rendering::extract_directive(content, "layout") {
+            if Layout::parse(layout).is_none() {
+                style.warnings.push(format!(
+                    "Unknown layout '{}', expected title, two-column, image-left, quote or section",
+                    layout
+                ));
+            }
+        }
+        if let Some(column_layout) = rendering::extract_directive(content, "column_layout") {
+            let lines: Vec<&str> = content.lines().collect();
+            match ColumnLayout::parse(column_layout) {
+                Some(column_layout) => style.warnings.extend(column_layout.split(&lines).err()),
+                None => style.warnings.push(format!(
+                    "Invalid column layout '{}', expected the widths of the columns like [1, 1]",
+                    column_layout
+                )),
+            }
+        }
+        if let Some(alignment) = rendering::extract_directive(content, "alignment") {
+            if Alignment::from_name(alignment).is_none() {
+                style.warnings.push(format!(
+                    "Unknown alignment '{}', expected left, center or right",
+                    alignment
+                ));
+            }
+        }
+        if let Some(alignment) = rendering::extract_directive(content, "vertical_alignment") {
+            if VerticalAlignment::from_name(alignment).is_none() {
+                style.warnings.push(format!(
+                    "Unknown vertical alignment '{}', expected top, center or bottom",
+                    alignment
+                ));
+            }
+        }
+        if let Some(duration) = rendering::extract_directive(content, "duration") {
+            if cues::parse_duration(duration).is_none() {
+                style.warnings.push(format!(
+                    "Invalid duration '{}', expected e.g. 30s or 1m30s",
+                    duration
+                ));
+            }
+        }
+        style.warnings.extend(
+            content
+                .lines()
+                .filter_map(images::Image::parse)
+                .filter_map(|image| image.attributes().err()),
+        );
+        style.warnings.extend(
+            rendering::code_blocks(content, aliases)
+                .into_iter()
+                .filter_map(|(_, block)| block.highlight_steps().err()),
+        );
+        style
+    }
+}
+
+impl Slide {
+    /// Splits speaker notes, given as `<!-- notes: ... -->` comments or fenced
+    /// `notes` blocks, from the content shown to the audience.
+    pub fn parse(text: &str, aliases: &languages::LanguageAliases) -> Slide {
+        let mut content = String::new();
+        let mut notes: Vec<String> = Vec::new();
+        let mut lines = text.split_inclusive('\n');
+        while let Some(line) = lines.next() {
+            let trimmed = line.trim();
+            if let Some(comment) = line.trim_start().strip_prefix("<!-- notes:") {
+                let mut note = comment.to_string();
+                while !note.trim_end().ends_with("-->") {
+                    match lines.next() {
+                        Some(line) => note.push_str(line),
+                        None => break,
+                    }
+                }
+                notes.push(note.trim().trim_end_matches("-->").trim().to_string());
+            } else if trimmed == "```notes" {
+                let note: Vec<&str> = lines
+                    .by_ref()
+                    .take_while(|line| !line.trim().starts_with("```"))
+                    .collect();
+                notes.push(note.concat().trim().to_string());
+            } else {
+                content.push_str(line);
+            }
+        }
+        Slide {
+            style: SlideStyle::parse(&content, aliases),
+            starts_appendix: rendering::has_directive(&content, "appendix"),
+            hidden: rendering::has_directive(&content, "hidden"),
+            title: first_heading(&content),
+            line: 1,
+            content,
+            notes: (!notes.is_empty()).then(|| notes.join("\n")),
+        }
+    }
+}
+
+/// The first heading of a slide. Lines starting with `#` in code blocks are
+/// not headings.
+fn first_heading(content: &str) -> Option<String> {
+    let mut in_code_block = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        } else if !in_code_block && line.starts_with('#') {
+            let heading = line.trim_start_matches('#');
+            if heading.starts_with(' ') {
+                return Some(heading.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// A presentation loaded next to the shown one, with the position it was
+/// left at.
+struct Deck<'a> {
+    presentation_file: &'a str,
+    metadata: Metadata,
+    slides: Vec<Slide>,
+    symbols: SymbolIndex,
+    current_slide: usize,
+    revealed_steps: usize,
 }
 
 pub struct Presentation<'a> {
     current_slide: usize,
+    revealed_steps: usize,
     presentation_file: &'a str,
-    slides: Vec<&'a str>,
+    slides: Vec<Slide>,
+    show_notes: bool,
+    /// Show the questions asked by the audience over the slide
+    show_questions: bool,
     metadata: Metadata,
     current_theme_index: usize,
-    themes: Vec<&'a Theme>,
+    themes: Vec<Theme>,
+    options: Options,
+    exec_cache: ExecCache,
+    symbols: SymbolIndex,
+    notifications: Notifications,
+    /// Text shown across the whole screen instead of the slide
+    large_type: Option<String>,
+    /// The text searched for last, highlighted on the slides
+    search: Option<String>,
+    /// The running countdown of a break slide
+    countdown: Cell<Option<CountdownTimer>>,
+    /// The slide scrolled last and by how many blocks of content
+    scroll: Option<(usize, usize)>,
+    /// Where a code block was copied last and which one
+    copied_code: Option<((usize, usize, usize), usize)>,
+    /// Whether the content of the slide on screen runs past the bottom
+    overflowing: Cell<bool>,
+    /// The slide whose alerts were triggered last, so that they are only
+    /// triggered again once another slide was shown
+    alerted_slide: Option<usize>,
+    cues: Option<CueTimer>,
+    /// When the presentation was opened, for the clock
+    started: Instant,
+    show_clock: bool,
+    /// Where the notes and the clock are drawn
+    panes: Panes,
+    /// The configured margins and maximum width of the content
+    stage: StageWidth,
+    /// Played when another slide comes up
+    sounds: Sounds,
+    /// The configured glyphs, replacing those of the themes
+    glyphs: GlyphOverrides,
+    /// The time spent per slide, while rehearsing
+    rehearsal: Option<Rehearsal>,
+    /// Where the events of the talk are appended to, with `--event-log`
+    event_log: Option<EventLog>,
+    /// All loaded presentations, with `None` in place of the shown one
+    decks: Vec<Option<Deck<'a>>>,
+    current_deck: usize,
+    /// Collects the votes of the audience while the deck has polls
+    server: Option<Server>,
+    /// The slides of the current deck drawn so far
+    frames: FrameCache,
+}
+
+impl<'a> Presentation<'a> {
+    /// Loads another presentation to switch to.
+    pub fn add_deck(&mut self, presentation_file: &'a str, metadata: Metadata, slides: Vec<Slide>) {
+        self.decks.push(Some(Deck {
+            presentation_file,
+            metadata,
+            symbols: SymbolIndex::build(&slides, &self.options.language_aliases),
+            slides,
+            current_slide: 0,
+            revealed_steps: 0,
+        }));
+    }
+
+    /// Shows another of the loaded presentations at the position it was left
+    /// at.
+    pub fn switch_deck(&mut self, index: usize) {
+        if index == self.current_deck {
+            return;
+        }
+        let Some(next) = self.decks.get_mut(index).and_then(Option::take) else {
+            return;
+        };
+        let previous = Deck {
+            presentation_file: mem::replace(&mut self.presentation_file, next.presentation_file),
+            metadata: mem::replace(&mut self.metadata, next.metadata),
+            slides: mem::replace(&mut self.slides, next.slides),
+            symbols: mem::replace(&mut self.symbols, next.symbols),
+            current_slide: mem::replace(&mut self.current_slide, next.current_slide),
+            revealed_steps: mem::replace(&mut self.revealed_steps, next.revealed_steps),
+        };
+        self.decks[self.current_deck] = Some(previous);
+        self.current_deck = index;
+        self.alerted_slide = None;
+        self.frames.clear();
+        self.update_polls();
+    }
 }
 
 impl Presentation<'_> {
     pub fn new<'a>(
         metadata: Metadata,
-        slides: Vec<&'a str>,
+        slides: Vec<Slide>,
         presentation_file: &'a str,
+        options: Options,
+        initial_slide: usize,
     ) -> Presentation<'a> {
         Presentation {
-            current_slide: 0,
+            current_slide: initial_slide.min(slides.len() - 1),
+            revealed_steps: 0,
             presentation_file,
-            slides,
+            show_notes: false,
+            show_questions: false,
+            show_clock: metadata.duration.is_some(),
             metadata,
             current_theme_index: 0,
-            themes: vec![
-                &Theme::CatppuccinLatte,
-                &Theme::CatppuccinMocha,
-                &Theme::OneDark,
-            ],
+            themes: Theme::builtin(),
+            exec_cache: ExecCache::new(options.refresh_exec),
+            symbols: SymbolIndex::build(&slides, &options.language_aliases),
+            notifications: Notifications::default(),
+            large_type: None,
+            search: None,
+            countdown: Cell::new(None),
+            scroll: None,
+            copied_code: None,
+            overflowing: Cell::new(false),
+            alerted_slide: None,
+            cues: None,
+            started: Instant::now(),
+            panes: Panes::default(),
+            stage: StageWidth::default(),
+            sounds: Sounds::default(),
+            glyphs: GlyphOverrides::default(),
+            rehearsal: options.rehearse.then(Rehearsal::default),
+            event_log: None,
+            decks: vec![None],
+            current_deck: 0,
+            server: None,
+            frames: FrameCache::default(),
+            slides,
+            options,
+        }
+    }
+
+    pub fn deck_count(&self) -> usize {
+        self.decks.len()
+    }
+
+    pub fn current_deck(&self) -> usize {
+        self.current_deck
+    }
+
+    /// The title of the shown presentation, or its file name.
+    pub fn deck_name(&self) -> &str {
+        self.metadata.title.as_deref().unwrap_or_else(|| {
+            Path::new(self.presentation_file)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(self.presentation_file)
+        })
+    }
+
+    /// The countdown of the current slide if it is a break slide.
+    pub fn current_countdown(&self) -> Option<Countdown> {
+        rendering::extract_directive(self.current_slide(), "countdown").and_then(Countdown::parse)
+    }
+
+    /// The title of a slide, which is the presentation's title for the title
+    /// slide.
+    pub fn slide_title(&self, index: usize) -> Option<&str> {
+        let slide = &self.slides[index];
+        if rendering::has_directive(&slide.content, "title_slide") {
+            return self.metadata.title.as_deref();
         }
+        slide.title.as_deref()
+    }
+
+    /// The slides with a title matching the query, best matches first.
+    pub fn find_slides(&self, query: &str) -> Vec<(usize, &str)> {
+        let mut matches: Vec<(i64, usize, &str)> = (0..self.slides.len())
+            .filter_map(|index| {
+                let title = self.slide_title(index)?;
+                fuzzy::score(query, title).map(|score| (score, index, title))
+            })
+            .collect();
+        // Equal scores keep the order of the slides
+        matches.sort_by_key(|(score, index, _)| (-score, *index));
+        matches
+            .into_iter()
+            .map(|(_, index, title)| (index, title))
+            .collect()
+    }
+
+    /// Finds the next slide containing the text, going backwards with
+    /// `forward` set to false. Wraps around at the ends of the presentation,
+    /// so the current slide comes last.
+    pub fn search_slide(&self, text: &str, forward: bool) -> Option<usize> {
+        let total = self.slides.len();
+        (1..=total)
+            .map(|offset| match forward {
+                true => (self.current_slide + offset) % total,
+                false => (self.current_slide + total - offset) % total,
+            })
+            .find(|&index| !search::find_matches(&self.slides[index].content, text).is_empty())
+    }
+
+    /// The text and severity of the clock in the footer, if it is shown.
+    pub fn clock(&self) -> Option<(String, Severity)> {
+        self.show_clock
+            .then(|| clock::footer_clock(self.started.elapsed(), self.metadata.duration))
     }
 
     pub fn total_slides(&self) -> usize {
         self.slides.len()
     }
 
+    /// The index of the first slide after the `<!-- appendix -->` marker, or
+    /// the number of slides without one. Appendix slides are only shown when
+    /// jumped to.
+    pub fn appendix_start(&self) -> usize {
+        self.slides
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, slide)| slide.starts_appendix)
+            .map_or(self.slides.len(), |(index, _)| index)
+    }
+
+    pub fn in_appendix(&self) -> bool {
+        self.current_slide >= self.appendix_start()
+    }
+
+    /// The 1-based number of the current slide when counting neither hidden
+    /// nor appendix slides, as opposed to its index in the file. Slides that
+    /// don't count get the number of the last one before them that does.
+    pub fn logical_slide_number(&self) -> usize {
+        let appendix_start = self.appendix_start();
+        self.slides[..=self.current_slide.min(appendix_start.saturating_sub(1))]
+            .iter()
+            .filter(|slide| !slide.hidden)
+            .count()
+    }
+
+    /// The number of slides counted in the footer and progress bar.
+    pub fn logical_slide_count(&self) -> usize {
+        self.counted_slides().len()
+    }
+
+    /// The indices of the slides that are neither hidden nor in the appendix.
+    pub fn counted_slides(&self) -> Vec<usize> {
+        (0..self.appendix_start())
+            .filter(|&index| !self.slides[index].hidden)
+            .collect()
+    }
+
+    /// The slides that next and previous move through: the appendix once
+    /// it has been jumped to, the rest otherwise.
+    fn current_section(&self) -> Range<usize> {
+        let appendix_start = self.appendix_start();
+        if self.current_slide >= appendix_start {
+            appendix_start..self.slides.len()
+        } else {
+            0..appendix_start
+        }
+    }
+
     pub fn current_slide(&self) -> &str {
-        self.slides[self.current_slide]
+        &self.slides[self.current_slide].content
+    }
+
+    /// The line of the presentation file the current slide starts at.
+    pub fn current_line(&self) -> usize {
+        self.slides[self.current_slide].line
+    }
+
+    pub fn current_notes(&self) -> Option<&str> {
+        self.slides[self.current_slide].notes.as_deref()
+    }
+
+    pub fn toggle_notes(&mut self) {
+        self.show_notes = !self.show_notes;
+    }
+
+    /// The part of the current slide before the first unrevealed pause marker.
+    pub fn visible_content(&self) -> &str {
+        let slide = self.current_slide();
+        let steps = self.current_steps();
+        let pause = steps[self.revealed_steps.min(steps.len())..]
+            .iter()
+            .find_map(|step| match step {
+                Step::Pause(position) => Some(*position),
+                Step::Highlight(_) => None,
+            });
+        match pause {
+            Some(position) => &slide[..position],
+            None => slide,
+        }
+    }
+
+    /// The code of a revealed code block of the current slide to copy, the one
+    /// after the block copied last when copying again. Returns the index of the
+    /// block, the number of blocks and the code.
+    pub fn next_code_block(&mut self) -> Option<(usize, usize, String)> {
+        let blocks: Vec<_> =
+            rendering::code_blocks(self.visible_content(), &self.options.language_aliases)
+                .into_iter()
+                .map(|(_, block)| block)
+                .filter(|block| block.language != "poll")
+                .collect();
+        if blocks.is_empty() {
+            return None;
+        }
+        let index = match self.copied_code {
+            Some((position, index)) if position == self.position() => (index + 1) % blocks.len(),
+            _ => 0,
+        };
+        self.copied_code = Some((self.position(), index));
+        Some((index, blocks.len(), blocks[index].content.clone()))
+    }
+
+    /// The group of highlighted lines shown of a code block of the current
+    /// slide, counting from 0.
+    pub(crate) fn highlight_step(&self, block: &rendering::CodeBlock) -> usize {
+        let slide = self.current_slide();
+        let index = rendering::code_blocks(slide, &self.options.language_aliases)
+            .iter()
+            .position(|(_, other)| {
+                other.content == block.content && other.highlight == block.highlight
+            });
+        let steps = self.current_steps();
+        steps[..self.revealed_steps.min(steps.len())]
+            .iter()
+            .filter(|step| index.is_some_and(|index| **step == Step::Highlight(index)))
+            .count()
     }
+
+    /// The theme of the current slide, which is the selected one unless the
+    /// slide asks for another.
     pub fn current_theme(&self) -> &Theme {
-        self.themes[self.current_theme_index]
+        self.slides[self.current_slide]
+            .style
+            .theme
+            .as_ref()
+            .and_then(|name| self.themes.iter().find(|theme| theme.matches(name)))
+            .unwrap_or(&self.themes[self.current_theme_index])
+    }
+
+    /// The first and the last column slides are drawn in on a screen `width`
+    /// columns wide.
+    pub fn stage_columns(&self, width: u16) -> (u16, u16) {
+        self.metadata.options.stage.or(self.stage).columns(width)
+    }
+
+    /// How the content of the current slide is aligned, by its directives or
+    /// else by the options of the presentation.
+    pub fn alignment(&self) -> (Alignment, VerticalAlignment) {
+        let content = self.current_slide();
+        let options = &self.metadata.options;
+        (
+            rendering::extract_directive(content, "alignment")
+                .and_then(Alignment::from_name)
+                .unwrap_or(options.alignment),
+            rendering::extract_directive(content, "vertical_alignment")
+                .and_then(VerticalAlignment::from_name)
+                .unwrap_or(options.vertical_alignment),
+        )
+    }
+
+    /// The glyphs of the current theme with the configured ones applied, or
+    /// only ASCII characters with `--ascii`.
+    pub fn glyphs(&self) -> Glyphs {
+        match self.options.ascii {
+            true => Glyphs::ascii(),
+            false => self.glyphs.apply(&self.current_theme().glyphs),
+        }
+    }
+
+    /// The syntax highlighting colors of the current theme with those of the
+    /// presentation applied.
+    pub fn code_colors(&self) -> CodeColors {
+        self.metadata.code_colors.apply(&self.current_theme().code)
+    }
+
+    pub fn current_background(&self) -> Option<Rgb> {
+        self.slides[self.current_slide].style.background
+    }
+
+    /// Problems with the slide directives for colors and with image
+    /// attributes, one per slide and directive.
+    pub fn style_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (index, slide) in self.slides.iter().enumerate() {
+            let style = &slide.style;
+            if let Some(name) = &style.theme {
+                if !self.themes.iter().any(|theme| theme.matches(name)) {
+                    warnings.push(format!("Slide {}: unknown theme '{}'", index + 1, name));
+                }
+            }
+            for warning in &style.warnings {
+                warnings.push(format!("Slide {}: {}", index + 1, warning));
+            }
+        }
+        warnings
+    }
+
+    /// Switches to the theme with the given name, ignoring case.
+    pub fn select_theme(&mut self, name: &str) -> Result<(), String> {
+        match self.themes.iter().position(|theme| theme.matches(name)) {
+            Some(index) => {
+                self.current_theme_index = index;
+                Ok(())
+            }
+            None => {
+                let names: Vec<&str> = self.themes.iter().map(|theme| theme.get_name()).collect();
+                Err(format!(
+                    "Unknown theme '{}', available themes: {}",
+                    name,
+                    names.join(", ")
+                ))
+            }
+        }
     }
 
     pub fn cycle_theme(&mut self) {
         self.current_theme_index = (self.current_theme_index + 1) % self.themes.len();
     }
 
+    /// Runs all annotated code blocks so their results are available when the
+    /// slides are shown.
+    pub fn run_annotations(&mut self) {
+        // Nothing runs on its own for attendees following along
+        if self.options.read_only {
+            return;
+        }
+        for slide in &self.slides {
+            for (_, block) in rendering::code_blocks(&slide.content, &self.options.language_aliases)
+            {
+                if block.is_annotated() {
+                    self.exec_cache.execute(&block.language, &block.content);
+                }
+            }
+        }
+    }
+
+    pub fn execute_current_slide(&mut self) -> Result<(), String> {
+        let slide = &self.slides[self.current_slide].content;
+        let mut languages = Vec::new();
+        for (_, block) in rendering::code_blocks(slide, &self.options.language_aliases) {
+            if block.is_executable() {
+                self.exec_cache.execute(&block.language, &block.content);
+                languages.push(block.language);
+            }
+        }
+        // The output is shown below the blocks
+        self.frames.clear();
+        for language in languages {
+            self.log_event(LoggedEvent::Exec {
+                slide: self.current_slide + 1,
+                language: &language,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Appends the event to the `--event-log` file, if there is one.
+    fn log_event(&mut self, event: LoggedEvent) -> Result<(), String> {
+        match &mut self.event_log {
+            Some(log) => log
+                .record(event)
+                .map_err(|err| format!("Could not write the event log: {}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether any of the loaded presentations asks the audience something.
+    pub fn has_polls(&self) -> bool {
+        let aliases = &self.options.language_aliases;
+        !poll::find_polls(&self.slides, aliases).is_empty()
+            || self
+                .decks
+                .iter()
+                .flatten()
+                .any(|deck| !poll::find_polls(&deck.slides, aliases).is_empty())
+    }
+
+    /// Lets the audience vote on the polls of the shown presentation.
+    fn update_polls(&self) {
+        if let Some(server) = &self.server {
+            server.set_polls(poll::find_polls(
+                &self.slides,
+                &self.options.language_aliases,
+            ));
+        }
+    }
+
+    /// Replaces the slides after the presentation file changed, staying on the
+    /// current slide if it still exists.
+    pub fn reload(&mut self, metadata: Metadata, slides: Vec<Slide>) {
+        self.metadata = metadata;
+        self.symbols = SymbolIndex::build(&slides, &self.options.language_aliases);
+        self.slides = slides;
+        self.frames.clear();
+        self.update_polls();
+        self.current_slide = self.current_slide.min(self.slides.len() - 1);
+        self.revealed_steps = self.revealed_steps.min(self.current_steps().len());
+    }
+
+    /// The number of blocks of content the current slide is scrolled by.
+    pub fn scrolled(&self) -> usize {
+        self.scroll
+            .filter(|(slide, _)| *slide == self.current_slide)
+            .map_or(0, |(_, blocks)| blocks)
+    }
+
+    /// Scrolls the current slide by a block of content, down only while more
+    /// content is hidden below the stage.
+    pub fn scroll(&mut self, down: bool) {
+        let scrolled = match down {
+            true if self.overflowing.get() => self.scrolled() + 1,
+            true => self.scrolled(),
+            false => self.scrolled().saturating_sub(1),
+        };
+        self.scroll = Some((self.current_slide, scrolled));
+    }
+
+    pub fn go_to_slide(&mut self, slide: usize) {
+        self.current_slide = slide.min(self.slides.len() - 1);
+        self.revealed_steps = 0;
+    }
+
+    /// Jumps to the slide with the given 1-based number as typed by the user.
+    pub fn go_to_slide_number(&mut self, number: &str) -> Result<(), String> {
+        match number.parse::<usize>() {
+            Ok(number) if (1..=self.total_slides()).contains(&number) => {
+                self.go_to_slide(number - 1);
+                Ok(())
+            }
+            _ => Err(format!(
+                "No slide {}, there are {} slides",
+                number,
+                self.total_slides()
+            )),
+        }
+    }
+
+    /// Jumps to a slide number, or to the slide with a heading given like
+    /// `#getting-started`.
+    pub fn go_to_target(&mut self, target: &str) -> Result<(), String> {
+        let Some(anchor) = target.strip_prefix('#') else {
+            return self.go_to_slide_number(target);
+        };
+        let slide = self.slides.iter().position(|slide| {
+            slide
+                .content
+                .lines()
+                .filter_map(|line| line.strip_prefix('#'))
+                .any(|heading| heading_anchor(heading) == anchor)
+        });
+        match slide {
+            Some(slide) => {
+                self.go_to_slide(slide);
+                Ok(())
+            }
+            None => Err(format!("No slide with the heading #{}", anchor)),
+        }
+    }
+
+    /// Jumps to the next slide defining or using the symbol. Returns false if
+    /// no code block mentions it.
+    pub fn go_to_symbol(&mut self, name: &str) -> bool {
+        match self.symbols.next_slide(name, self.current_slide) {
+            Some(slide) => {
+                self.go_to_slide(slide);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_to_first_slide(&mut self) {
+        let first = (0..self.slides.len()).find(|&index| !self.slides[index].hidden);
+        self.go_to_slide(first.unwrap_or(0));
+    }
+
+    pub fn move_to_last_slide(&mut self) {
+        let section = self.current_section();
+        let last = section
+            .clone()
+            .rev()
+            .find(|&index| !self.slides[index].hidden);
+        self.go_to_slide(last.unwrap_or(section.end - 1));
+    }
+
     pub fn move_to_previous_slide(&mut self) {
-        self.current_slide = self.current_slide.saturating_sub(1);
+        if let Some(previous) = self.previous_slide() {
+            self.go_to_slide(previous);
+        }
     }
 
     pub fn move_to_next_slide(&mut self) {
-        if self.current_slide < self.slides.len() - 1 {
-            self.current_slide = self.current_slide.saturating_add(1);
+        let section = self.current_section();
+        let next = (self.current_slide + 1..section.end)
+            .find(|&index| !self.slides[index].hidden)
+            .or_else(|| self.wrapped_slide(section.clone()));
+        if let Some(next) = next {
+            self.go_to_slide(next);
+        }
+    }
+
+    fn previous_slide(&self) -> Option<usize> {
+        (0..self.current_slide)
+            .rev()
+            .find(|&index| !self.slides[index].hidden)
+            .or_else(|| self.wrapped_slide(self.current_section().rev()))
+    }
+
+    /// With `--loop`, the first slide that isn't hidden in the given order,
+    /// which comes after the end of the section.
+    fn wrapped_slide(&self, mut section: impl Iterator<Item = usize>) -> Option<usize> {
+        if !self.options.loop_slides {
+            return None;
+        }
+        section.find(|&index| !self.slides[index].hidden)
+    }
+
+    /// The deck, slide and revealed steps on screen.
+    fn position(&self) -> (usize, usize, usize) {
+        (self.current_deck, self.current_slide, self.revealed_steps)
+    }
+
+    /// How long the current slide is shown before moving on, when
+    /// auto-advancing.
+    pub fn advance_after(&self) -> Option<Duration> {
+        let default = self.options.auto_advance?;
+        Some(
+            rendering::extract_directive(self.current_slide(), "duration")
+                .and_then(cues::parse_duration)
+                .unwrap_or(default),
+        )
+    }
+
+    /// Reveals the next paused fragment or highlighted lines, or moves on to
+    /// the next slide.
+    pub fn move_forward(&mut self) {
+        if self.revealed_steps < self.current_steps().len() {
+            self.revealed_steps += 1;
+        } else {
+            self.move_to_next_slide();
         }
     }
+
+    /// Hides the last revealed fragment or moves back to the fully revealed
+    /// previous slide.
+    pub fn move_backward(&mut self) {
+        if self.revealed_steps > 0 {
+            self.revealed_steps -= 1;
+        } else if let Some(previous) = self.previous_slide() {
+            self.go_to_slide(previous);
+            self.reveal_all_steps();
+        }
+    }
+
+    /// Reveals all paused fragments and highlighted lines of the current slide.
+    pub fn reveal_all_steps(&mut self) {
+        self.revealed_steps = self.current_steps().len();
+    }
+
+    /// The pauses and highlighted line groups of the current slide.
+    fn current_steps(&self) -> Vec<Step> {
+        slide_steps(self.current_slide(), &self.options.language_aliases)
+    }
+}
+
+/// Shows a notification and has the corner redrawn once it expired.
+fn notify(
+    presentation: &mut Presentation,
+    sender: &UnboundedSender<Event>,
+    text: impl Into<String>,
+    severity: Severity,
+) {
+    presentation.notifications.push(text, severity);
+    events::spawn_notification_timer(sender.clone(), NOTIFICATION_DURATION);
+}
+
+/// Opens the presentation file in `$VISUAL` or `$EDITOR` at the line the
+/// current slide starts at. The slides are reloaded once the file is saved.
+async fn edit_current_slide(
+    presentation: &Presentation<'_>,
+    key_reader: &KeyReader,
+    stdout: &mut RawTerminal<Stdout>,
+) -> Result<(), String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    key_reader.pause();
+    rendering::restore_screen(stdout);
+    let _ = stdout.suspend_raw_mode();
+    // Run by the shell, so that the editor can come with arguments like
+    // `emacs -nw`
+    let status = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg("sh")
+        .arg(format!("+{}", presentation.current_line()))
+        .arg(presentation.presentation_file)
+        .status()
+        .await;
+    let _ = stdout.activate_raw_mode();
+    let mut restored = rendering::enter_alternate_screen(stdout);
+    if presentation.options.invert && restored.is_ok() {
+        restored = rendering::set_reverse_video(stdout, true);
+    }
+    key_reader.resume();
+    restored.map_err(|err| err.to_string())?;
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", editor, status)),
+        Err(err) => Err(format!("Could not run {}: {}", editor, err)),
+    }
+}
+
+/// Starts the web server for polls and questions unless it is running
+/// already.
+fn start_server(
+    presentation: &mut Presentation,
+    sender: &UnboundedSender<Event>,
+) -> Result<(), String> {
+    if presentation.server.is_none() {
+        presentation.server = Some(Server::start(
+            presentation.options.port,
+            presentation.options.token.clone(),
+            sender.clone(),
+        )?);
+        presentation.update_polls();
+    }
+    Ok(())
+}
+
+/// What the keys typed are used for.
+enum Mode {
+    /// Keys move through the slides and trigger actions
+    Slides,
+    /// The prompt in the last row is open, with the text typed into it so far
+    Prompt(PromptKind, String),
+    /// A grid of all slides to pick one from
+    Overview { selected: usize },
+    /// The slides whose titles match the typed query, with the index of the
+    /// selected match
+    Picker { query: String, selected: usize },
+    /// Every key but the lock key is ignored
+    Locked,
+    /// The active key bindings, as pairs of keys and what they do, are shown
+    /// over the slide until any key is pressed
+    Help(Vec<(String, String)>),
+}
+
+/// What the text typed into the prompt in the last row is used for.
+enum PromptKind {
+    Symbol,
+    Slide,
+    Search,
+    LargeType,
+    Quit,
+}
+
+impl PromptKind {
+    fn label(&self) -> &str {
+        match self {
+            PromptKind::Symbol => "Symbol: ",
+            PromptKind::Slide => ":",
+            PromptKind::Search => "/",
+            PromptKind::LargeType => "Large type: ",
+            PromptKind::Quit => "Really quit? (y/n) ",
+        }
+    }
+}
+
+/// What moving forward on a slide reveals before moving on to the next one.
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// The content up to the pause marker at this byte offset
+    Pause(usize),
+    /// The next group of highlighted lines of the code block at this index
+    Highlight(usize),
+}
+
+fn slide_steps(slide: &str, aliases: &languages::LanguageAliases) -> Vec<Step> {
+    let blocks = rendering::code_blocks(slide, aliases);
+    let mut steps = Vec::new();
+    let mut offset = 0;
+    for (i, line) in slide.split_inclusive('\n').enumerate() {
+        if line.trim() == "<!-- pause -->" {
+            steps.push(Step::Pause(offset));
+        } else if let Some(index) = blocks.iter().position(|(start, _)| *start == i) {
+            let groups = blocks[index].1.highlight_steps().ok().flatten();
+            let groups = groups.map_or(0, |groups| groups.len());
+            steps.extend((1..groups).map(|_| Step::Highlight(index)));
+        }
+        offset += line.len();
+    }
+    steps
 }
 
 #[tokio::main]
 async fn main() {
     let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 {
-        let presentation_file = &args[1];
-        if !Path::new(presentation_file).exists() {
-            eprintln!("The file {} does not exist!", presentation_file);
+    let args = match cli::parse_args(&args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
             process::exit(1);
         }
-        match fs::read_to_string(presentation_file) {
-            Ok(content) => {
-                let (metadata, content_without_metadata) = parse_metadata(&content);
-                let slides: Vec<&str> = content_without_metadata
-                    .split("<!-- end_slide -->")
+    };
+    if let Command::Doctor = args.command {
+        doctor::run();
+        process::exit(0);
+    }
+    if !Path::new(&args.presentation_file).exists() {
+        eprintln!("The file {} does not exist!", args.presentation_file);
+        process::exit(1);
+    }
+    process::exit(run(args).await);
+}
+
+/// Runs the command on the presentation and returns the exit code. An
+/// unpacked archive is removed when returning, which exiting would skip.
+async fn run(args: Args) -> i32 {
+    let unpacked = if archive::is_archive(&args.presentation_file) {
+        match archive::unpack(&args.presentation_file) {
+            Ok(unpacked) => Some(unpacked),
+            Err(err) => {
+                eprintln!("{}", err);
+                return 1;
+            }
+        }
+    } else {
+        None
+    };
+    let presentation_file = unpacked
+        .as_ref()
+        .map_or(&args.presentation_file, |unpacked| {
+            &unpacked.presentation_file
+        });
+    match fs::read_to_string(presentation_file) {
+        Ok(content) => {
+            if let Command::Check(options) = &args.command {
+                let success = check::run(presentation_file, &content, options, args.options);
+                return if success { 0 } else { 1 };
+            }
+            if let Command::Bundle(output_dir) = &args.command {
+                let success = bundle::run(presentation_file, &content, Path::new(output_dir));
+                return if success { 0 } else { 1 };
+            }
+            let config = match config::load() {
+                Ok(config) => config,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            };
+            if let Command::Pack(archive_path) = &args.command {
+                let theme = args.options.theme.clone().or(parse_metadata(&content)
+                    .ok()
+                    .and_then(|(metadata, _)| metadata.theme));
+                let theme_file = theme.and_then(|name| {
+                    config
+                        .themes
+                        .iter()
+                        .find(|(_, theme)| theme.matches(&name))
+                        .map(|(path, _)| path.as_path())
+                });
+                let success = archive::pack(
+                    presentation_file,
+                    &content,
+                    Path::new(archive_path),
+                    theme_file,
+                );
+                return if success { 0 } else { 1 };
+            }
+            let mut start_slide = args.start_slide;
+            if args.resume {
+                match state::last_slide(&args.presentation_file) {
+                    Ok(Some(slide)) => start_slide = slide,
+                    Ok(None) => {}
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return 1;
+                    }
+                }
+            }
+            let (content, origins, include_errors) =
+                expand_includes(presentation_file, &content, args.options.read_only);
+            let aliases = &args.options.language_aliases;
+            let (metadata, slides) = match parse_expanded(&content, &origins, aliases) {
+                Ok(presentation) => presentation,
+                Err(err) => {
+                    eprintln!("{}: {}", presentation_file, err);
+                    return 1;
+                }
+            };
+            let mut presentation = Presentation::new(
+                metadata,
+                slides,
+                presentation_file,
+                args.options,
+                start_slide,
+            );
+            if let Some(cue_file) = &presentation.options.cues {
+                match Cues::load(cue_file) {
+                    Ok(cues) => {
+                        presentation.cues =
+                            Some(CueTimer::new(cues, presentation.options.follow_cues))
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return 1;
+                    }
+                }
+            }
+            if let Some(length) = presentation.options.lightning {
+                let cues = Cues::evenly(length, &presentation.counted_slides());
+                presentation.cues = Some(CueTimer::new(cues, true));
+            }
+            for deck_file in &args.decks {
+                if archive::is_archive(deck_file) {
+                    eprintln!("Only the first presentation can be a .termdeck archive");
+                    return 1;
+                }
+                match load_deck(deck_file, &presentation.options) {
+                    Ok((metadata, slides, _)) => presentation.add_deck(deck_file, metadata, slides),
+                    Err(err) => {
+                        eprintln!("{}: {}", deck_file, err);
+                        return 1;
+                    }
+                }
+            }
+            presentation
+                .themes
+                .extend(config.themes.into_iter().map(|(_, theme)| theme));
+            presentation.panes = config.panes;
+            presentation.stage = config.stage;
+            presentation.sounds = config.sounds;
+            presentation.glyphs = config.glyphs;
+            // The theme packed into an archive is used unless another one is
+            // chosen
+            let archive_theme = unpacked
+                .as_ref()
+                .and_then(|unpacked| unpacked.theme.clone());
+            let archive_theme_name = archive_theme.as_ref().map(|theme| theme.name.clone());
+            presentation.themes.extend(archive_theme);
+            let theme = presentation
+                .options
+                .theme
+                .clone()
+                .or(presentation.metadata.theme.clone())
+                .or(archive_theme_name);
+            if let Some(theme) = theme {
+                if let Err(err) = presentation.select_theme(&theme) {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+            if let Some(path) = presentation.options.event_log.clone() {
+                let slides = presentation.total_slides();
+                let log = EventLog::open(&path).and_then(|mut log| {
+                    log.record(LoggedEvent::Start {
+                        presentation: &args.presentation_file,
+                        slides,
+                    })?;
+                    Ok(log)
+                });
+                match log {
+                    Ok(log) => presentation.event_log = Some(log),
+                    Err(err) => {
+                        eprintln!("Could not write {}: {}", path, err);
+                        return 1;
+                    }
+                }
+            }
+            presentation.run_annotations();
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let key_reader = events::spawn_key_reader(sender.clone());
+            events::spawn_resize_watcher(sender.clone());
+            events::spawn_ticker(sender.clone());
+            if presentation.has_polls() {
+                if let Err(err) = start_server(&mut presentation, &sender) {
+                    eprintln!("{}", err);
+                    return 1;
+                }
+            }
+            // Presenting still works without hot reload, e.g. when inotify
+            // watches are exhausted
+            let _watchers: Vec<_> = std::iter::once(presentation_file)
+                .chain(&args.decks)
+                .map(|file| events::watch_presentation(file, sender.clone()).ok())
+                .collect();
+            for err in include_errors {
+                notify(&mut presentation, &sender, err, Severity::Error);
+            }
+            for warning in config
+                .warnings
+                .into_iter()
+                .chain(presentation.style_warnings())
+            {
+                notify(&mut presentation, &sender, warning, Severity::Warning);
+            }
+            let result = match TerminalGuard::new() {
+                Ok(mut stdout) => {
+                    let (columns, rows) = presentation.options.shift;
+                    rendering::set_stage_offset(columns, rows);
+                    inline::set_hyperlinks(terminal::supports_hyperlinks());
+                    present(
+                        &mut presentation,
+                        &config.key_bindings,
+                        (&sender, receiver),
+                        &key_reader,
+                        &mut stdout,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            presentation.switch_deck(0);
+            if let Err(err) = presentation.log_event(LoggedEvent::Quit) {
+                eprintln!("{}", err);
+            }
+            if let Err(err) =
+                state::save_last_slide(&args.presentation_file, presentation.current_slide)
+            {
+                eprintln!("Could not remember the slide: {}", err);
+            }
+            if let Some(rehearsal) = presentation.rehearsal.take() {
+                let titles: Vec<Option<&str>> = (0..presentation.total_slides())
+                    .map(|index| presentation.slide_title(index))
                     .collect();
-                let mut presentation = Presentation::new(metadata, slides, presentation_file);
-                let stdin = stdin();
-                let mut stdout = stdout().into_raw_mode().unwrap();
-                rendering::render_slide(&presentation, &mut stdout);
-                for c in stdin.keys() {
-                    match c.unwrap() {
-                        termion::event::Key::Char('h') => {
-                            presentation.move_to_previous_slide();
-                        }
-                        termion::event::Key::Char('l') => {
-                            presentation.move_to_next_slide();
+                let report = rehearsal.finish(&titles);
+                print!("{}", report);
+                let report_file =
+                    Path::new(&args.presentation_file).with_extension("rehearsal.txt");
+                match fs::write(&report_file, report) {
+                    Ok(()) => println!("Saved the timings to {}", report_file.display()),
+                    Err(err) => eprintln!("Could not write {}: {}", report_file.display(), err),
+                }
+            }
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                return 1;
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("Error reading file: {}", err);
+            1
+        }
+    }
+}
+
+/// Shows the presentation and handles its events until quitting.
+async fn present(
+    presentation: &mut Presentation<'_>,
+    configured: &KeyBindings,
+    (sender, mut receiver): (&UnboundedSender<Event>, UnboundedReceiver<Event>),
+    key_reader: &KeyReader,
+    stdout: &mut RawTerminal<Stdout>,
+) -> Result<(), Error> {
+    let (mut bindings, mut jump_targets, warnings) =
+        deck_key_bindings(configured, &presentation.metadata.keys);
+    for warning in warnings {
+        notify(presentation, sender, warning, Severity::Warning);
+    }
+    if presentation.options.invert {
+        rendering::set_reverse_video(stdout, true)?;
+    }
+    let mut mode = Mode::Slides;
+    render(presentation, &mode, sender, stdout)?;
+    // Digits typed before `g` to jump to a slide
+    let mut count = String::new();
+    // The keys typed so far of a sequence like `gg`
+    let mut pending_keys = Vec::new();
+    // The slide the presenter was last told about running over time on
+    let mut overtime_alerted = None;
+    // What was on screen at the last tick and since when, for auto-advancing
+    let mut shown = (presentation.position(), Instant::now());
+    while let Some(event) = receiver.recv().await {
+        let key = match event {
+            Event::Key(key) => key,
+            Event::PresentationChanged => {
+                if let Some(shortcuts) = reload_deck(presentation, sender, configured) {
+                    (bindings, jump_targets) = shortcuts;
+                }
+                if let Mode::Overview { selected } = &mut mode {
+                    *selected = (*selected).min(presentation.total_slides() - 1);
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Event::Tick => {
+                if let Some(slide) = presentation.cues.as_mut().and_then(CueTimer::due_slide) {
+                    presentation.go_to_slide(slide);
+                }
+                if shown.0 != presentation.position() {
+                    shown = (presentation.position(), Instant::now());
+                }
+                // Ticks come once a second, the nearest one to the time is
+                // taken
+                let advance = presentation
+                    .advance_after()
+                    .is_some_and(|after| shown.1.elapsed() + Duration::from_millis(500) >= after);
+                if advance && matches!(mode, Mode::Slides | Mode::Locked) {
+                    presentation.move_forward();
+                    if shown.0 != presentation.position() {
+                        shown = (presentation.position(), Instant::now());
+                        render(presentation, &mode, sender, stdout)?;
+                        continue;
+                    }
+                }
+                let slide = presentation.current_slide;
+                let over_time = presentation
+                    .cues
+                    .as_ref()
+                    .is_some_and(|cues| cues.over_time(slide));
+                if over_time && overtime_alerted != Some(slide) {
+                    overtime_alerted = Some(slide);
+                    let message = format!("Behind schedule on slide {}", slide + 1);
+                    for alert in presentation.options.overtime_alerts.clone() {
+                        if let Err(err) = alerts::trigger(alert, &message, stdout) {
+                            notify(presentation, sender, err, Severity::Error);
                         }
-                        termion::event::Key::Char('t') => {
-                            presentation.cycle_theme();
-                            rendering::render_slide(&presentation, &mut stdout);
-                            rendering::render_notification(
-                                presentation.current_theme().get_name(),
-                                &mut stdout,
-                                presentation.current_theme().get_theme_colors().text,
-                            )
-                            .await;
+                    }
+                }
+                if presentation.cues.is_none()
+                    && presentation.current_countdown().is_none()
+                    && !presentation.show_clock
+                {
+                    continue;
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Event::NotificationExpired if !matches!(mode, Mode::Slides | Mode::Locked) => {
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Event::NotificationExpired => {
+                rendering::render_notification_area(presentation, stdout)?;
+                continue;
+            }
+            Event::Resized | Event::PollVoted | Event::QuestionAsked => {
+                if matches!(event, Event::Resized) {
+                    presentation.frames.clear();
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+        };
+        // Raw mode turns Ctrl-C into a key press, it still quits right
+        // away unless locked
+        if key == Key::Ctrl('c') && !matches!(mode, Mode::Locked) {
+            break;
+        }
+        match &mut mode {
+            Mode::Locked => {
+                if bindings.feed(&mut pending_keys, key) == Some(Action::Lock) {
+                    mode = Mode::Slides;
+                    notify(presentation, sender, "Input unlocked", Severity::Info);
+                    render(presentation, &mode, sender, stdout)?;
+                }
+                continue;
+            }
+            Mode::Prompt(PromptKind::Quit, _) => {
+                // The quit key confirms as well
+                let action = bindings.feed(&mut pending_keys, key);
+                if key == Key::Char('y') || action == Some(Action::Quit) {
+                    break;
+                }
+                pending_keys.clear();
+                mode = Mode::Slides;
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Mode::Prompt(kind, input) => {
+                match key {
+                    Key::Char('\n') => {
+                        let input = input.trim();
+                        let error = match kind {
+                            PromptKind::Symbol => (!presentation.go_to_symbol(input))
+                                .then(|| format!("Symbol {} not found", input)),
+                            PromptKind::Slide => presentation.go_to_slide_number(input).err(),
+                            PromptKind::Search => search(presentation, input, true),
+                            PromptKind::LargeType => {
+                                if !input.is_empty() {
+                                    presentation.large_type = Some(input.to_string());
+                                }
+                                None
+                            }
+                            PromptKind::Quit => None,
+                        };
+                        if let Some(error) = error {
+                            notify(presentation, sender, error, Severity::Warning);
                         }
-                        termion::event::Key::Char('q') => {
-                            break;
+                        mode = Mode::Slides;
+                    }
+                    Key::Esc => mode = Mode::Slides,
+                    Key::Backspace => {
+                        input.pop();
+                    }
+                    Key::Char(c) => input.push(c),
+                    _ => {}
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Mode::Overview { selected } => {
+                let columns = rendering::overview_columns()?;
+                let last = presentation.total_slides() - 1;
+                match key {
+                    Key::Char('\n') => {
+                        presentation.go_to_slide(*selected);
+                        mode = Mode::Slides;
+                    }
+                    Key::Esc | Key::Char('o') | Key::Char('q') => mode = Mode::Slides,
+                    Key::Char('h') | Key::Left => *selected = selected.saturating_sub(1),
+                    Key::Char('l') | Key::Right => *selected = (*selected + 1).min(last),
+                    Key::Char('k') | Key::Up => {
+                        *selected = selected.checked_sub(columns).unwrap_or(*selected)
+                    }
+                    Key::Char('j') | Key::Down if *selected + columns <= last => {
+                        *selected += columns
+                    }
+                    Key::Char('g') | Key::Home => *selected = 0,
+                    Key::Char('G') | Key::End => *selected = last,
+                    _ => continue,
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Mode::Picker { query, selected } => {
+                match key {
+                    Key::Char('\n') => {
+                        if let Some((slide, _)) = presentation.find_slides(query).get(*selected) {
+                            presentation.go_to_slide(*slide);
                         }
-                        _ => {}
+                        mode = Mode::Slides;
+                    }
+                    Key::Esc => mode = Mode::Slides,
+                    Key::Up | Key::Ctrl('p') => *selected = selected.saturating_sub(1),
+                    Key::Down | Key::Ctrl('n') => *selected += 1,
+                    Key::Backspace => {
+                        query.pop();
+                        *selected = 0;
+                    }
+                    Key::Char(c) => {
+                        query.push(c);
+                        *selected = 0;
+                    }
+                    _ => continue,
+                }
+                if let Mode::Picker { query, selected } = &mut mode {
+                    let matches = presentation.find_slides(query).len();
+                    *selected = (*selected).min(matches.saturating_sub(1));
+                }
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Mode::Help(_) => {
+                pending_keys.clear();
+                mode = Mode::Slides;
+                render(presentation, &mode, sender, stdout)?;
+                continue;
+            }
+            Mode::Slides => {}
+        }
+        if presentation.large_type.is_some() {
+            // Any key goes back to the slide
+            presentation.large_type = None;
+            render(presentation, &mode, sender, stdout)?;
+            continue;
+        }
+        // While a search is shown, n and N move between the slides containing
+        // it and Esc ends the search
+        if let Some(text) = presentation
+            .search
+            .clone()
+            .filter(|_| matches!(key, Key::Char('n') | Key::Char('N') | Key::Esc))
+        {
+            pending_keys.clear();
+            match key {
+                Key::Esc => presentation.search = None,
+                _ => {
+                    if let Some(error) = search(presentation, &text, key == Key::Char('n')) {
+                        notify(presentation, sender, error, Severity::Warning);
                     }
-                    rendering::render_slide(&presentation, &mut stdout);
                 }
             }
+            render(presentation, &mode, sender, stdout)?;
+            continue;
+        }
+        // Digits bound to an action only count once a count is started
+        let counts = !count.is_empty() || (pending_keys.is_empty() && !bindings.is_bound(key));
+        if let (Key::Char(digit @ '0'..='9'), true) = (key, counts) {
+            pending_keys.clear();
+            count.push(digit);
+            rendering::render_prompt(
+                "",
+                &count,
+                stdout,
+                presentation.current_theme().get_theme_colors().accent,
+            )?;
+            continue;
+        }
+        let count = std::mem::take(&mut count);
+        // A count followed by the first key of the first or last slide
+        // binding, e.g. `12g` or `12G`, jumps to that slide
+        if !count.is_empty() && bindings.starts(key, &[Action::FirstSlide, Action::LastSlide]) {
+            if let Err(error) = presentation.go_to_slide_number(&count) {
+                notify(presentation, sender, error, Severity::Warning);
+            }
+            render(presentation, &mode, sender, stdout)?;
+            continue;
+        }
+        let Some(action) = bindings.feed(&mut pending_keys, key) else {
+            if !count.is_empty() {
+                render(presentation, &mode, sender, stdout)?;
+            }
+            continue;
+        };
+        match action {
+            action if action.is_mutating() && presentation.options.read_only => {
+                let message = format!(
+                    "{} is disabled in read-only mode",
+                    action.name().unwrap_or_default().replace('_', " ")
+                );
+                notify(presentation, sender, message, Severity::Warning);
+            }
+            Action::Next => presentation.move_forward(),
+            Action::Previous => presentation.move_backward(),
+            Action::FirstSlide => presentation.move_to_first_slide(),
+            Action::LastSlide => presentation.move_to_last_slide(),
+            Action::ScrollDown => presentation.scroll(true),
+            Action::ScrollUp => presentation.scroll(false),
+            Action::GoToSlide => mode = Mode::Prompt(PromptKind::Slide, String::new()),
+            Action::Search => mode = Mode::Prompt(PromptKind::Search, String::new()),
+            Action::FindSymbol => mode = Mode::Prompt(PromptKind::Symbol, String::new()),
+            Action::LargeType => mode = Mode::Prompt(PromptKind::LargeType, String::new()),
+            Action::PickSlide => {
+                mode = Mode::Picker {
+                    query: String::new(),
+                    selected: 0,
+                }
+            }
+            Action::Overview => {
+                mode = Mode::Overview {
+                    selected: presentation.current_slide,
+                }
+            }
+            Action::ToggleNotes => presentation.toggle_notes(),
+            Action::ToggleClock => presentation.show_clock = !presentation.show_clock,
+            Action::Execute => {
+                if let Err(err) = presentation.execute_current_slide() {
+                    notify(presentation, sender, err, Severity::Error);
+                }
+            }
+            Action::CycleTheme => {
+                presentation.cycle_theme();
+                let name = presentation.current_theme().get_name().to_string();
+                notify(presentation, sender, name, Severity::Info);
+            }
+            Action::ToggleLog => presentation.notifications.toggle_log(),
+            Action::ToggleQuestions => match start_server(presentation, sender) {
+                Ok(()) => presentation.show_questions = !presentation.show_questions,
+                Err(err) => notify(presentation, sender, err, Severity::Error),
+            },
+            Action::CopySlide => {
+                match clipboard::copy(stdout, presentation.current_slide().trim()) {
+                    Ok(()) => {
+                        let message = format!("Copied slide {}", presentation.current_slide + 1);
+                        notify(presentation, sender, message, Severity::Info);
+                    }
+                    Err(err) => notify(presentation, sender, err, Severity::Error),
+                }
+            }
+            Action::CopyCode => match presentation.next_code_block() {
+                Some((index, count, code)) => match clipboard::copy(stdout, &code) {
+                    Ok(()) => {
+                        let message = match count {
+                            1 => "Copied the code block".to_string(),
+                            count => format!("Copied code block {} of {}", index + 1, count),
+                        };
+                        notify(presentation, sender, message, Severity::Info);
+                    }
+                    Err(err) => notify(presentation, sender, err, Severity::Error),
+                },
+                None => notify(
+                    presentation,
+                    sender,
+                    "No code block on this slide",
+                    Severity::Warning,
+                ),
+            },
+            Action::EditSlide => {
+                if let Err(err) = edit_current_slide(presentation, key_reader, stdout).await {
+                    notify(presentation, sender, err, Severity::Error);
+                }
+            }
+            Action::Lock => {
+                mode = Mode::Locked;
+                notify(presentation, sender, "Input locked", Severity::Info);
+            }
+            Action::Quit if presentation.options.confirm_quit => {
+                mode = Mode::Prompt(PromptKind::Quit, String::new())
+            }
+            Action::Quit => break,
+            Action::NextDeck if presentation.deck_count() > 1 => {
+                presentation
+                    .switch_deck((presentation.current_deck() + 1) % presentation.deck_count());
+                // Pick up changes made while the deck wasn't shown
+                if let Some(shortcuts) = reload_deck(presentation, sender, configured) {
+                    (bindings, jump_targets) = shortcuts;
+                }
+                let name = presentation.deck_name().to_string();
+                notify(presentation, sender, name, Severity::Info);
+            }
+            Action::NextDeck => {}
+            Action::Help => mode = Mode::Help(help_lines(&bindings, &jump_targets)),
+            Action::Jump(index) => {
+                if let Err(err) = presentation.go_to_target(&jump_targets[index]) {
+                    notify(presentation, sender, err, Severity::Warning);
+                }
+            }
+        }
+        render(presentation, &mode, sender, stdout)?;
+    }
+    Ok(())
+}
+
+/// Goes to the next slide containing the text and highlights it there. An
+/// empty text ends the search.
+fn search(presentation: &mut Presentation, text: &str, forward: bool) -> Option<String> {
+    if text.is_empty() {
+        presentation.search = None;
+        return None;
+    }
+    match presentation.search_slide(text, forward) {
+        Some(slide) => {
+            presentation.search = Some(text.to_string());
+            presentation.go_to_slide(slide);
+            None
+        }
+        None => {
+            presentation.search = None;
+            Some(format!("Pattern not found: {}", text))
+        }
+    }
+}
+
+/// Draws the screen for the mode the presentation is in, and triggers the
+/// alerts that became due.
+fn render(
+    presentation: &mut Presentation,
+    mode: &Mode,
+    sender: &UnboundedSender<Event>,
+    stdout: &mut RawTerminal<Stdout>,
+) -> Result<(), Error> {
+    let (deck, slide) = (presentation.current_deck(), presentation.current_slide);
+    if let Some(rehearsal) = &mut presentation.rehearsal {
+        // Only the slides of the first presentation are timed
+        match deck {
+            0 => rehearsal.show(slide),
+            _ => rehearsal.stop(),
+        }
+    }
+    let title = presentation.slide_title(slide).map(String::from);
+    let logged = presentation.log_event(LoggedEvent::Slide {
+        deck: deck + 1,
+        slide: slide + 1,
+        title: title.as_deref(),
+    });
+    draw(presentation, mode, stdout)?;
+    let mut errors = trigger_alerts(presentation, stdout);
+    errors.extend(logged.err());
+    if errors.is_empty() {
+        return Ok(());
+    }
+    for err in errors {
+        notify(presentation, sender, err, Severity::Error);
+    }
+    draw(presentation, mode, stdout)
+}
+
+fn draw(
+    presentation: &Presentation,
+    mode: &Mode,
+    stdout: &mut RawTerminal<Stdout>,
+) -> Result<(), Error> {
+    if rendering::too_small()? {
+        return rendering::render_too_small(presentation, stdout);
+    }
+    match mode {
+        Mode::Overview { selected } => rendering::render_overview(presentation, *selected, stdout),
+        Mode::Picker { query, selected } => {
+            rendering::render_slide(presentation, stdout)?;
+            rendering::render_picker(presentation, query, *selected, stdout)
+        }
+        Mode::Prompt(kind, input) => {
+            rendering::render_slide(presentation, stdout)?;
+            rendering::render_prompt(
+                kind.label(),
+                input,
+                stdout,
+                presentation.current_theme().get_theme_colors().accent,
+            )
+        }
+        Mode::Help(lines) => {
+            rendering::render_slide(presentation, stdout)?;
+            rendering::render_help(presentation, lines, stdout)
+        }
+        Mode::Slides | Mode::Locked => rendering::render_slide(presentation, stdout),
+    }
+}
+
+/// The keys of every bound action and what the action does, including the
+/// remapped keys and the presentation's shortcuts.
+fn help_lines(bindings: &KeyBindings, jump_targets: &[String]) -> Vec<(String, String)> {
+    bindings
+        .list()
+        .into_iter()
+        .map(|(action, keys)| {
+            let description = match action {
+                Action::Jump(index) => format!("goto {}", jump_targets[index]),
+                action => action.name().unwrap_or_default().replace('_', " "),
+            };
+            (keys.join(", "), description)
+        })
+        .collect()
+}
+
+/// Triggers the alerts and the sound of a slide that just came up and the
+/// alerts of a countdown whose time just ran out. Returns the alerts that
+/// failed.
+fn trigger_alerts(
+    presentation: &mut Presentation,
+    stdout: &mut RawTerminal<Stdout>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let slide = presentation.current_slide;
+    if presentation.alerted_slide != Some(slide) {
+        // Nothing is played for the first slide shown
+        let sound = match presentation.alerted_slide {
+            Some(_) if is_section(presentation.current_slide()) => presentation
+                .sounds
+                .section
+                .as_ref()
+                .or(presentation.sounds.slide.as_ref()),
+            Some(_) => presentation.sounds.slide.as_ref(),
+            None => None,
+        };
+        errors.extend(sound.and_then(|sound| alerts::play_sound(sound).err()));
+        presentation.alerted_slide = Some(slide);
+        let alert = rendering::extract_directive(presentation.current_slide(), "alert")
+            .and_then(SlideAlert::parse);
+        if let Some(alert) = alert {
+            let message = alert
+                .message
+                .or(presentation.slide_title(slide).map(String::from))
+                .unwrap_or_else(|| format!("Slide {}", slide + 1));
+            for alert in alert.alerts {
+                errors.extend(alerts::trigger(alert, &message, stdout).err());
+            }
+        }
+    }
+    if let (Some(mut timer), Some(countdown)) = (
+        presentation.countdown.get(),
+        presentation.current_countdown(),
+    ) {
+        if timer.finished && !timer.alerted {
+            timer.alerted = true;
+            presentation.countdown.set(Some(timer));
+            for alert in countdown.alerts {
+                errors.extend(alerts::trigger(alert, "Time is up", stdout).err());
+            }
+        }
+    }
+    errors
+}
+
+/// Whether the slide starts a section of the talk, with the `section` layout.
+fn is_section(slide: &str) -> bool {
+    rendering::extract_directive(slide, "layout").and_then(Layout::parse) == Some(Layout::Section)
+}
+
+/// Reads a presentation with the output of its `exec_include` commands and the
+/// included code files inserted. Returns the metadata, the slides and the
+/// includes that failed.
+fn load_deck(
+    presentation_file: &str,
+    options: &Options,
+) -> Result<(Metadata, Vec<Slide>, Vec<String>), String> {
+    let content = fs::read_to_string(presentation_file).map_err(|err| err.to_string())?;
+    let (content, origins, include_errors) =
+        expand_includes(presentation_file, &content, options.read_only);
+    let (metadata, slides) = parse_expanded(&content, &origins, &options.language_aliases)?;
+    Ok((metadata, slides, include_errors))
+}
+
+/// Reads the shown deck again, after it changed on disk or was switched to,
+/// and returns the key bindings with its shortcuts. Problems are shown as
+/// notifications.
+fn reload_deck(
+    presentation: &mut Presentation,
+    sender: &UnboundedSender<Event>,
+    configured: &KeyBindings,
+) -> Option<(KeyBindings, Vec<String>)> {
+    let (metadata, slides, include_errors) =
+        match load_deck(presentation.presentation_file, &presentation.options) {
+            Ok(deck) => deck,
             Err(err) => {
-                eprintln!("Error reading file: {}", err);
-                process::exit(1);
+                notify(
+                    presentation,
+                    sender,
+                    format!("Could not reload: {}", err),
+                    Severity::Error,
+                );
+                return None;
+            }
+        };
+    presentation.reload(metadata, slides);
+    presentation.run_annotations();
+    let (bindings, jump_targets, warnings) =
+        deck_key_bindings(configured, &presentation.metadata.keys);
+    for err in include_errors {
+        notify(presentation, sender, err, Severity::Error);
+    }
+    for warning in warnings.into_iter().chain(presentation.style_warnings()) {
+        notify(presentation, sender, warning, Severity::Warning);
+    }
+    Some((bindings, jump_targets))
+}
+
+/// Inserts the output of the `exec_include` commands, which are run next to
+/// the presentation file, and the code of the files code blocks include.
+/// Read-only, the commands aren't run and their directives stay comments.
+/// Also returns the line of the presentation file each line of the expanded
+/// content comes from, counting from 1, with included lines coming from the
+/// line that includes them.
+fn expand_includes(
+    presentation_file: &str,
+    content: &str,
+    read_only: bool,
+) -> (String, Vec<usize>, Vec<String>) {
+    let directory = Path::new(presentation_file)
+        .parent()
+        .unwrap_or(Path::new(""));
+    let mut expanded = String::with_capacity(content.len());
+    let mut origins = Vec::new();
+    let mut errors = Vec::new();
+    // A line or a whole code block at a time, to know where the lines come from
+    let mut lines = content.split_inclusive('\n').enumerate();
+    while let Some((index, line)) = lines.next() {
+        let mut part = line.to_string();
+        let mut part_lines = 1;
+        if line.trim_start().starts_with("```") {
+            for (_, line) in lines.by_ref() {
+                part.push_str(line);
+                part_lines += 1;
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
             }
         }
-    } else {
-        eprintln!("Please provide a presentation markdown file as an argument!");
-        process::exit(1);
+        if !read_only {
+            let (output, command_errors) = exec::expand_includes(&part, directory);
+            part = output;
+            errors.extend(command_errors);
+        }
+        let (part, file_errors) = sources::expand_code_files(&part, directory);
+        errors.extend(file_errors);
+        let count = part.lines().count();
+        origins.extend((0..count).map(|offset| match count == part_lines {
+            true => index + 1 + offset,
+            false => index + 1,
+        }));
+        expanded.push_str(&part);
     }
+    (expanded, origins, errors)
 }
 
-fn parse_metadata(content: &str) -> (Metadata, String) {
-    let re = Regex::new(r"(author|title|subtitle): (.*?)\n").unwrap();
-    let mut metadata = Metadata {
-        author: None,
-        title: None,
-        subtitle: None,
-    };
+/// The line of the presentation file a line of the expanded content comes
+/// from.
+fn source_line(origins: &[usize], line: usize) -> usize {
+    origins.get(line - 1).copied().unwrap_or(line)
+}
+
+/// Parses content with its includes expanded, with the slides starting at
+/// their lines in the presentation file, e.g. to open them in the editor.
+fn parse_expanded(
+    content: &str,
+    origins: &[usize],
+    aliases: &languages::LanguageAliases,
+) -> Result<(Metadata, Vec<Slide>), String> {
+    let (metadata, mut slides) = parse_presentation(content, aliases)?;
+    for slide in &mut slides {
+        slide.line = source_line(origins, slide.line);
+    }
+    Ok((metadata, slides))
+}
+
+fn parse_presentation(
+    content: &str,
+    aliases: &languages::LanguageAliases,
+) -> Result<(Metadata, Vec<Slide>), String> {
+    let (metadata, content_without_metadata) = parse_metadata(content)?;
+    let mut slides: Vec<Slide> = split_slides(content_without_metadata, &metadata.options)
+        .into_iter()
+        .map(|slide| {
+            let mut text = match metadata.options.emoji {
+                true => emoji::replace_shortcodes(slide),
+                false => slide.to_string(),
+            };
+            if metadata.options.line_breaks == LineBreaks::Join {
+                text = paragraphs::join_soft_breaks(&text);
+            }
+            let mut parsed = Slide::parse(&text, aliases);
+            parsed.line = line_number(content, slide);
+            parsed
+        })
+        .collect();
+    if metadata.has_title_slide() {
+        slides.insert(0, Slide::parse("<!-- title_slide -->\n", aliases));
+    }
+    Ok((metadata, slides))
+}
 
-    for cap in re.captures_iter(content) {
-        let key = &cap[1];
-        let value = cap[2].trim().to_string();
-        match key {
-            "author" => metadata.author = Some(value),
-            "title" => metadata.title = Some(value),
-            "subtitle" => metadata.subtitle = Some(value),
-            _ => {}
+/// The line of the content the first non-blank line of a part of the content
+/// is on, counting from 1.
+fn line_number(content: &str, part: &str) -> usize {
+    let start = part.as_ptr() as usize - content.as_ptr() as usize;
+    let blank = part.len() - part.trim_start().len();
+    content[..start + blank].matches('\n').count() + 1
+}
+
+/// Splits the slides at `<!-- end_slide -->` comments and at `---` lines
+/// outside of code blocks, as well as before headings if the presentation
+/// asks for it. Lines are kept in place so that they can be counted.
+fn split_slides<'a>(content: &'a str, options: &DeckOptions) -> Vec<&'a str> {
+    content
+        .split("<!-- end_slide -->")
+        .flat_map(|slide| split_at_breaks(slide, options))
+        .collect()
+}
+
+fn split_at_breaks<'a>(content: &'a str, options: &DeckOptions) -> Vec<&'a str> {
+    let mut slides = Vec::new();
+    let mut start = 0;
+    let mut line_start = 0;
+    let mut in_code_block = false;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end();
+        if text.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        } else if in_code_block {
+            // Lines in code blocks never end a slide
+        } else if text == "---" {
+            slides.push(&content[start..line_start]);
+            start = line_start + text.len();
+        } else if options.split_on_headings
+            && (text.starts_with("# ") || text.starts_with("## "))
+            && has_content(&content[start..line_start])
+        {
+            slides.push(&content[start..line_start]);
+            start = line_start;
         }
+        line_start += line.len();
+    }
+    slides.push(&content[start..]);
+    slides
+}
+
+/// Whether the text has anything besides blank lines and comments.
+fn has_content(text: &str) -> bool {
+    text.lines()
+        .any(|line| !line.trim().is_empty() && !rendering::is_comment(line))
+}
+
+/// Splits the frontmatter between the leading `---` lines from the slides.
+fn split_front_matter(content: &str) -> (&str, &str) {
+    let front_matter = Regex::new(r"(?s)^---\n(.*?\n)?---\n").unwrap();
+    match front_matter.captures(content) {
+        Some(cap) => (
+            cap.get(1).map_or("", |header| header.as_str()),
+            &content[cap.get(0).unwrap().end()..],
+        ),
+        None => ("", content),
+    }
+}
+
+/// Reads the YAML frontmatter and returns the metadata together with the rest
+/// of the presentation.
+fn parse_metadata(content: &str) -> Result<(Metadata, &str), String> {
+    let (header, content_without_metadata) = split_front_matter(content);
+    if header.trim().is_empty() {
+        return Ok((Metadata::default(), content_without_metadata));
+    }
+    let metadata =
+        serde_yaml::from_str(header).map_err(|err| format!("Invalid frontmatter: {}", err))?;
+    Ok((metadata, content_without_metadata))
+}
+
+/// The anchor a heading is jumped to by, e.g. `getting-started` for
+/// `## Getting Started!`.
+fn heading_anchor(heading: &str) -> String {
+    let mut anchor = String::new();
+    for c in heading.trim_start_matches('#').trim().chars() {
+        if c.is_alphanumeric() {
+            anchor.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    anchor
+}
+
+/// Binds the shortcuts of the presentation on top of the configured keys.
+/// Returns the bindings, the targets of the `goto` shortcuts indexed by
+/// `Action::Jump`, and the shortcuts that could not be bound.
+fn deck_key_bindings(
+    configured: &KeyBindings,
+    shortcuts: &BTreeMap<String, String>,
+) -> (KeyBindings, Vec<String>, Vec<String>) {
+    let mut bindings = configured.clone();
+    let mut targets = Vec::new();
+    let mut warnings = Vec::new();
+    for (keys, command) in shortcuts {
+        let action = match DeckAction::parse(command) {
+            Some(DeckAction::Action(action)) => action,
+            Some(DeckAction::GoTo(target)) => {
+                targets.push(target);
+                Action::Jump(targets.len() - 1)
+            }
+            None => {
+                warnings.push(format!("Unknown action '{}' for key {}", command, keys));
+                continue;
+            }
+        };
+        if let Err(err) = bindings.bind(keys, action) {
+            warnings.push(err);
+        }
+    }
+    (bindings, targets, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slide(text: &str) -> Slide {
+        Slide::parse(text, &languages::LanguageAliases::default())
+    }
+
+    fn presentation(slides: Vec<&str>) -> Presentation<'_> {
+        Presentation::new(
+            Metadata::default(),
+            slides.into_iter().map(slide).collect(),
+            "slides.md",
+            Options::default(),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_slide_parse_extracts_comment_notes() {
+        let slide = slide("# Title\n<!-- notes: mention\nthe demo -->\nText\n");
+        assert_eq!(slide.content, "# Title\nText\n");
+        assert_eq!(slide.notes.as_deref(), Some("mention\nthe demo"));
+    }
+
+    #[test]
+    fn test_slide_parse_extracts_fenced_notes() {
+        let slide = slide("# Title\n```notes\nbreathe\n```\nText\n");
+        assert_eq!(slide.content, "# Title\nText\n");
+        assert_eq!(slide.notes.as_deref(), Some("breathe"));
+    }
+
+    #[test]
+    fn test_slide_without_notes() {
+        let slide = slide("# Title\n<!-- pause -->\nText");
+        assert_eq!(slide.content, "# Title\n<!-- pause -->\nText");
+        assert_eq!(slide.notes, None);
+    }
+
+    #[test]
+    fn test_initial_slide_is_clamped() {
+        let slides = || vec!["one", "two"].into_iter().map(slide).collect();
+        let presentation =
+            Presentation::new(Metadata::default(), slides(), "", Options::default(), 1);
+        assert_eq!(presentation.current_slide, 1);
+        let presentation = Presentation::new(
+            Metadata::default(),
+            slides(),
+            "",
+            Options::default(),
+            usize::MAX,
+        );
+        assert_eq!(presentation.current_slide, 1);
+    }
+
+    #[test]
+    fn test_go_to_slide_number() {
+        let mut presentation = presentation(vec!["one", "two", "three"]);
+        assert_eq!(presentation.go_to_slide_number("3"), Ok(()));
+        assert_eq!(presentation.current_slide, 2);
+        assert!(presentation.go_to_slide_number("0").is_err());
+        assert!(presentation.go_to_slide_number("4").is_err());
+        assert!(presentation.go_to_slide_number("two").is_err());
+        assert_eq!(presentation.current_slide, 2);
+    }
+
+    #[test]
+    fn test_select_theme() {
+        let mut presentation = presentation(vec!["one"]);
+        assert_eq!(presentation.select_theme("one dark"), Ok(()));
+        assert_eq!(presentation.current_theme().get_name(), "One Dark");
+        assert_eq!(
+            presentation.select_theme("Dracula"),
+            Err(
+                "Unknown theme 'Dracula', available themes: Catppuccin Latte, \
+                 Catppuccin Mocha, One Dark"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_slide_style() {
+        let mut presentation = presentation(vec![
+            "# Demo\n<!-- theme: OneDark -->\n<!-- background: #1e1e2e -->",
+            "<!-- theme: Dracula -->\n<!-- background: dark -->",
+            "plain",
+        ]);
+        assert_eq!(presentation.current_theme().get_name(), "One Dark");
+        assert_eq!(
+            presentation.current_background(),
+            Some(Rgb(0x1e, 0x1e, 0x2e))
+        );
+        presentation.go_to_slide(2);
+        assert_eq!(presentation.current_theme().get_name(), "Catppuccin Latte");
+        assert_eq!(presentation.current_background(), None);
+        assert_eq!(
+            presentation.style_warnings(),
+            vec![
+                "Slide 2: unknown theme 'Dracula'",
+                "Slide 2: Invalid background color 'dark', expected #rrggbb"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_key_shortcuts() {
+        let (metadata, _) =
+            parse_metadata("---\ntitle: Demo\nkeys: {d: \"goto #demo\", x: \"exec slide\"}\n---\n")
+                .unwrap();
+        assert_eq!(metadata.keys["d"], "goto #demo");
+        assert_eq!(metadata.keys["x"], "exec slide");
+        let (metadata, _) =
+            parse_metadata("---\nkeys:\n  F5: goto 3\n  n: next\ntitle: T\n---\n").unwrap();
+        assert_eq!(metadata.keys.len(), 2);
+        assert_eq!(metadata.keys["F5"], "goto 3");
+    }
+
+    #[test]
+    fn test_go_to_target() {
+        let mut presentation = presentation(vec!["# Intro", "## Live Demo!\ncode", "end"]);
+        assert_eq!(presentation.go_to_target("#live-demo"), Ok(()));
+        assert_eq!(presentation.current_slide, 1);
+        assert_eq!(presentation.go_to_target("3"), Ok(()));
+        assert_eq!(presentation.current_slide, 2);
+        assert!(presentation.go_to_target("#outro").is_err());
+    }
+
+    #[test]
+    fn test_switch_deck_keeps_position() {
+        let mut presentation = presentation(vec!["a", "b", "c"]);
+        presentation.add_deck(
+            "backup.md",
+            Metadata::default(),
+            vec![slide("x"), slide("y")],
+        );
+        presentation.go_to_slide(2);
+        presentation.switch_deck(1);
+        assert_eq!(presentation.presentation_file, "backup.md");
+        assert_eq!(presentation.current_slide, 0);
+        presentation.go_to_slide(1);
+        presentation.switch_deck(0);
+        assert_eq!(presentation.presentation_file, "slides.md");
+        assert_eq!(presentation.current_slide, 2);
+        presentation.switch_deck(1);
+        assert_eq!(presentation.current_slide, 1);
+        assert_eq!(presentation.deck_name(), "backup.md");
+    }
+
+    #[test]
+    fn test_appendix_is_only_reached_by_jumping() {
+        let mut presentation = presentation(vec!["a", "b", "<!-- appendix -->\nc", "d"]);
+        assert_eq!(presentation.appendix_start(), 2);
+        presentation.move_to_next_slide();
+        presentation.move_to_next_slide();
+        assert_eq!(presentation.current_slide, 1);
+        presentation.move_to_first_slide();
+        presentation.move_to_last_slide();
+        assert_eq!(presentation.current_slide, 1);
+
+        presentation.go_to_slide_number("3").unwrap();
+        assert!(presentation.in_appendix());
+        presentation.move_to_next_slide();
+        presentation.move_to_next_slide();
+        assert_eq!(presentation.current_slide, 3);
+    }
+
+    #[test]
+    fn test_hidden_slides_are_skipped_and_not_counted() {
+        let mut presentation = presentation(vec![
+            "<!-- hidden -->\na",
+            "b",
+            "<!-- hidden -->\nc",
+            "d",
+            "<!-- appendix -->\ne",
+        ]);
+        presentation.move_to_first_slide();
+        assert_eq!(presentation.current_slide, 1);
+        assert_eq!(presentation.logical_slide_count(), 2);
+        assert_eq!(presentation.logical_slide_number(), 1);
+        presentation.move_forward();
+        assert_eq!(presentation.current_slide, 3);
+        assert_eq!(presentation.logical_slide_number(), 2);
+        presentation.move_backward();
+        assert_eq!(presentation.current_slide, 1);
+        presentation.move_backward();
+        assert_eq!(presentation.current_slide, 1);
+
+        presentation.go_to_slide_number("3").unwrap();
+        assert_eq!(presentation.logical_slide_number(), 1);
+        presentation.go_to_slide_number("5").unwrap();
+        assert_eq!(presentation.logical_slide_number(), 2);
+    }
+
+    #[test]
+    fn test_slide_title_is_first_heading() {
+        let parsed = slide("```sh\n# comment\n```\n#hashtag\n## Setup\n# Later\n");
+        assert_eq!(parsed.title.as_deref(), Some("Setup"));
+        assert_eq!(slide("Just text\n").title, None);
+    }
+
+    #[test]
+    fn test_find_slides_by_title() {
+        let presentation = presentation(vec![
+            "# Generic types",
+            "no title",
+            "# Using generics",
+            "# Traits",
+        ]);
+        let found: Vec<usize> = presentation
+            .find_slides("gen")
+            .iter()
+            .map(|(slide, _)| *slide)
+            .collect();
+        assert_eq!(found, vec![0, 2]);
+        assert_eq!(presentation.find_slides("").len(), 3);
+    }
+
+    #[test]
+    fn test_search_slide_wraps_around() {
+        let mut presentation = presentation(vec![
+            "# Lifetimes",
+            "# Traits",
+            "Borrowing and lifetimes",
+            "# Generics",
+        ]);
+        assert_eq!(presentation.search_slide("LIFETIME", true), Some(2));
+        assert_eq!(presentation.search_slide("lifetime", false), Some(2));
+        presentation.go_to_slide(2);
+        assert_eq!(presentation.search_slide("lifetime", true), Some(0));
+        assert_eq!(presentation.search_slide("traits", true), Some(1));
+        assert_eq!(presentation.search_slide("closures", true), None);
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let (metadata, content) = parse_metadata(
+            "---\ntitle: Talk\ntheme: One Dark\ndate: 2024-05-01\nevent: RustConf\n\
+             slides_url: https://example.com\n---\n# Hi\ntitle: not metadata\n",
+        )
+        .unwrap();
+        assert_eq!(metadata.theme.as_deref(), Some("One Dark"));
+        assert_eq!(metadata.title.as_deref(), Some("Talk"));
+        assert_eq!(metadata.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(metadata.event.as_deref(), Some("RustConf"));
+        assert!(metadata.author.is_empty());
+        assert_eq!(content, "# Hi\ntitle: not metadata\n");
+    }
+
+    #[test]
+    fn test_title_slide() {
+        let (_, slides) = parse_presentation(
+            "---\ntitle: Talk\n---\n# Hi\n",
+            &languages::LanguageAliases::default(),
+        )
+        .unwrap();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].content, "<!-- title_slide -->\n");
+
+        let (_, slides) = parse_presentation(
+            "---\ntitle: Talk\noptions:\n  title_slide: false\n---\n# Hi\n",
+            &languages::LanguageAliases::default(),
+        )
+        .unwrap();
+        assert_eq!(slides.len(), 1);
+        let (_, slides) =
+            parse_presentation("# Hi\n", &languages::LanguageAliases::default()).unwrap();
+        assert_eq!(slides.len(), 1);
+    }
+
+    #[test]
+    fn test_slide_lines() {
+        let (_, slides) = parse_presentation(
+            "---\ntitle: Talk\n---\n# A\n\n---\n\n\n# B\n",
+            &languages::LanguageAliases::default(),
+        )
+        .unwrap();
+        let lines: Vec<usize> = slides.iter().map(|slide| slide.line).collect();
+        assert_eq!(lines, [1, 4, 9]);
+    }
+
+    #[test]
+    fn test_slide_lines_are_those_of_the_file() {
+        let content = "# One\n<!-- exec_include: printf 'a\\nb\\nc\\n' -->\n---\n# Two\n";
+        let (expanded, origins, errors) = expand_includes("slides.md", content, false);
+        assert!(errors.is_empty());
+        let (_, slides) =
+            parse_expanded(&expanded, &origins, &languages::LanguageAliases::default()).unwrap();
+        assert_eq!(slides[1].line, 4);
+    }
+
+    #[test]
+    fn test_split_slides() {
+        let options = DeckOptions::default();
+        assert_eq!(
+            split_slides(
+                "# A\n---\n# B\n```yaml\n---\n```\n<!-- end_slide -->\n# C\n",
+                &options
+            ),
+            vec!["# A\n", "\n# B\n```yaml\n---\n```\n", "\n# C\n"]
+        );
+
+        let options = DeckOptions {
+            split_on_headings: true,
+            ..DeckOptions::default()
+        };
+        assert_eq!(
+            split_slides(
+                "<!-- theme: OneDark -->\n# A\ntext\n## B\n### C\n---\n\n# D\n",
+                &options
+            ),
+            vec![
+                "<!-- theme: OneDark -->\n# A\ntext\n",
+                "## B\n### C\n",
+                "\n\n# D\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_colors() {
+        let (metadata, _) =
+            parse_metadata("---\ncode_colors:\n  comment: \"#c0c0c0\"\n---\n").unwrap();
+        let theme = &Theme::builtin()[2];
+        let code = metadata.code_colors.apply(&theme.code);
+        assert_eq!(code.comment, Rgb(192, 192, 192));
+        assert_eq!(code.string, theme.code.string);
+        assert!(parse_metadata("---\ncode_colors:\n  coment: \"#c0c0c0\"\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_authors() {
+        let (metadata, _) = parse_metadata("---\nauthor: Ada\n---\n").unwrap();
+        assert_eq!(metadata.author, vec!["Ada"]);
+        let (metadata, _) = parse_metadata("---\nauthor: [Ada, Grace]\n---\n").unwrap();
+        assert_eq!(metadata.author, vec!["Ada", "Grace"]);
+    }
+
+    #[test]
+    fn test_parse_metadata_duration() {
+        let (metadata, _) = parse_metadata("---\nduration: 30m\n---\n").unwrap();
+        assert_eq!(metadata.duration, Some(Duration::from_secs(1800)));
+        assert_eq!(parse_metadata("# Slide").unwrap().0.duration, None);
+        assert!(parse_metadata("---\nduration: half an hour\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_without_front_matter() {
+        let (metadata, content) = parse_metadata("# Slide\nauthor: someone\n").unwrap();
+        assert!(metadata.author.is_empty());
+        assert_eq!(content, "# Slide\nauthor: someone\n");
+        assert!(parse_metadata("---\ntitle: [unclosed\n---\n").is_err());
+        assert!(parse_metadata("---\n---\n# Slide").is_ok());
+    }
+
+    #[test]
+    fn test_move_to_first_and_last_slide() {
+        let mut presentation = presentation(vec!["one\n<!-- pause -->\nmore", "two", "three"]);
+        presentation.move_to_last_slide();
+        assert_eq!(presentation.current_slide, 2);
+        presentation.move_to_first_slide();
+        assert_eq!(presentation.current_slide, 0);
+        assert_eq!(presentation.visible_content(), "one\n");
+    }
+
+    #[test]
+    fn test_loop_wraps_around() {
+        let mut presentation = presentation(vec!["one", "two", "<!-- hidden -->\nthree"]);
+        presentation.move_to_next_slide();
+        presentation.move_to_next_slide();
+        assert_eq!(presentation.current_slide, 1);
+        presentation.options.loop_slides = true;
+        presentation.move_to_next_slide();
+        assert_eq!(presentation.current_slide, 0);
+        presentation.move_backward();
+        assert_eq!(presentation.current_slide, 1);
+    }
+
+    #[test]
+    fn test_advance_after() {
+        let mut presentation = presentation(vec!["one", "<!-- duration: 30s -->\ntwo"]);
+        assert_eq!(presentation.advance_after(), None);
+        presentation.options.auto_advance = Some(Duration::from_secs(10));
+        assert_eq!(presentation.advance_after(), Some(Duration::from_secs(10)));
+        presentation.move_to_next_slide();
+        assert_eq!(presentation.advance_after(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_move_forward_reveals_pauses_before_next_slide() {
+        let mut presentation = presentation(vec!["- one\n<!-- pause -->\n- two\n", "second"]);
+        assert_eq!(presentation.visible_content(), "- one\n");
+        presentation.move_forward();
+        assert_eq!(
+            presentation.visible_content(),
+            "- one\n<!-- pause -->\n- two\n"
+        );
+        presentation.move_forward();
+        assert_eq!(presentation.visible_content(), "second");
+    }
+
+    #[test]
+    fn test_move_backward_shows_previous_slide_fully_revealed() {
+        let mut presentation = presentation(vec!["- one\n<!-- pause -->\n- two\n", "second"]);
+        presentation.move_to_next_slide();
+        presentation.move_backward();
+        assert_eq!(
+            presentation.visible_content(),
+            "- one\n<!-- pause -->\n- two\n"
+        );
+        presentation.move_backward();
+        assert_eq!(presentation.visible_content(), "- one\n");
+    }
+
+    #[test]
+    fn test_next_code_block_cycles_through_revealed_blocks() {
+        let slide = "```rust\none\n```\n```poll\nA?\n- a\n```\n```sh\ntwo\n```\n<!-- pause -->\n```\nthree\n```\n";
+        let mut presentation = presentation(vec![slide, "none"]);
+        assert_eq!(
+            presentation.next_code_block(),
+            Some((0, 2, "one".to_string()))
+        );
+        assert_eq!(
+            presentation.next_code_block(),
+            Some((1, 2, "two".to_string()))
+        );
+        assert_eq!(
+            presentation.next_code_block(),
+            Some((0, 2, "one".to_string()))
+        );
+        presentation.move_forward();
+        assert_eq!(
+            presentation.next_code_block(),
+            Some((0, 3, "one".to_string()))
+        );
+        presentation.move_forward();
+        assert_eq!(presentation.next_code_block(), None);
+    }
+
+    #[test]
+    fn test_read_only_runs_nothing_on_load() {
+        let content = "<!-- exec_include: echo included -->\n```sh +annotate\necho 1  # =>\n```\n";
+        let (expanded, _, errors) = expand_includes("slides.md", content, true);
+        assert_eq!((expanded.as_str(), errors), (content, vec![]));
+        let (expanded, _, _) = expand_includes("slides.md", content, false);
+        assert!(expanded.starts_with("included\n"));
+
+        let mut presentation = presentation(vec![content]);
+        presentation.options.read_only = true;
+        presentation.run_annotations();
+        let (_, block) =
+            &rendering::code_blocks(content, &presentation.options.language_aliases)[0];
+        assert_eq!(
+            presentation
+                .exec_cache
+                .output(&block.language, &block.content),
+            None
+        );
+    }
+
+    #[test]
+    fn test_move_forward_steps_through_highlighted_lines() {
+        let slide = "```rust {1|2-3}\na\nb\nc\n```\n<!-- pause -->\nmore\n";
+        let mut presentation = presentation(vec![slide, "second"]);
+        let aliases = languages::LanguageAliases::default();
+        let (_, block) = rendering::code_blocks(slide, &aliases).remove(0);
+        assert_eq!(presentation.highlight_step(&block), 0);
+        assert!(!presentation.visible_content().contains("more"));
+        presentation.move_forward();
+        assert_eq!(presentation.highlight_step(&block), 1);
+        assert!(!presentation.visible_content().contains("more"));
+        presentation.move_forward();
+        assert_eq!(presentation.visible_content(), slide);
+        presentation.move_forward();
+        assert_eq!(presentation.visible_content(), "second");
+        presentation.move_backward();
+        assert_eq!(presentation.highlight_step(&block), 1);
     }
-    let content_without_metadata = Regex::new(r"(?s)^---\n.*\n---\n")
-        .unwrap()
-        .replace(content, "");
-    (metadata, content_without_metadata.to_string())
 }
@@ -0,0 +1,169 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+use crate::width::{self, truncate};
+
+/// How long a notification stays in the corner before it is only found in the
+/// message log.
+pub const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+/// At most this many notifications are stacked in the corner at once.
+const MAX_VISIBLE: usize = 3;
+
+/// A notification wraps into at most this many rows before it is cut off.
+const MAX_LINES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Notification {
+    pub text: String,
+    pub severity: Severity,
+    pub created: Instant,
+}
+
+/// The part of the top-right corner covered by notifications.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    /// The column the longest notification starts at
+    pub left: u16,
+    pub rows: u16,
+}
+
+/// The notifications shown in the top-right corner together with the log of
+/// every message shown during the presentation.
+#[derive(Debug)]
+pub struct Notifications {
+    started: Instant,
+    log: Vec<Notification>,
+    pub show_log: bool,
+    /// Where the notifications were drawn last, to clear just that area once
+    /// they expire
+    drawn: Cell<Option<Area>>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications {
+            started: Instant::now(),
+            log: Vec::new(),
+            show_log: false,
+            drawn: Cell::new(None),
+        }
+    }
+}
+
+impl Notifications {
+    pub fn push(&mut self, text: impl Into<String>, severity: Severity) {
+        self.log.push(Notification {
+            text: text.into(),
+            severity,
+            created: Instant::now(),
+        });
+    }
+
+    /// The notifications that have not expired yet, newest first.
+    pub fn visible(&self, now: Instant) -> impl Iterator<Item = &Notification> {
+        self.log
+            .iter()
+            .rev()
+            .take_while(move |notification| {
+                now.duration_since(notification.created) < NOTIFICATION_DURATION
+            })
+            .take(MAX_VISIBLE)
+    }
+
+    pub fn log(&self) -> &[Notification] {
+        &self.log
+    }
+
+    /// The time since the start of the presentation at which a notification
+    /// was shown, as `mm:ss`.
+    pub fn timestamp(&self, notification: &Notification) -> String {
+        let seconds = notification.created.duration_since(self.started).as_secs();
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    pub fn drawn(&self) -> Option<Area> {
+        self.drawn.get()
+    }
+
+    pub fn set_drawn(&self, area: Option<Area>) {
+        self.drawn.set(area);
+    }
+
+    pub fn toggle_log(&mut self) {
+        self.show_log = !self.show_log;
+    }
+}
+
+/// Wraps the text of a notification into lines of at most `max_width`
+/// columns, cutting off what doesn't fit into `MAX_LINES` with an ellipsis.
+pub fn layout(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = width::wrap(text, max_width);
+    if lines.len() > MAX_LINES {
+        let rest = lines.split_off(MAX_LINES - 1).join(" ");
+        lines.push(truncate(&rest, max_width));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_notifications_are_stacked_newest_first() {
+        let mut notifications = Notifications::default();
+        for text in ["one", "two", "three", "four"] {
+            notifications.push(text, Severity::Info);
+        }
+        let now = Instant::now();
+        let visible: Vec<&str> = notifications
+            .visible(now)
+            .map(|notification| notification.text.as_str())
+            .collect();
+        assert_eq!(visible, vec!["four", "three", "two"]);
+        assert_eq!(notifications.log().len(), 4);
+    }
+
+    #[test]
+    fn test_expired_notifications_stay_in_the_log() {
+        let mut notifications = Notifications::default();
+        notifications.push("image failed to load", Severity::Warning);
+        let later = Instant::now() + NOTIFICATION_DURATION;
+        assert_eq!(notifications.visible(later).count(), 0);
+        assert_eq!(notifications.log()[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(layout("Theme: dark", 20), vec!["Theme: dark"]);
+        assert_eq!(
+            layout("Could not load images/architecture.png", 20),
+            vec!["Could not load", "images/architecture…"]
+        );
+        assert_eq!(
+            layout("one two three four five six", 9),
+            vec!["one two", "three fo…"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use crate::{images::Image, lists::parse_list_item, rendering};
+
+/// What a single newline within a paragraph does, set with `line_breaks`
+/// under `options` in the frontmatter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineBreaks {
+    /// Every line of the file starts a line on the slide
+    #[default]
+    Hard,
+    /// Lines of a paragraph or list item are joined and wrapped as a whole,
+    /// for decks written with a line per sentence
+    Join,
+}
+
+/// Joins the lines of paragraphs and list items with spaces. A line ending in
+/// two spaces or a backslash still breaks, and code, headings, tables, quotes,
+/// images and comments keep their lines.
+pub fn join_soft_breaks(text: &str) -> String {
+    let mut joined = String::with_capacity(text.len());
+    let mut in_code_block = false;
+    let mut open = false;
+    for line in text.split_inclusive('\n') {
+        let fence = line.trim_start().starts_with("```");
+        in_code_block ^= fence;
+        let content = line.trim_end_matches(['\n', '\r']);
+        if open && !fence && continues(content) {
+            // The newline of the joined line is the last character
+            joined.pop();
+            if joined.ends_with('\r') {
+                joined.pop();
+            }
+            joined.push(' ');
+            joined.push_str(line.trim_start());
+        } else {
+            joined.push_str(line);
+        }
+        open = !in_code_block && !fence && (continues(content) || is_list_item(content));
+        if content.ends_with('\\') && open {
+            // The backslash only asks for the break
+            let end = joined.trim_end_matches(['\n', '\r']).len();
+            joined.replace_range(end - 1..end, "");
+            open = false;
+        }
+        open &= !content.ends_with("  ") && line.ends_with('\n');
+    }
+    joined
+}
+
+/// Whether the line is plain text that can go on the line before it.
+fn continues(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    !trimmed.is_empty()
+        && !["#", ">", "|", "<!--", "[^", "---"]
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+        && !is_list_item(line)
+        && Image::parse(line).is_none()
+        && !rendering::is_horizontal_rule(line)
+}
+
+fn is_list_item(line: &str) -> bool {
+    parse_list_item(line).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_soft_breaks() {
+        assert_eq!(
+            join_soft_breaks("# Title\nOne sentence.\nAnother one.\n\nNext paragraph.\n"),
+            "# Title\nOne sentence. Another one.\n\nNext paragraph.\n"
+        );
+        assert_eq!(
+            join_soft_breaks("- item\n  goes on\n- next\n\nBreak  \nhere\\\nand\n"),
+            "- item goes on\n- next\n\nBreak  \nhere\nand\n"
+        );
+        let kept = "```\na\nb\n```\n| a |\n| b |\n> quote\n> more\n<!-- pause -->\ntext\n";
+        assert_eq!(join_soft_breaks(kept), kept);
+    }
+}
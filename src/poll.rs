@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use qrcode::{render::unicode::Dense1x2, QrCode};
+
+use crate::{languages::LanguageAliases, rendering, Slide};
+
+/// A question for the audience, written as a `poll` code block with the
+/// question on the first line and one `- ` line per answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poll {
+    pub question: String,
+    pub answers: Vec<String>,
+}
+
+impl Poll {
+    pub fn parse(content: &str) -> Option<Poll> {
+        let mut lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let question = lines.next()?.to_string();
+        let answers: Vec<String> = lines
+            .filter_map(|line| line.strip_prefix("- "))
+            .map(|answer| answer.trim().to_string())
+            .collect();
+        (!answers.is_empty()).then_some(Poll { question, answers })
+    }
+}
+
+/// The polls of a presentation by slide, at most one per slide.
+pub fn find_polls(slides: &[Slide], aliases: &LanguageAliases) -> BTreeMap<usize, Poll> {
+    let mut polls = BTreeMap::new();
+    for (index, slide) in slides.iter().enumerate() {
+        let poll = rendering::code_blocks(&slide.content, aliases)
+            .into_iter()
+            .filter(|(_, block)| block.language == "poll")
+            .find_map(|(_, block)| Poll::parse(&block.content));
+        if let Some(poll) = poll {
+            polls.insert(index, poll);
+        }
+    }
+    polls
+}
+
+/// The rows of a QR code for the URL, drawn with half blocks.
+pub fn qr_code(url: &str) -> Option<Vec<String>> {
+    let code = QrCode::new(url).ok()?;
+    let rendered = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    Some(rendered.lines().map(String::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_poll() {
+        let poll = Poll::parse("Favorite language?\n- Rust\n-  Go \n\n- Python\n").unwrap();
+        assert_eq!(poll.question, "Favorite language?");
+        assert_eq!(poll.answers, vec!["Rust", "Go", "Python"]);
+        assert_eq!(Poll::parse("Only a question\n"), None);
+    }
+}
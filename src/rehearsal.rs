@@ -0,0 +1,94 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use crate::cues::format_duration;
+
+/// Records how long each slide is shown while rehearsing with `--rehearse`.
+#[derive(Debug, Default)]
+pub struct Rehearsal {
+    /// The slide on screen and since when
+    current: Option<(usize, Instant)>,
+    /// The time spent on each slide so far, by slide index
+    times: BTreeMap<usize, Duration>,
+}
+
+impl Rehearsal {
+    /// Notes that the slide is on screen, which ends the time of the slide
+    /// shown before.
+    pub fn show(&mut self, slide: usize) {
+        self.show_at(slide, Instant::now());
+    }
+
+    /// Stops timing until a slide is shown again.
+    pub fn stop(&mut self) {
+        self.stop_at(Instant::now());
+    }
+
+    fn show_at(&mut self, slide: usize, now: Instant) {
+        if self.current.is_some_and(|(current, _)| current == slide) {
+            return;
+        }
+        self.stop_at(now);
+        self.current = Some((slide, now));
+    }
+
+    fn stop_at(&mut self, now: Instant) {
+        if let Some((slide, since)) = self.current.take() {
+            *self.times.entry(slide).or_default() += now - since;
+        }
+    }
+
+    /// Ends the rehearsal and lays out the time spent on every slide, with
+    /// the given titles, and in total.
+    pub fn finish(mut self, titles: &[Option<&str>]) -> String {
+        self.stop();
+        self.report(titles)
+    }
+
+    fn report(&self, titles: &[Option<&str>]) -> String {
+        let mut report = String::from("Slide   Time  Title\n");
+        for (index, title) in titles.iter().enumerate() {
+            let time = self.times.get(&index).copied().unwrap_or_default();
+            report.push_str(
+                format!(
+                    "{:>5} {:>6}  {}",
+                    index + 1,
+                    format_duration(time),
+                    title.unwrap_or_default()
+                )
+                .trim_end(),
+            );
+            report.push('\n');
+        }
+        let total: Duration = self.times.values().sum();
+        report.push_str(&format!("Total {:>6}\n", format_duration(total)));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut rehearsal = Rehearsal::default();
+        rehearsal.show_at(0, at(0));
+        rehearsal.show_at(0, at(10));
+        rehearsal.show_at(1, at(42));
+        rehearsal.show_at(0, at(100));
+        rehearsal.stop_at(at(110));
+        assert_eq!(
+            rehearsal.report(&[Some("Welcome"), Some("Ownership"), None]),
+            "Slide   Time  Title\n\
+             \x20   1   0:52  Welcome\n\
+             \x20   2   0:58  Ownership\n\
+             \x20   3   0:00\n\
+             Total   1:50\n"
+        );
+    }
+}
@@ -1,21 +1,45 @@
-use crate::{Presentation, Theme};
+use crate::{
+    admonitions::{Admonition, AdmonitionKind},
+    bigtext,
+    blockquotes::parse_quote_line,
+    colors::CodeColors,
+    config::{HorizontalPosition, NotesPane, VerticalPosition},
+    countdown::{Countdown, CountdownTimer},
+    cues::{self, CueTimer},
+    error::Error,
+    footnotes,
+    frames::{CachedFrame, FrameKey},
+    glyphs::Glyphs,
+    highlights::LineSelection,
+    images::{self, Align, Image, ImageAttributes, ImageOptions},
+    inline::{parse_inline, wrap_spans, Span},
+    languages::LanguageAliases,
+    layout::{self, ColumnLayout, Columns, Layout, Quote},
+    lists::{parse_list_item, ListItem, ListMarker, ListState},
+    notifications::{self, Area, Severity},
+    poll::{self, Poll},
+    search,
+    tables::{self, Alignment, Table},
+    width::{self, char_width, text_width},
+    Presentation, Theme,
+};
+use image::DynamicImage;
 use std::{
     fmt::Display,
-    io::{stdout, Write},
+    io::Write,
+    iter,
     ops::Add,
-    path::Path,
-    process, thread,
-    time::Duration,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
 };
 use streaming_iterator::StreamingIterator;
 use termion::{
     color::{self, Rgb},
-    cursor::{self, DetectCursorPos},
-    raw::IntoRawMode,
-    style, terminal_size,
+    cursor, screen, style, terminal_size,
 };
 use tree_sitter::{Language, Parser, Query};
-use viuer::{print_from_file, Config};
+use viuer::{print, print_from_file, Config};
 
 enum Header {
     Header1,
@@ -24,10 +48,269 @@ enum Header {
     Header4,
 }
 
+pub(crate) struct FullImage<'a> {
+    pub(crate) path: &'a str,
+    pub(crate) caption: Option<&'a str>,
+}
+
+/// A terminal area that is never drawn over, e.g. to overlay a webcam feed.
+#[derive(Debug, PartialEq)]
+struct ReservedRegion {
+    left: u16,
+    top: u16,
+    width: u16,
+    height: u16,
+}
+
+impl ReservedRegion {
+    /// Parses `<corner> <width>x<height>`, e.g. `bottom-right 40x12`.
+    fn parse(directive: &str, terminal_width: u16, terminal_height: u16) -> Option<Self> {
+        let (corner, size) = directive.split_once(' ')?;
+        let (width, height) = size.trim().split_once('x')?;
+        let width = width.parse::<u16>().ok()?.min(terminal_width);
+        let height = height.parse::<u16>().ok()?.min(terminal_height);
+        let left = match corner {
+            "top-left" | "bottom-left" => 1,
+            "top-right" | "bottom-right" => terminal_width - width + 1,
+            _ => return None,
+        };
+        let top = match corner {
+            "top-left" | "top-right" => 1,
+            _ => terminal_height - height + 1,
+        };
+        Some(ReservedRegion {
+            left,
+            top,
+            width,
+            height,
+        })
+    }
+
+    fn contains_row(&self, row: u16) -> bool {
+        row >= self.top && row < self.top + self.height
+    }
+
+    /// Returns the first and last column that may be drawn on in `row`.
+    fn free_columns(&self, row: u16, terminal_width: u16) -> (u16, u16) {
+        if !self.contains_row(row) {
+            (1, terminal_width)
+        } else if self.left == 1 {
+            (self.width + 1, terminal_width)
+        } else {
+            (1, self.left - 1)
+        }
+    }
+
+    fn clear(&self, stdout: &mut impl Write) -> Result<(), Error> {
+        for row in self.top..self.top + self.height {
+            write!(
+                stdout,
+                "{}{}",
+                goto(self.left, row),
+                " ".repeat(self.width as usize)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Columns and rows everything is moved by, e.g. to keep the slides clear of
+/// the parts of a projection screen that are cut off.
+static STAGE_OFFSET: Mutex<(u16, u16)> = Mutex::new((0, 0));
+
+pub fn set_stage_offset(columns: u16, rows: u16) {
+    *STAGE_OFFSET.lock().unwrap() = (columns, rows);
+}
+
+/// Moves the cursor to a position on the stage, i.e. the terminal without the
+/// stage offset.
+fn goto(column: u16, row: u16) -> cursor::Goto {
+    let (columns, rows) = *STAGE_OFFSET.lock().unwrap();
+    cursor::Goto(column + columns, row + rows)
+}
+
+/// The terminal size the slides are laid out for instead of the actual one,
+/// when checking that they fit.
+static LAYOUT_SIZE: Mutex<Option<(u16, u16)>> = Mutex::new(None);
+
+pub(crate) fn set_layout_size(size: Option<(u16, u16)>) {
+    *LAYOUT_SIZE.lock().unwrap() = size;
+}
+
+/// The size of the terminal without the stage offset.
+fn stage_size() -> Result<(u16, u16), Error> {
+    let layout_size = *LAYOUT_SIZE.lock().unwrap();
+    let (width, height) = match layout_size {
+        Some(size) => size,
+        None => terminal_size()?,
+    };
+    let (columns, rows) = *STAGE_OFFSET.lock().unwrap();
+    Ok((
+        width.saturating_sub(columns).max(1),
+        height.saturating_sub(rows).max(1),
+    ))
+}
+
+/// The smallest stage the slides are laid out on, below it the terminal only
+/// asks to be enlarged.
+pub const MIN_STAGE_SIZE: (u16, u16) = (40, 10);
+
+/// The request to enlarge the terminal when the stage is smaller than
+/// `MIN_STAGE_SIZE`.
+fn enlarge_message((width, height): (u16, u16)) -> Option<String> {
+    let (min_width, min_height) = MIN_STAGE_SIZE;
+    (width < min_width || height < min_height).then(|| {
+        format!(
+            "Please enlarge the terminal to at least {}x{} (now {}x{})",
+            min_width, min_height, width, height
+        )
+    })
+}
+
+/// Whether the stage is too small to lay out the slides on.
+pub fn too_small() -> Result<bool, Error> {
+    Ok(enlarge_message(stage_size()?).is_some())
+}
+
+/// Asks for a larger terminal in the middle of the stage, instead of a slide
+/// that doesn't fit. The slide comes back with the next resize that is large
+/// enough.
+pub fn render_too_small(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    write!(
+        stdout,
+        "{}{}{}",
+        cursor::Hide,
+        termion::clear::All,
+        goto(1, 1)
+    )?;
+    presentation.notifications.set_drawn(None);
+    let message = enlarge_message((width, height)).unwrap_or_default();
+    let lines = width::wrap(&message, width as usize);
+    let top = (height as usize).saturating_sub(lines.len()) / 2 + 1;
+    write!(
+        stdout,
+        "{}",
+        color::Fg(presentation.current_theme().get_theme_colors().accent)
+    )?;
+    for (index, line) in lines.iter().enumerate().take(height as usize) {
+        let left = (width as usize).saturating_sub(text_width(line)) / 2 + 1;
+        write!(
+            stdout,
+            "{}{}",
+            goto(left as u16, (top + index) as u16),
+            line
+        )?;
+    }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// The columns a block of content is laid out in, around the reserved region.
+#[derive(Clone, Copy)]
+struct Frame<'a> {
+    left: u16,
+    right: u16,
+    /// The last row content is drawn in, above the footer
+    bottom: u16,
+    reserved: Option<&'a ReservedRegion>,
+    /// The size of the stage the frame is on
+    stage: (u16, u16),
+    /// Where lines of text and blocks go between the columns
+    alignment: Alignment,
+}
+
+impl<'a> Frame<'a> {
+    /// The columns of the stage between the margins of the presentation.
+    fn stage(
+        presentation: &Presentation,
+        reserved: Option<&'a ReservedRegion>,
+    ) -> Result<Self, Error> {
+        let (width, height) = stage_size()?;
+        let (left, right) = presentation.stage_columns(width);
+        Ok(Frame {
+            left,
+            right,
+            bottom: height.saturating_sub(2),
+            reserved,
+            stage: (width, height),
+            alignment: Alignment::Left,
+        })
+    }
+
+    /// The rows of this frame above the footnotes, which take up the bottom
+    /// of it below a rule.
+    fn above_footnotes(&self, notes: usize) -> Self {
+        let footnote_rows = match notes {
+            0 => 0,
+            count => count as u16 + 1,
+        };
+        Frame {
+            bottom: self.bottom.saturating_sub(footnote_rows),
+            ..*self
+        }
+    }
+
+    /// The two columns of a layout like `two_column` within this frame of the
+    /// stage, two columns apart and split in the middle.
+    fn split(&self, stage: &Frame) -> [Self; 2] {
+        let middle = (stage.left + stage.right) / 2;
+        [
+            self.columns(stage.left, middle.saturating_sub(1)),
+            self.columns(middle + 2, stage.right),
+        ]
+    }
+
+    /// Whether the frame spans the whole stage with its text left-aligned.
+    fn is_stage(&self) -> bool {
+        self.left == 1 && self.right == self.stage.0 && self.alignment == Alignment::Left
+    }
+
+    /// The columns a block of the given width is drawn in, placed by the
+    /// alignment, with the lines inside of the block left-aligned.
+    fn block(&self, width: u16) -> Self {
+        let available = (self.right + 1).saturating_sub(self.left) as usize;
+        let (before, _) = self.alignment.padding(width as usize, available);
+        Frame {
+            alignment: Alignment::Left,
+            ..self.columns(self.left + before as u16, self.right)
+        }
+    }
+
+    /// The columns of this frame without a sixth of its width on either side.
+    fn middle_third(&self) -> Self {
+        let sixth = (self.right + 1 - self.left) / 6;
+        self.columns(self.left + sixth, self.right - sixth)
+    }
+
+    /// The columns from `left` to `right` within this frame.
+    fn columns(&self, left: u16, right: u16) -> Self {
+        Frame {
+            left: left.max(self.left),
+            right: right.min(self.right).max(left),
+            ..*self
+        }
+    }
+}
+
+fn free_columns(frame: &Frame, row: u16) -> (u16, u16) {
+    let (width, _) = frame.stage;
+    let (start, end) = match frame.reserved {
+        Some(reserved) => reserved.free_columns(row, width),
+        None => (1, width),
+    };
+    let start = start.max(frame.left);
+    (start, end.min(frame.right).max(start))
+}
+
 #[derive(Clone)]
-struct CodeBlock {
-    language: String,
-    content: String,
+pub(crate) struct CodeBlock {
+    pub(crate) language: String,
+    pub(crate) attributes: Vec<String>,
+    /// The lines to emphasize as written between the braces
+    pub(crate) highlight: Option<String>,
+    pub(crate) content: String,
 }
 
 #[derive(Debug)]
@@ -53,26 +336,26 @@ enum SyntaxKind {
 }
 
 impl SyntaxKind {
-    fn color(&self, theme: &Theme) -> Rgb {
+    fn color(&self, theme: &Theme, code: &CodeColors) -> Rgb {
         match self {
             SyntaxKind::Keyword => theme.get_theme_colors().primary,
-            SyntaxKind::Conditional => Rgb(247, 118, 142),
-            SyntaxKind::Constant => Rgb(217, 118, 142),
-            SyntaxKind::Repeat => Rgb(117, 118, 142),
-            SyntaxKind::Modifier => Rgb(187, 118, 142),
-            SyntaxKind::Delimiter => Rgb(155, 118, 142),
-            SyntaxKind::Bracket => Rgb(247, 158, 142),
+            SyntaxKind::Conditional => code.conditional,
+            SyntaxKind::Constant => code.constant,
+            SyntaxKind::Repeat => code.repeat,
+            SyntaxKind::Modifier => code.modifier,
+            SyntaxKind::Delimiter => code.delimiter,
+            SyntaxKind::Bracket => code.bracket,
             SyntaxKind::Function => theme.get_theme_colors().secondary,
-            SyntaxKind::Spell => Rgb(158, 186, 106),
+            SyntaxKind::Spell => code.spell,
             SyntaxKind::Type => theme.get_theme_colors().tertiary,
-            SyntaxKind::String => Rgb(158, 206, 106),
-            SyntaxKind::KeywordType => Rgb(28, 206, 106),
-            SyntaxKind::Number => Rgb(247, 118, 142),
-            SyntaxKind::Comment => Rgb(150, 150, 150),
+            SyntaxKind::String => code.string,
+            SyntaxKind::KeywordType => code.keyword_type,
+            SyntaxKind::Number => code.number,
+            SyntaxKind::Comment => code.comment,
             SyntaxKind::Variable => theme.get_theme_colors().accent,
-            SyntaxKind::Parameter => Rgb(224, 175, 104),
-            SyntaxKind::Operator => Rgb(187, 154, 247),
-            SyntaxKind::Default => Rgb(255, 255, 255),
+            SyntaxKind::Parameter => code.parameter,
+            SyntaxKind::Operator => code.operator,
+            SyntaxKind::Default => code.default,
         }
     }
 }
@@ -83,7 +366,7 @@ struct SyntaxToken {
     end: usize,
 }
 
-fn get_language_config(lang: &str) -> Option<(Language, &'static str)> {
+pub(crate) fn get_language_config(lang: &str) -> Option<(Language, &'static str)> {
     match lang {
         "rust" => Some((
             tree_sitter_rust::LANGUAGE.into(),
@@ -101,11 +384,7 @@ fn get_language_config(lang: &str) -> Option<(Language, &'static str)> {
     }
 }
 
-fn parse_syntax(
-    content: &str,
-    language: &str,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
-) -> Vec<SyntaxToken> {
+fn parse_syntax(content: &str, language: &str) -> Result<Vec<SyntaxToken>, Error> {
     let mut tokens = Vec::new();
 
     if let Some((lang, query_source)) = get_language_config(language) {
@@ -114,22 +393,11 @@ fn parse_syntax(
 
         let tree = match parser.parse(content, None) {
             Some(tree) => tree,
-            None => return Vec::new(),
+            None => return Ok(Vec::new()),
         };
 
-        let query = match Query::new(&lang, query_source) {
-            Ok(query) => query,
-            Err(e) => {
-                write!(
-                    stdout,
-                    "Error parsing query for language {}: {:?}",
-                    language, e
-                )
-                .unwrap();
-                stdout.flush().unwrap();
-                process::exit(1);
-            }
-        };
+        let query = Query::new(&lang, query_source)
+            .map_err(|err| Error::Query(language.to_string(), err.to_string()))?;
 
         let mut query_cursor = tree_sitter::QueryCursor::new();
         let mut matches = query_cursor.matches(&query, tree.root_node(), content.as_bytes());
@@ -172,28 +440,134 @@ fn parse_syntax(
     }
 
     tokens.sort_by_key(|t| t.start);
-    tokens
+    Ok(tokens)
 }
 
 impl CodeBlock {
-    fn parse(text: &str) -> Option<Self> {
+    pub(crate) fn parse(text: &str, aliases: &LanguageAliases) -> Option<Self> {
         let mut lines = text.lines();
         let first_line = lines.next()?;
+        // Fences can be indented to nest the block in a list item, its lines
+        // lose as much of their indentation
+        let indent = first_line.chars().take_while(|c| c.is_whitespace()).count();
 
-        if !first_line.starts_with("```") {
+        if !first_line.trim_start().starts_with("```") {
             return None;
         }
 
-        let language = first_line.trim_start_matches('`').trim().to_string();
+        let info = first_line.trim_start().trim_start_matches('`');
+        // The lines to highlight are given in braces, which may contain spaces
+        let (info, highlight) = match (info.find('{'), info.rfind('}')) {
+            (Some(start), Some(end)) if start < end => (
+                format!("{} {}", &info[..start], &info[end + 1..]),
+                Some(info[start + 1..end].to_string()),
+            ),
+            _ => (info.to_string(), None),
+        };
+        // Flags can follow the language after commas, as in `rust,linenos`
+        let mut info = info.split_whitespace();
+        let mut label = info.next().unwrap_or_default().split(',');
+        let language = aliases.normalize(label.next().unwrap_or_default());
+        let attributes = label.chain(info).map(String::from).collect();
         let content = text
             .lines()
             .skip(1)
-            .take_while(|line| !line.starts_with("```"))
+            .take_while(|line| !line.trim_start().starts_with("```"))
+            .map(|line| {
+                let removed: usize = line
+                    .chars()
+                    .take(indent)
+                    .take_while(|c| c.is_whitespace())
+                    .map(char::len_utf8)
+                    .sum();
+                &line[removed..]
+            })
             .collect::<Vec<_>>()
             .join("\n");
 
-        Some(CodeBlock { language, content })
+        Some(CodeBlock {
+            language,
+            attributes,
+            highlight,
+            content,
+        })
+    }
+
+    /// The columns taken up from the left edge of the frame on: the indent and
+    /// the longest of the language and the lines behind the gutter.
+    pub(crate) fn width(&self, tab_width: usize, gutter: usize) -> u16 {
+        let widest = expand_tabs(&self.content, tab_width)
+            .lines()
+            .map(|line| gutter + text_width(line))
+            .chain([text_width(&self.language)])
+            .max()
+            .unwrap_or_default();
+        (3 + widest).min(u16::MAX as usize) as u16
+    }
+
+    /// The columns of the line numbers and the space behind them. Lines are
+    /// numbered with a `linenos` flag, or by default unless `nolinenos` is
+    /// given.
+    pub(crate) fn gutter(&self, line_numbers: bool) -> usize {
+        let flag = |name: &str| self.attributes.iter().any(|attribute| attribute == name);
+        match (line_numbers && !flag("nolinenos")) || flag("linenos") {
+            true => self.content.lines().count().max(1).to_string().len() + 1,
+            false => 0,
+        }
+    }
+
+    /// The groups of lines to emphasize one after the other, if any are
+    /// given. Those that can't be understood or lie past the end of the block
+    /// are an error.
+    pub(crate) fn highlight_steps(&self) -> Result<Option<Vec<LineSelection>>, String> {
+        let Some(spec) = &self.highlight else {
+            return Ok(None);
+        };
+        let steps = LineSelection::parse_steps(spec).ok_or(format!(
+            "Invalid line highlight '{{{}}}', expected lines and ranges like {{3,5-7|9}}",
+            spec
+        ))?;
+        let last = steps.iter().map(LineSelection::last).max().unwrap_or(0);
+        let count = self.content.lines().count();
+        match last > count {
+            true => Err(format!(
+                "Line {} is highlighted, but the code block ends at line {}",
+                last, count
+            )),
+            false => Ok(Some(steps)),
+        }
+    }
+
+    pub(crate) fn is_executable(&self) -> bool {
+        self.attributes.iter().any(|attribute| attribute == "+exec")
+    }
+
+    /// Annotated blocks are run when loading the presentation and each output
+    /// line is appended to the next line ending in a `# =>` or `// =>` marker.
+    pub(crate) fn is_annotated(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute == "+annotate")
+    }
+}
+
+/// Returns the code blocks of a slide along with the index of their opening
+/// fence line.
+pub(crate) fn code_blocks(slide: &str, aliases: &LanguageAliases) -> Vec<(usize, CodeBlock)> {
+    let lines: Vec<&str> = slide.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim_start().starts_with("```") {
+            if let Some(block) = CodeBlock::parse(&lines[i..].join("\n"), aliases) {
+                let start = i;
+                i += block.content.lines().count() + 1;
+                blocks.push((start, block));
+            }
+        }
+        i += 1;
     }
+    blocks
 }
 
 impl Header {
@@ -217,143 +591,1896 @@ impl Header {
     }
 }
 
-pub fn render_slide(
+pub fn render_slide(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    set_background(stdout, presentation.current_background())?;
+    write!(
+        stdout,
+        "{}{}{}",
+        cursor::Hide,
+        termion::clear::All,
+        goto(1, 1)
+    )?;
+    presentation.notifications.set_drawn(None);
+    presentation.overflowing.set(false);
+    let countdown = presentation.current_countdown();
+    if countdown.is_none() {
+        presentation.countdown.set(None);
+    }
+    if let Some(text) = &presentation.large_type {
+        render_large_type(text, stdout, presentation)?;
+        render_notifications(presentation, stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    if has_directive(presentation.current_slide(), "title_slide") {
+        render_title_slide(presentation, stdout)?;
+        render_notifications(presentation, stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    if let Some(countdown) = countdown {
+        render_countdown(presentation, &countdown, stdout)?;
+        render_notifications(presentation, stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    if let Some(full_image) = extract_full_image(presentation.current_slide()) {
+        render_full_image(presentation, &full_image, stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    if let Some(background) = extract_directive(presentation.current_slide(), "background_image") {
+        render_background_image(presentation, background)?;
+    }
+    let reserved = reserved_region(presentation)?;
+    let reserved = reserved.as_ref();
+    let visible: Vec<&str> = presentation.visible_content().lines().collect();
+    let footnoted = footnotes::extract_footnotes(&visible);
+    let lines: Vec<&str> = footnoted.lines.iter().map(String::as_str).collect();
+    let page = Frame::stage(presentation, reserved)?;
+    let stage = page.above_footnotes(footnoted.notes.len());
+    match frame_key(presentation)? {
+        Some(key) => match presentation.frames.get(&key) {
+            Some(frame) => {
+                stdout.write_all(&frame.output)?;
+                presentation.overflowing.set(frame.overflowing);
+            }
+            None => {
+                let mut output = Vec::new();
+                render_body(
+                    presentation,
+                    (&lines, &footnoted.notes),
+                    (page, stage),
+                    &mut output,
+                )?;
+                stdout.write_all(&output)?;
+                let overflowing = presentation.overflowing.get();
+                presentation.frames.insert(
+                    key,
+                    CachedFrame {
+                        output,
+                        overflowing,
+                    },
+                );
+            }
+        },
+        // Drawn in place, as images have to go straight to the terminal
+        None => render_body(
+            presentation,
+            (&lines, &footnoted.notes),
+            (page, stage),
+            stdout,
+        )?,
+    }
+    render_footer(presentation, &stage, stdout)?;
+    if presentation.options.lightning.is_some() {
+        render_slide_countdown(presentation, stdout)?;
+    }
+    if let Some(reserved) = reserved {
+        reserved.clear(stdout)?;
+    }
+    render_notifications(presentation, stdout)?;
+    if presentation.show_questions {
+        render_questions(presentation, stdout)?;
+    }
+    if presentation.notifications.show_log {
+        render_message_log(presentation, stdout)?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// The region the current slide keeps clear with a `reserve` directive.
+fn reserved_region(presentation: &Presentation) -> Result<Option<ReservedRegion>, Error> {
+    let Some(directive) = extract_directive(presentation.current_slide(), "reserve") else {
+        return Ok(None);
+    };
+    let (width, height) = stage_size()?;
+    Ok(ReservedRegion::parse(directive, width, height))
+}
+
+/// Draws the header, the content, the footnotes and the notes of the slide:
+/// all that stays the same while the slide is shown.
+fn render_body(
     presentation: &Presentation,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
-) {
-    write!(stdout, "{}{}", termion::clear::All, cursor::Goto(1, 1)).unwrap();
-    render_text_centered(
-        presentation
-            .metadata
-            .title
-            .as_ref()
-            .unwrap_or(&String::from("No title found")),
-        false,
+    (lines, notes): (&[&str], &[String]),
+    (page, stage): (Frame, Frame),
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let layout = extract_directive(presentation.current_slide(), "layout").and_then(Layout::parse);
+    let (alignment, vertical_alignment) = presentation.alignment();
+    let content = Frame { alignment, ..stage };
+    if draws_header(presentation) {
+        render_header(presentation, &stage, stdout)?;
+    }
+    let big_text = has_directive(presentation.current_slide(), "big_text");
+    let (_, height) = stage_size()?;
+    let quote = layout::parse_quote(lines);
+    let column_layout = extract_directive(presentation.current_slide(), "column_layout")
+        .and_then(ColumnLayout::parse)
+        .and_then(|column_layout| Some((column_layout.split(lines).ok()?, column_layout)));
+    match layout {
+        _ if quote.is_some() => render_quote(presentation, &quote.unwrap(), &stage, stdout)?,
+        None if column_layout.is_some() => {
+            let (columns, column_layout) = column_layout.unwrap();
+            render_columns(
+                presentation,
+                (&columns, &column_layout),
+                &content,
+                big_text,
+                stdout,
+            )?;
+        }
+        None => {
+            let viewport = Viewport::new(lines, presentation.scrolled());
+            let (rows, _) = content_size(presentation, viewport.lines, &content, big_text);
+            let top = vertical_alignment.top(rows, (4, stage.bottom));
+            let end = render_content(
+                presentation,
+                viewport.lines,
+                top,
+                &content,
+                big_text,
+                stdout,
+            )?;
+            let overflowing = end > stage.bottom + 1;
+            presentation.overflowing.set(overflowing);
+            viewport.render_indicators(overflowing, &stage, presentation, stdout)?;
+        }
+        Some(layout) if layout.is_centered() => {
+            let frame = match layout {
+                Layout::Quote => stage.middle_third(),
+                _ => stage,
+            };
+            let frame = Frame {
+                alignment: Alignment::Center,
+                ..frame
+            };
+            let big_text = big_text || layout == Layout::Section;
+            let (rows, _) = content_size(presentation, lines, &frame, big_text);
+            let top = height.saturating_sub(rows) / 2 + 1;
+            render_content(presentation, lines, top, &frame, big_text, stdout)?;
+        }
+        Some(layout) => {
+            let (heading, body) = layout::split_heading(lines);
+            let top = render_content(presentation, heading, 4, &content, big_text, stdout)?;
+            let (left, right) = layout::split_columns(layout, body);
+            for (lines, frame) in [left, right].into_iter().zip(content.split(&stage)) {
+                render_content(presentation, &lines, top, &frame, big_text, stdout)?;
+            }
+        }
+    }
+    if !notes.is_empty() {
+        render_footnotes(notes, &page, presentation, stdout)?;
+    }
+    if presentation.show_notes {
+        if let Some(notes) = presentation.current_notes() {
+            render_notes(
+                notes,
+                presentation.panes.notes,
+                stdout,
+                presentation.current_theme(),
+                &presentation.glyphs(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// How far a slide reaches past the edges of the stage.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Overflow {
+    pub(crate) rows: usize,
+    pub(crate) columns: usize,
+}
+
+/// Lays out the current slide like `render_slide` and returns how far it
+/// reaches past the footer and the sides of its frames. Images and the output
+/// of executed snippets are not known up front and take up a row.
+pub(crate) fn measure_overflow(presentation: &Presentation) -> Result<Overflow, Error> {
+    let slide = presentation.current_slide();
+    // These slides are drawn to fit the whole stage
+    if has_directive(slide, "title_slide")
+        || presentation.current_countdown().is_some()
+        || extract_full_image(slide).is_some()
+    {
+        return Ok(Overflow::default());
+    }
+    let reserved = reserved_region(presentation)?;
+    let visible: Vec<&str> = presentation.visible_content().lines().collect();
+    let footnoted = footnotes::extract_footnotes(&visible);
+    let lines: Vec<&str> = footnoted.lines.iter().map(String::as_str).collect();
+    let stage =
+        Frame::stage(presentation, reserved.as_ref())?.above_footnotes(footnoted.notes.len());
+
+    let layout = extract_directive(slide, "layout").and_then(Layout::parse);
+    let (alignment, vertical_alignment) = presentation.alignment();
+    let content = Frame { alignment, ..stage };
+    let big_text = has_directive(slide, "big_text");
+    let (_, height) = stage.stage;
+    let quote = layout::parse_quote(&lines);
+    let column_layout = extract_directive(slide, "column_layout")
+        .and_then(ColumnLayout::parse)
+        .and_then(|column_layout| Some((column_layout.split(&lines).ok()?, column_layout)));
+    let mut columns = 0;
+    let mut rows = |lines: &[&str], frame: &Frame, big_text: bool| {
+        let (rows, excess) = content_size(presentation, lines, frame, big_text);
+        columns = columns.max(excess);
+        rows
+    };
+    // The row below the last one drawn, as returned by `render_content`
+    let end = match layout {
+        _ if quote.is_some() => {
+            let rows = quote_rows(presentation, &quote.unwrap(), &stage.middle_third()).len();
+            let top = (height as usize).saturating_sub(rows) / 2 + 1;
+            (top + rows).min(u16::MAX as usize) as u16
+        }
+        None if column_layout.is_some() => {
+            let (split, column_layout) = column_layout.unwrap();
+            let top = 4 + rows(&split.above, &content, big_text);
+            let bottom = split
+                .columns
+                .iter()
+                .zip(column_layout.bounds((content.left, content.right), 2))
+                .map(|(lines, (left, right))| {
+                    top + rows(lines, &content.columns(left, right), big_text)
+                })
+                .fold(top, u16::max);
+            bottom + rows(&split.below, &content, big_text)
+        }
+        None => {
+            let content_rows = rows(&lines, &content, big_text);
+            vertical_alignment.top(content_rows, (4, stage.bottom)) + content_rows
+        }
+        Some(layout) if layout.is_centered() => {
+            let frame = match layout {
+                Layout::Quote => stage.middle_third(),
+                _ => stage,
+            };
+            let frame = Frame {
+                alignment: Alignment::Center,
+                ..frame
+            };
+            let big_text = big_text || layout == Layout::Section;
+            let content_rows = rows(&lines, &frame, big_text);
+            height.saturating_sub(content_rows) / 2 + 1 + content_rows
+        }
+        Some(layout) => {
+            let (heading, body) = layout::split_heading(&lines);
+            let top = 4 + rows(heading, &content, big_text);
+            let (left, right) = layout::split_columns(layout, body);
+            [left, right]
+                .into_iter()
+                .zip(content.split(&stage))
+                .map(|(lines, frame)| top + rows(&lines, &frame, big_text))
+                .fold(top, u16::max)
+        }
+    };
+    Ok(Overflow {
+        rows: end.saturating_sub(stage.bottom + 1) as usize,
+        columns,
+    })
+}
+
+/// What the current slide is drawn for, unless it can't be drawn from the
+/// cache: images are drawn straight to the terminal and poll results change
+/// with every vote.
+fn frame_key(presentation: &Presentation) -> Result<Option<FrameKey>, Error> {
+    let content = presentation.current_slide();
+    let cacheable = !content.lines().any(|line| Image::parse(line).is_some())
+        && extract_directive(content, "background_image").is_none()
+        && !code_blocks(content, &presentation.options.language_aliases)
+            .iter()
+            .any(|(_, block)| block.language == "poll");
+    let size = stage_size()?;
+    Ok(cacheable.then(|| FrameKey {
+        slide: presentation.current_slide,
+        theme: presentation.current_theme().name.clone(),
+        size,
+        revealed_steps: presentation.revealed_steps,
+        scrolled: presentation.scrolled(),
+        search: presentation.search.clone(),
+        show_notes: presentation.show_notes,
+    }))
+}
+
+/// The part of a slide's content on screen when it is scrolled: the blocks
+/// scrolled past are left out, a block being a line of text, a code block or a
+/// table.
+struct Viewport<'a, 'b> {
+    lines: &'b [&'a str],
+    scrolled: bool,
+}
+
+impl<'a, 'b> Viewport<'a, 'b> {
+    fn new(lines: &'b [&'a str], blocks: usize) -> Self {
+        let starts = block_starts(lines);
+        // Trailing blank lines don't make the slide overflow
+        let end = lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty() && !is_comment(line))
+            .map_or(0, |last| last + 1);
+        match starts.get(blocks.min(starts.len().saturating_sub(1))) {
+            Some(&start) if blocks > 0 => Viewport {
+                lines: &lines[start..end],
+                scrolled: true,
+            },
+            _ => Viewport {
+                lines: &lines[..end],
+                scrolled: false,
+            },
+        }
+    }
+
+    /// Marks the last column of the first and last row of content when there
+    /// is more content above or below.
+    fn render_indicators(
+        &self,
+        overflowing: bool,
+        frame: &Frame,
+        presentation: &Presentation,
+        stdout: &mut impl Write,
+    ) -> Result<(), Error> {
+        let color = presentation.current_theme().get_theme_colors().accent;
+        let [up, down] = presentation.glyphs().scroll;
+        for (shown, indicator, row) in [(self.scrolled, up, 4), (overflowing, down, frame.bottom)] {
+            if shown {
+                let (_, end) = free_columns(frame, row);
+                write!(
+                    stdout,
+                    "{}{}{}{}",
+                    goto(end, row),
+                    color::Fg(color),
+                    indicator,
+                    color::Fg(color::Reset)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The lines that blocks of content start at: every line with text, except
+/// for the lines inside of code blocks and the rows of tables after the first.
+fn block_starts(lines: &[&str]) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut in_code_block = false;
+    for (index, line) in lines.iter().enumerate() {
+        let table_row = |line: &str| line.trim_start().starts_with('|');
+        let continues_table = index > 0 && table_row(line) && table_row(lines[index - 1]);
+        if !in_code_block && !continues_table && !line.trim().is_empty() && !is_comment(line) {
+            starts.push(index);
+        }
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+    }
+    starts
+}
+
+/// Whether the title and subtitle of the presentation are drawn above the
+/// slide, which they are not on centered slides.
+fn draws_header(presentation: &Presentation) -> bool {
+    !presentation.metadata.has_title_slide() && !is_centered(presentation)
+}
+
+/// Whether the slide is centered on the stage by its layout or as a quote.
+fn is_centered(presentation: &Presentation) -> bool {
+    let lines: Vec<&str> = presentation.visible_content().lines().collect();
+    extract_directive(presentation.current_slide(), "layout")
+        .and_then(Layout::parse)
+        .is_some_and(|layout| layout.is_centered())
+        || layout::parse_quote(&lines).is_some()
+}
+
+/// Draws the footnotes of a slide dimmed at the bottom of the frame, below a
+/// short rule.
+fn render_footnotes(
+    notes: &[String],
+    frame: &Frame,
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let dimmed = format!("{}{}", color::Fg(color::Reset), style::Faint);
+    let top = (frame.bottom + 1).saturating_sub(notes.len() as u16);
+    let (start, end) = free_columns(frame, top - 1);
+    let rule = presentation
+        .glyphs()
+        .horizontal(((end + 1).saturating_sub(start) as usize).min(8));
+    write!(
         stdout,
-        presentation.current_theme().get_theme_colors().primary,
-    );
-    render_text_centered(
-        presentation
-            .metadata
-            .subtitle
-            .as_ref()
-            .unwrap_or(&String::from("No subtitle found")),
-        false,
+        "{}{}{}{}",
+        goto(start, top - 1),
+        dimmed,
+        rule,
+        style::Reset
+    )?;
+    for (index, note) in notes.iter().enumerate() {
+        let row = top + index as u16;
+        let (start, end) = free_columns(frame, row);
+        let text = format!("{} {}", footnotes::superscript(index + 1), note);
+        write!(stdout, "{}", goto(start, row))?;
+        render_spans(
+            &parse_inline(&text),
+            &dimmed,
+            false,
+            (end + 1).saturating_sub(start) as usize,
+            presentation.current_theme(),
+            presentation.search.as_deref(),
+            stdout,
+        )?;
+    }
+    Ok(())
+}
+
+/// Draws the content of a slide with a column layout, each column in its own
+/// part of the stage below the content above them.
+fn render_columns(
+    presentation: &Presentation,
+    (columns, column_layout): (&Columns, &ColumnLayout),
+    frame: &Frame,
+    big_text: bool,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let top = render_content(presentation, &columns.above, 4, frame, big_text, stdout)?;
+    let mut bottom = top;
+    for (lines, (left, right)) in columns
+        .columns
+        .iter()
+        .zip(column_layout.bounds((frame.left, frame.right), 2))
+    {
+        let column = frame.columns(left, right);
+        let end = render_content(presentation, lines, top, &column, big_text, stdout)?;
+        bottom = bottom.max(end);
+    }
+    render_content(
+        presentation,
+        &columns.below,
+        bottom,
+        frame,
+        big_text,
         stdout,
-        presentation.current_theme().get_theme_colors().primary,
-    );
-    let lines: Vec<&str> = presentation.current_slide().lines().collect();
+    )?;
+    Ok(())
+}
+
+/// Draws a slide of a quote: the quote in italics between curly quotes,
+/// centered in the middle of the stage, and who said it after an em dash.
+fn render_quote(
+    presentation: &Presentation,
+    quote: &Quote,
+    stage: &Frame,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let (_, height) = stage_size()?;
+    let frame = stage.middle_third();
+    let rows = quote_rows(presentation, quote, &frame);
+    let top = (height as usize).saturating_sub(rows.len()) / 2 + 1;
+    for (index, (line, color)) in rows.iter().enumerate() {
+        let row = (top + index) as u16;
+        let (start, end) = free_columns(&frame, row);
+        let available = (end + 1 - start) as usize;
+        let line_width: usize = line.iter().map(|span| text_width(&span.text)).sum();
+        let padding = available.saturating_sub(line_width) / 2;
+        write!(stdout, "{}", goto(start + padding as u16, row))?;
+        render_spans(
+            line,
+            &color::Fg(*color),
+            false,
+            available,
+            presentation.current_theme(),
+            presentation.search.as_deref(),
+            stdout,
+        )?;
+    }
+    Ok(())
+}
+
+/// The rows of a quote slide in italics between curly quotes, wrapped to the
+/// frame, with their colors.
+fn quote_rows(presentation: &Presentation, quote: &Quote, frame: &Frame) -> Vec<(Vec<Span>, Rgb)> {
+    let colors = presentation.current_theme().get_theme_colors();
+    let [open_quote, close_quote] = presentation.glyphs().quotes;
+    let mut rows: Vec<(Vec<Span>, Rgb)> = Vec::new();
+    let last = quote.paragraphs.len() - 1;
+    for (index, paragraph) in quote.paragraphs.iter().enumerate() {
+        if index > 0 {
+            rows.push((Vec::new(), colors.text));
+        }
+        let open = if index == 0 { Some(open_quote) } else { None };
+        let close = if index == last {
+            Some(close_quote)
+        } else {
+            None
+        };
+        let text = open.into_iter().chain(paragraph.chars()).chain(close);
+        let mut spans = parse_inline(&text.collect::<String>());
+        for span in &mut spans {
+            span.style.italic = true;
+        }
+        let lines = wrap_spans(spans, (frame.right + 1 - frame.left) as usize);
+        rows.extend(lines.into_iter().map(|line| (line, colors.text)));
+    }
+    if let Some(attribution) = quote.attribution {
+        rows.push((Vec::new(), colors.accent));
+        rows.push((parse_inline(&format!("— {}", attribution)), colors.accent));
+    }
+    rows
+}
+
+/// Draws lines of the slide's content from the `top` row on within the
+/// frame. Returns the row below the last line drawn.
+fn render_content(
+    presentation: &Presentation,
+    lines: &[&str],
+    top: u16,
+    frame: &Frame,
+    big_text: bool,
+    stdout: &mut impl Write,
+) -> Result<u16, Error> {
+    // Rows taken up by content that has no source line, e.g. exec output
+    let mut row_offset = 0;
+    let mut list = ListState::default();
+    // Column where the text of the last list item starts, so that its
+    // continuation lines line up with it
+    let mut list_text_column: Option<u16> = None;
+    // The columns of the current list, placed by its widest item
+    let mut list_frame = None;
+    // The columns of the current blockquote, placed by its widest line
+    let mut quote_frame = None;
     let mut i = 0;
     while i < lines.len() {
         let line = lines[i];
-        if let Some(image_path) = extract_image_path(line) {
+        let row = i as u16 + top + row_offset;
+        if row > frame.bottom {
+            break;
+        }
+        if let Some(image) = Image::parse(line) {
             let full_image_path = Path::new(presentation.presentation_file)
                 .parent()
                 .unwrap()
-                .join(image_path);
-            render_image(&full_image_path);
-        } else if line.starts_with("```") {
+                .join(image.path);
+            // Invalid attributes are reported when the presentation is loaded
+            let attributes = image.attributes().unwrap_or_default();
+            row_offset += render_image(
+                &full_image_path,
+                &attributes,
+                row,
+                frame,
+                &presentation.options.image_options,
+            )? - 1;
+            i += 1;
+        } else if is_comment(line) {
+            i += 1;
+        } else if line.trim_start().starts_with("```") {
             let remaining_lines = lines[i..].join("\n");
+            // Indented blocks in a list line up with the text of its items
+            let frame = &match list_text_column.filter(|_| line.starts_with(char::is_whitespace)) {
+                Some(text_column) => list_frame
+                    .as_ref()
+                    .unwrap_or(frame)
+                    .columns(text_column.saturating_sub(3), frame.right),
+                None => *frame,
+            };
 
-            if let Some(code_block) = CodeBlock::parse(&remaining_lines) {
-                render_code_block(
-                    &code_block,
-                    stdout,
-                    i as u16 + 4,
-                    presentation.current_theme(),
-                );
+            if let Some(code_block) =
+                CodeBlock::parse(&remaining_lines, &presentation.options.language_aliases)
+            {
+                let annotations = presentation
+                    .exec_cache
+                    .output(&code_block.language, &code_block.content)
+                    .filter(|_| code_block.is_annotated());
+                let poll = (code_block.language == "poll")
+                    .then(|| Poll::parse(&code_block.content))
+                    .flatten();
+                if let Some(poll) = poll {
+                    render_poll(&poll, presentation, stdout, row, frame)?;
+                } else {
+                    let tab_width = presentation.options.tab_width;
+                    let gutter = code_block.gutter(presentation.metadata.options.line_numbers);
+                    // Invalid highlights are reported when the presentation is
+                    // loaded
+                    let steps = code_block.highlight_steps().unwrap_or_default();
+                    let highlighted = steps.as_ref().map(|steps| {
+                        let step = presentation.highlight_step(&code_block);
+                        &steps[step.min(steps.len() - 1)]
+                    });
+                    render_code_block(
+                        &code_block,
+                        stdout,
+                        row,
+                        &frame.block(code_block.width(tab_width, gutter)),
+                        (tab_width, gutter),
+                        (annotations, highlighted),
+                        (presentation.current_theme(), &presentation.code_colors()),
+                    )?;
+                }
                 // Skip the remaining lines of the code block
                 i += code_block.content.lines().count() + 2; // +2 for start/end markers
+
+                let exec_output = presentation
+                    .exec_cache
+                    .output(&code_block.language, &code_block.content);
+                if let (true, Some(output)) = (code_block.is_executable(), exec_output) {
+                    let output_row = i as u16 + top + row_offset;
+                    row_offset += render_exec_output(output, stdout, output_row, frame)?;
+                }
+            }
+        } else if let Some((table, line_count)) = Table::parse(&lines[i..]) {
+            render_table(
+                &table,
+                stdout,
+                row,
+                &frame.block(table.width() as u16),
+                (presentation.current_theme(), &presentation.glyphs()),
+                presentation.search.as_deref(),
+            )?;
+            // The table gains a top and a bottom border
+            row_offset += 2;
+            i += line_count;
+        } else if is_horizontal_rule(line) {
+            list.reset();
+            list_text_column = None;
+            list_frame = None;
+            let (start, end) = free_columns(frame, row);
+            write!(
+                stdout,
+                "{}{}{}{}",
+                goto(start, row),
+                color::Fg(presentation.current_theme().get_theme_colors().secondary),
+                presentation
+                    .glyphs()
+                    .horizontal((end + 1).saturating_sub(start) as usize),
+                color::Fg(color::Reset)
+            )?;
+            i += 1;
+        } else if let Some(item) = parse_list_item(line) {
+            let (depth, number) = list.push_item(&item);
+            let glyphs = presentation.glyphs();
+            let marker = list_marker(&item, (depth, number), &glyphs);
+            let frame = &*list_frame.get_or_insert_with(|| {
+                frame.block(list_width(&lines[i..], &glyphs).min(u16::MAX as usize) as u16)
+            });
+            let (start, end) = free_columns(frame, row);
+            let column = (start + 2 * depth as u16).min(end);
+            write!(
+                stdout,
+                "{}{}{}{}",
+                goto(column, row),
+                color::Fg(match item.checked {
+                    Some(true) => presentation.current_theme().get_theme_colors().tertiary,
+                    _ => list_marker_color(presentation.current_theme(), depth),
+                }),
+                marker,
+                color::Fg(color::Reset)
+            )?;
+            let text_column = column + text_width(&marker) as u16 + 1;
+            list_text_column = Some(text_column);
+            let rows = render_wrapped(
+                item.text,
+                (text_column, row),
+                frame,
+                (&color::Fg(color::Reset), false),
+                presentation,
+                stdout,
+            )?;
+            row_offset += rows - 1;
+            i += 1;
+        } else if let Some(text_column) =
+            list_text_column.filter(|_| line.starts_with(char::is_whitespace))
+        {
+            let rows = render_wrapped(
+                line.trim_start(),
+                (text_column, row),
+                list_frame.as_ref().unwrap_or(frame),
+                (&color::Fg(color::Reset), false),
+                presentation,
+                stdout,
+            )?;
+            row_offset += rows - 1;
+            i += 1;
+        } else if let Some((admonition, line_count)) = Admonition::parse(&lines[i..]) {
+            list.reset();
+            list_text_column = None;
+            list_frame = None;
+            let rows = render_admonition(&admonition, row, frame, presentation, stdout)?;
+            row_offset += rows - line_count as u16;
+            i += line_count;
+        } else if let Some(quote) = parse_quote_line(line) {
+            list.reset();
+            list_text_column = None;
+            list_frame = None;
+            if i == 0 || parse_quote_line(lines[i - 1]).is_none() {
+                let width = quote_width(&lines[i..]).min(u16::MAX as usize) as u16;
+                quote_frame = Some(frame.block(width));
+            }
+            let frame = quote_frame.as_ref().unwrap_or(frame);
+            let (start, _) = free_columns(frame, row);
+            let text_style = format!(
+                "{}{}{}",
+                color::Fg(color::Reset),
+                style::Faint,
+                style::Italic
+            );
+            let rows = render_wrapped(
+                quote.text,
+                (start + 2 * quote.depth as u16, row),
+                frame,
+                (&text_style, false),
+                presentation,
+                stdout,
+            )?
+            .max(1);
+            let gutter = format!("{} ", presentation.glyphs().quote_bar).repeat(quote.depth);
+            let bar_color = presentation.current_theme().get_theme_colors().tertiary;
+            for row in row..(row + rows).min(frame.bottom + 1) {
+                let (start, _) = free_columns(frame, row);
+                write!(
+                    stdout,
+                    "{}{}{}{}",
+                    goto(start, row),
+                    color::Fg(bar_color),
+                    gutter,
+                    color::Fg(color::Reset)
+                )?;
             }
+            row_offset += rows - 1;
+            i += 1;
+        } else if let Some(rows) = big_text
+            .then(|| extract_prefix(line))
+            .filter(|(hash, _)| hash == "#")
+            .map(|(_, heading)| render_big_heading(heading, row, frame, stdout, presentation))
+            .transpose()?
+            .flatten()
+        {
+            list.reset();
+            list_text_column = None;
+            list_frame = None;
+            row_offset += rows - 1;
+            i += 1;
         } else {
-            let (line, color): (&str, Box<dyn Display>) = match line.starts_with("#") {
+            if !line.trim().is_empty() {
+                list.reset();
+                list_text_column = None;
+                list_frame = None;
+            }
+            let (line, color, bold): (&str, Box<dyn Display>, bool) = match line.starts_with("#") {
                 true => {
                     let (hash, line) = extract_prefix(line);
                     let header = Header::header_by_prefix(&hash).unwrap();
                     (
                         line,
                         Box::new(color::Fg(header.color(presentation.current_theme()))),
+                        true,
                     )
                 }
-                _ => (line, Box::new(color::Fg(color::Reset))),
+                _ => (line, Box::new(color::Fg(color::Reset)), false),
             };
-            write!(
+            let (start, _) = free_columns(frame, row);
+            let rows = render_wrapped(
+                line,
+                (start, row),
+                frame,
+                (color.as_ref(), bold),
+                presentation,
                 stdout,
-                "{}{}{}{}{}{}",
-                style::Bold,
-                cursor::Goto(1, i as u16 + 4),
-                color,
+            )?;
+            row_offset += rows - 1;
+            i += 1;
+        }
+    }
+    Ok(lines.len() as u16 + top + row_offset)
+}
+
+/// The number of rows the lines take up when they are drawn, the height of
+/// the content's bounding box, and the columns the widest code block or table
+/// reaches past the frame. Code blocks and tables are measured with their
+/// borders and text as wrapped, while images and command output are not
+/// measured and count as one row.
+fn content_size(
+    presentation: &Presentation,
+    lines: &[&str],
+    frame: &Frame,
+    big_text: bool,
+) -> (u16, usize) {
+    let (_, height) = frame.stage;
+    let width = (frame.right + 1).saturating_sub(frame.left) as usize;
+    let glyphs = presentation.glyphs();
+    let wrapped_rows = |text: &str, indent: usize| {
+        wrap_spans(parse_inline(text), width.saturating_sub(indent).max(1))
+            .len()
+            .max(1)
+    };
+    let mut list = ListState::default();
+    let mut list_text_column = None;
+    let mut rows = 0;
+    let mut excess = 0;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let code_block = line
+            .trim_start()
+            .starts_with("```")
+            .then(|| {
+                CodeBlock::parse(
+                    &lines[i..].join("\n"),
+                    &presentation.options.language_aliases,
+                )
+            })
+            .flatten();
+        let (count, height) = if let Some(code_block) = code_block {
+            let gutter = code_block.gutter(presentation.metadata.options.line_numbers);
+            let block_width = code_block.width(presentation.options.tab_width, gutter) as usize;
+            // Indented blocks in a list line up with the text of its items
+            let indent = list_text_column
+                .filter(|_| line.starts_with(char::is_whitespace))
+                .map_or(0, |column: usize| column.saturating_sub(3));
+            excess = excess.max((indent + block_width).saturating_sub(width));
+            let count = code_block.content.lines().count() + 2;
+            (count, count)
+        } else if let Some((table, line_count)) = Table::parse(&lines[i..]) {
+            excess = excess.max(table.width().saturating_sub(width));
+            // The table gains a top and a bottom border
+            (line_count, line_count + 2)
+        } else if let Some(item) = parse_list_item(line) {
+            let (depth, number) = list.push_item(&item);
+            let text_column =
+                2 * depth + text_width(&list_marker(&item, (depth, number), &glyphs)) + 1;
+            list_text_column = Some(text_column);
+            (1, wrapped_rows(item.text, text_column))
+        } else if let Some(text_column) =
+            list_text_column.filter(|_| line.starts_with(char::is_whitespace))
+        {
+            (1, wrapped_rows(line.trim_start(), text_column))
+        } else if let Some((admonition, line_count)) = Admonition::parse(&lines[i..]) {
+            list.reset();
+            list_text_column = None;
+            (line_count, admonition.rows(admonition.width().min(width)))
+        } else if let Some(quote) = parse_quote_line(line) {
+            list.reset();
+            list_text_column = None;
+            (1, wrapped_rows(quote.text, 2 * quote.depth))
+        } else {
+            if !line.trim().is_empty() && !is_comment(line) {
+                list.reset();
+                list_text_column = None;
+            }
+            let height = match extract_prefix(line) {
+                (hash, heading) if big_text && hash == "#" => {
+                    let text: String = parse_inline(heading)
+                        .into_iter()
+                        .map(|span| span.text)
+                        .collect();
+                    bigtext::fit(&text, width, height as usize / 2, 2)
+                        .map_or(1, |(scale, lines)| {
+                            lines.len() * (bigtext::height(scale) + 1) - 1
+                        })
+                }
+                _ if is_comment(line) || Image::parse(line).is_some() => 1,
+                (_, text) => wrapped_rows(text, 0),
+            };
+            (1, height)
+        };
+        rows += height;
+        i += count;
+    }
+    (rows.min(u16::MAX as usize) as u16, excess)
+}
+
+/// The marker of a list item at the given depth and with the given number.
+pub(crate) fn list_marker(
+    item: &ListItem,
+    (depth, number): (usize, usize),
+    glyphs: &Glyphs,
+) -> String {
+    let checkbox = item
+        .checked
+        .map(|checked| &glyphs.checkboxes[checked as usize]);
+    match (item.marker, checkbox) {
+        (ListMarker::Bullet, Some(checkbox)) => checkbox.clone(),
+        (ListMarker::Bullet, None) => glyphs.bullet(depth).to_string(),
+        (ListMarker::Ordered, Some(checkbox)) => format!("{}. {}", number, checkbox),
+        (ListMarker::Ordered, None) => format!("{}.", number),
+    }
+}
+
+/// The width of the list starting at the first line, the widest of its items
+/// and their continuation lines as drawn.
+fn list_width(lines: &[&str], glyphs: &Glyphs) -> usize {
+    let mut list = ListState::default();
+    let mut widest = 0;
+    let mut text_column = 0;
+    let mut in_code_block = false;
+    for line in lines {
+        // Code blocks with indented fences are drawn below the text of their
+        // item
+        let fence = line.trim_start().starts_with("```");
+        if in_code_block || (fence && line.starts_with(char::is_whitespace)) {
+            in_code_block ^= fence;
+            widest = widest.max(text_column + text_width(line.trim()));
+            continue;
+        }
+        let text = if let Some(item) = parse_list_item(line) {
+            let (depth, number) = list.push_item(&item);
+            text_column = 2 * depth + text_width(&list_marker(&item, (depth, number), glyphs)) + 1;
+            item.text
+        } else if line.trim().is_empty() || line.starts_with(char::is_whitespace) {
+            line.trim()
+        } else {
+            break;
+        };
+        let text_width: usize = parse_inline(text)
+            .iter()
+            .map(|span| text_width(&span.text))
+            .sum();
+        widest = widest.max(text_column + text_width);
+    }
+    widest
+}
+
+/// Draws a note, tip or warning box in the color of its kind, with its icon and
+/// title in the top border. Returns the number of rows it takes up.
+fn render_admonition(
+    admonition: &Admonition,
+    row: u16,
+    frame: &Frame,
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+) -> Result<u16, Error> {
+    let theme = presentation.current_theme();
+    let glyphs = presentation.glyphs();
+    let available = (frame.right + 1).saturating_sub(frame.left) as usize;
+    let width = admonition.width().min(available).max(4);
+    let inner = width - 4;
+    let frame = frame.block(width as u16);
+    let color = color::Fg(admonition_color(theme, admonition.kind));
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    let title = width::truncate(admonition.title, inner.saturating_sub(3));
+    let mut rows = vec![format!(
+        "{}{}{} {}{} {}{} {}{}",
+        color,
+        top_left,
+        glyphs.horizontal(1),
+        glyphs.icons[admonition.kind as usize],
+        style::Bold,
+        title,
+        style::NoBold,
+        glyphs.horizontal(inner.saturating_sub(text_width(&title) + 3)),
+        top_right
+    )];
+    let lines: Vec<Vec<Span>> = admonition
+        .lines
+        .iter()
+        .flat_map(|line| {
+            let wrapped = wrap_spans(parse_inline(line), inner.max(1));
+            match wrapped.is_empty() {
+                true => vec![Vec::new()],
+                false => wrapped,
+            }
+        })
+        .collect();
+    rows.extend(lines.iter().map(|_| String::new()));
+    rows.push(format!(
+        "{}{}{}{}",
+        color,
+        bottom_left,
+        glyphs.horizontal(width - 2),
+        bottom_right
+    ));
+    for (index, text) in rows.iter().enumerate() {
+        let row = row + index as u16;
+        if row > frame.bottom {
+            break;
+        }
+        write!(stdout, "{}{}", goto(frame.left, row), text)?;
+        if let Some(line) = index.checked_sub(1).and_then(|index| lines.get(index)) {
+            write!(stdout, "{}{} ", color, glyphs.vertical())?;
+            render_spans(
                 line,
-                color::Fg(color::Reset),
+                &color::Fg(color::Reset),
+                false,
+                inner,
+                theme,
+                presentation.search.as_deref(),
+                stdout,
+            )?;
+            write!(
+                stdout,
+                "{}{}{}",
+                goto(frame.left + width as u16 - 1, row),
+                color,
+                glyphs.vertical()
+            )?;
+        }
+        write!(stdout, "{}", color::Fg(color::Reset))?;
+    }
+    Ok(rows.len() as u16)
+}
+
+fn admonition_color(theme: &Theme, kind: AdmonitionKind) -> Rgb {
+    let colors = theme.get_theme_colors();
+    match kind {
+        AdmonitionKind::Note => colors.primary,
+        AdmonitionKind::Tip => colors.secondary,
+        AdmonitionKind::Important => colors.tertiary,
+        AdmonitionKind::Warning => colors.accent,
+        AdmonitionKind::Caution => colors.error,
+    }
+}
+
+/// The width of the blockquote starting at the first line, the widest of its
+/// lines with the bars in front of them.
+fn quote_width(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .map_while(|line| parse_quote_line(line))
+        .map(|quote| {
+            let text_width: usize = parse_inline(quote.text)
+                .iter()
+                .map(|span| text_width(&span.text))
+                .sum();
+            2 * quote.depth + text_width
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+/// The title and subtitle of the presentation in the first two rows.
+fn render_header(
+    presentation: &Presentation,
+    frame: &Frame,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    render_text_centered(
+        presentation
+            .metadata
+            .title
+            .as_ref()
+            .unwrap_or(&String::from("No title found")),
+        1,
+        frame,
+        stdout,
+        presentation.current_theme().get_theme_colors().primary,
+    )?;
+    render_text_centered(
+        presentation
+            .metadata
+            .subtitle
+            .as_ref()
+            .unwrap_or(&String::from("No subtitle found")),
+        2,
+        frame,
+        stdout,
+        presentation.current_theme().get_theme_colors().primary,
+    )?;
+    Ok(())
+}
+
+/// Redraws the notifications after some of them expired. Only the corner they
+/// were drawn in is cleared when it doesn't reach beyond the header; otherwise
+/// the whole slide is drawn again.
+pub fn render_notification_area(
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let Some(area) = presentation.notifications.drawn() else {
+        return Ok(());
+    };
+    let slide = presentation.current_slide();
+    let plain_slide = presentation.large_type.is_none()
+        && !presentation.notifications.show_log
+        && !presentation.show_questions
+        && !has_directive(slide, "title_slide")
+        && extract_full_image(slide).is_none()
+        && extract_directive(slide, "background_image").is_none()
+        && !is_centered(presentation);
+    // The third row is the gap between the header and the content
+    if !plain_slide || area.rows > 3 {
+        return render_slide(presentation, stdout);
+    }
+    let (width, height) = stage_size()?;
+    let reserved = extract_directive(slide, "reserve")
+        .and_then(|directive| ReservedRegion::parse(directive, width, height));
+    for row in 1..=area.rows {
+        write!(
+            stdout,
+            "{}{}",
+            goto(area.left, row),
+            " ".repeat((width + 1).saturating_sub(area.left) as usize)
+        )?;
+    }
+    if !presentation.metadata.has_title_slide() {
+        render_header(
+            presentation,
+            &Frame::stage(presentation, reserved.as_ref())?,
+            stdout,
+        )?;
+    }
+    if let Some(reserved) = &reserved {
+        reserved.clear(stdout)?;
+    }
+    render_notifications(presentation, stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Writes a line of text from the position on, wrapped where the free columns
+/// of the frame end, and aligned between them by the frame's alignment.
+/// Returns the number of rows taken up.
+fn render_wrapped(
+    text: &str,
+    (column, row): (u16, u16),
+    frame: &Frame,
+    (color, bold): (&dyn Display, bool),
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+) -> Result<u16, Error> {
+    let spans = parse_inline(text);
+    // Around a reserved region the rows further down can be narrower
+    let narrowest = |rows: usize| {
+        (row..row + rows as u16)
+            .map(|row| {
+                let (start, end) = free_columns(frame, row);
+                (end + 1).saturating_sub(column.max(start)) as usize
+            })
+            .min()
+            .unwrap_or_default()
+    };
+    let mut lines = wrap_spans(spans.clone(), narrowest(1));
+    if narrowest(lines.len()) < narrowest(1) {
+        lines = wrap_spans(spans, narrowest(lines.len()));
+    }
+    for (index, line) in lines.iter().enumerate() {
+        let row = row + index as u16;
+        if row > frame.bottom {
+            break;
+        }
+        let (start, end) = free_columns(frame, row);
+        let column = column.max(start);
+        let available = (end + 1).saturating_sub(column) as usize;
+        let line_width: usize = line.iter().map(|span| text_width(&span.text)).sum();
+        let (padding, _) = frame.alignment.padding(line_width, available);
+        write!(stdout, "{}", goto(column + padding as u16, row))?;
+        render_spans(
+            line,
+            color,
+            bold,
+            available,
+            presentation.current_theme(),
+            presentation.search.as_deref(),
+            stdout,
+        )?;
+    }
+    Ok(lines.len() as u16)
+}
+
+/// Writes a line of text with its inline markdown formatting applied, cut off
+/// after `max_width` columns.
+fn render_inline(
+    text: &str,
+    color: &dyn Display,
+    bold: bool,
+    max_width: usize,
+    theme: &Theme,
+    highlight: Option<&str>,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    render_spans(
+        &parse_inline(text),
+        color,
+        bold,
+        max_width,
+        theme,
+        highlight,
+        stdout,
+    )?;
+    Ok(())
+}
+
+fn render_spans(
+    spans: &[Span],
+    color: &dyn Display,
+    bold: bool,
+    max_width: usize,
+    theme: &Theme,
+    highlight: Option<&str>,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let mut remaining = max_width;
+    for span in spans {
+        let mut text: Vec<char> = Vec::new();
+        for c in span.text.chars() {
+            if char_width(c) > remaining {
+                remaining = 0;
+                break;
+            }
+            remaining -= char_width(c);
+            text.push(c);
+        }
+        if text.is_empty() {
+            break;
+        }
+
+        let mut span_style = color.to_string();
+        if bold || span.style.bold {
+            span_style.push_str(style::Bold.as_ref());
+        }
+        if span.style.italic {
+            span_style.push_str(style::Italic.as_ref());
+        }
+        if span.style.strikethrough {
+            span_style.push_str(style::CrossedOut.as_ref());
+        }
+        if span.style.code {
+            span_style.push_str(&color::Fg(theme.get_theme_colors().accent).to_string());
+        }
+        if span.style.link {
+            span_style.push_str(style::Underline.as_ref());
+        }
+        if let Some(url) = &span.url {
+            write!(stdout, "\x1b]8;;{}\x1b\\", url)?;
+        }
+        // Matches of the search are shown inverted
+        let matches = highlight
+            .map(|pattern| search::find_matches(&String::from_iter(&text), pattern))
+            .unwrap_or_default();
+        let mut position = 0;
+        for range in matches
+            .into_iter()
+            .chain(iter::once(text.len()..text.len()))
+        {
+            write!(
+                stdout,
+                "{}{}{}",
+                span_style,
+                String::from_iter(&text[position..range.start]),
                 style::Reset
-            )
-            .unwrap();
-            i += 1;
+            )?;
+            if !range.is_empty() {
+                write!(
+                    stdout,
+                    "{}{}{}{}",
+                    span_style,
+                    style::Invert,
+                    String::from_iter(&text[range.clone()]),
+                    style::Reset
+                )?;
+            }
+            position = range.end;
+        }
+        if span.url.is_some() {
+            write!(stdout, "\x1b]8;;\x1b\\")?;
         }
     }
-    render_footer(presentation, stdout);
-    stdout.flush().unwrap();
+    Ok(())
+}
+
+/// Draws a table with box-drawing borders, starting at the given row with its
+/// top border.
+fn render_table(
+    table: &Table,
+    stdout: &mut impl Write,
+    start_row: u16,
+    frame: &Frame,
+    (theme, glyphs): (&Theme, &Glyphs),
+    highlight: Option<&str>,
+) -> Result<(), Error> {
+    let colors = theme.get_theme_colors();
+    let widths = table.column_widths();
+    let padded: Vec<usize> = widths.iter().map(|width| width + 2).collect();
+    let border = |row: usize| {
+        format!(
+            "{}{}{}",
+            color::Fg(colors.secondary),
+            glyphs.border_line(row, &padded),
+            color::Fg(color::Reset)
+        )
+    };
+
+    let mut row = start_row;
+    let write_line = |text: &str, row: u16, stdout: &mut dyn Write| {
+        if row <= frame.bottom {
+            write!(stdout, "{}{}", goto(free_columns(frame, row).0, row), text)?;
+        }
+        Ok::<(), Error>(())
+    };
+    write_line(&border(0), row, stdout)?;
+    row += 1;
+
+    let cells = std::iter::once(&table.header).chain(&table.rows);
+    for (index, cells) in cells.enumerate() {
+        if row > frame.bottom {
+            return Ok(());
+        }
+        let (start, _) = free_columns(frame, row);
+        write!(stdout, "{}", goto(start, row))?;
+        for ((cell, width), alignment) in cells.iter().zip(&widths).zip(&table.alignments) {
+            let (before, after) = alignment.padding(tables::cell_width(cell), *width);
+            write!(
+                stdout,
+                "{}{}{} {}",
+                color::Fg(colors.secondary),
+                glyphs.vertical(),
+                color::Fg(color::Reset),
+                " ".repeat(before)
+            )?;
+            if index == 0 {
+                render_inline(
+                    cell,
+                    &color::Fg(colors.primary),
+                    true,
+                    *width,
+                    theme,
+                    highlight,
+                    stdout,
+                )?;
+            } else {
+                render_inline(
+                    cell,
+                    &color::Fg(color::Reset),
+                    false,
+                    *width,
+                    theme,
+                    highlight,
+                    stdout,
+                )?;
+            }
+            write!(stdout, "{} ", " ".repeat(after))?;
+        }
+        write!(
+            stdout,
+            "{}{}{}",
+            color::Fg(colors.secondary),
+            glyphs.vertical(),
+            color::Fg(color::Reset)
+        )?;
+        row += 1;
+
+        if index == 0 {
+            write_line(&border(1), row, stdout)?;
+            row += 1;
+        }
+    }
+    write_line(&border(2), row, stdout)
+}
+
+/// Draws a top level heading centered in banner letters at up to twice the
+/// height of the pixel font. Returns the number of rows taken up, or `None` if
+/// the heading is too wide and has to be drawn normally.
+fn render_big_heading(
+    heading: &str,
+    row: u16,
+    frame: &Frame,
+    stdout: &mut impl Write,
+    presentation: &Presentation,
+) -> Result<Option<u16>, Error> {
+    let text: String = parse_inline(heading)
+        .into_iter()
+        .map(|span| span.text)
+        .collect();
+    let (start, end) = free_columns(frame, row);
+    let (_, height) = stage_size()?;
+    let Some((scale, lines)) = bigtext::fit(
+        &text,
+        (end - start + 1) as usize,
+        height.saturating_sub(row + 2) as usize,
+        2,
+    ) else {
+        return Ok(None);
+    };
+
+    let mut current_row = row;
+    let color = Header::Header1.color(presentation.current_theme());
+    write!(stdout, "{}", color::Fg(color))?;
+    let blocks = presentation.glyphs().blocks;
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 {
+            current_row += 1;
+        }
+        for big_line in bigtext::render_line(line, scale, blocks) {
+            let (start, end) = free_columns(frame, current_row);
+            let padding = ((end - start + 1) as usize).saturating_sub(text_width(&big_line)) / 2;
+            write!(
+                stdout,
+                "{}{}",
+                goto(start + padding as u16, current_row),
+                big_line
+            )?;
+            current_row += 1;
+        }
+    }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    Ok(Some(current_row - row))
+}
+
+/// Draws the slide generated from the metadata: the title in banner letters,
+/// followed by the subtitle, the authors and the date and event, all centered.
+fn render_title_slide(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    let metadata = &presentation.metadata;
+    let colors = presentation.current_theme().get_theme_colors();
+    let (width, height) = stage_size()?;
+    let title = metadata.title.as_deref().unwrap_or_default();
+    let mut rows: Vec<(String, Rgb)> = Vec::new();
+    match bigtext::fit(title, width as usize, height as usize / 2, 2) {
+        Some((scale, lines)) => {
+            for (index, line) in lines.iter().enumerate() {
+                if index > 0 {
+                    rows.push((String::new(), colors.primary));
+                }
+                rows.extend(
+                    bigtext::render_line(line, scale, presentation.glyphs().blocks)
+                        .into_iter()
+                        .map(|row| (row, colors.primary)),
+                );
+            }
+        }
+        None => rows.push((title.to_string(), colors.primary)),
+    }
+    let occasion: Vec<&str> = [&metadata.date, &metadata.event]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let details = [
+        (metadata.subtitle.clone(), colors.secondary),
+        (
+            (!metadata.author.is_empty()).then(|| metadata.author.join(", ")),
+            colors.text,
+        ),
+        (
+            (!occasion.is_empty()).then(|| occasion.join(" · ")),
+            colors.tertiary,
+        ),
+    ];
+    for (index, (text, color)) in details.into_iter().enumerate() {
+        if let Some(text) = text {
+            // The subtitle is set apart from the title
+            if index == 0 {
+                rows.push((String::new(), color));
+            }
+            rows.push((String::new(), color));
+            rows.push((text, color));
+        }
+    }
+
+    let top = (height as usize).saturating_sub(rows.len()) / 2 + 1;
+    for (index, (row, color)) in rows.iter().enumerate().take(height as usize) {
+        let left = (width as usize).saturating_sub(text_width(row)) / 2 + 1;
+        write!(
+            stdout,
+            "{}{}{}",
+            goto(left as u16, (top + index) as u16),
+            color::Fg(*color),
+            row
+        )?;
+    }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    Ok(())
+}
+
+/// Fills the screen with the text in banner-size letters, or in normal letters
+/// if it is too long for that.
+/// The text in the largest letters that fit the area, or as plain text if
+/// even the smallest ones don't.
+fn large_rows(text: &str, (width, height): (u16, u16), blocks: [char; 3]) -> Vec<String> {
+    match bigtext::fit(text, width as usize, height as usize, bigtext::MAX_SCALE) {
+        Some((scale, lines)) => lines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                // Lines are separated by a blank row
+                let gap = (index > 0).then(String::new);
+                gap.into_iter()
+                    .chain(bigtext::render_line(line, scale, blocks))
+            })
+            .collect(),
+        None => text
+            .chars()
+            .collect::<Vec<char>>()
+            .chunks(width as usize)
+            .map(|chunk| chunk.iter().collect())
+            .collect(),
+    }
+}
+
+fn render_large_type(
+    text: &str,
+    stdout: &mut impl Write,
+    presentation: &Presentation,
+) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    let rows = large_rows(text, (width, height), presentation.glyphs().blocks);
+    let top = (height as usize).saturating_sub(rows.len()) / 2 + 1;
+    let colors = presentation.current_theme().get_theme_colors();
+    write!(stdout, "{}", color::Fg(colors.primary))?;
+    for (index, row) in rows.iter().enumerate().take(height as usize) {
+        let left = (width as usize).saturating_sub(text_width(row)) / 2 + 1;
+        write!(stdout, "{}{}", goto(left as u16, (top + index) as u16), row)?;
+    }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    Ok(())
+}
+
+/// Fills the screen with the time left of a break, started when the slide
+/// first came up, and the time the break ends below the label.
+fn render_countdown(
+    presentation: &Presentation,
+    countdown: &Countdown,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let mut timer = match presentation.countdown.get() {
+        Some(timer) if timer.slide == presentation.current_slide => timer,
+        _ => CountdownTimer::start(presentation.current_slide, countdown.length),
+    };
+    let remaining = timer.remaining(countdown.length);
+    // Rounded up, so that 0:00 only shows once the time is up
+    let remaining =
+        Duration::from_secs(remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0));
+    timer.finished = remaining.is_zero();
+    presentation.countdown.set(Some(timer));
+
+    let (width, height) = stage_size()?;
+    let colors = presentation.current_theme().get_theme_colors();
+    let rows = large_rows(
+        &cues::format_duration(remaining),
+        (width, height.saturating_sub(2)),
+        presentation.glyphs().blocks,
+    );
+    let label = countdown
+        .label
+        .as_ref()
+        .map(|label| format!("{} {}", label, timer.end_time()));
+    let label_rows = if label.is_some() { 2 } else { 0 };
+    let top = (height as usize).saturating_sub(rows.len() + label_rows) / 2 + 1;
+    if let Some(label) = &label {
+        let left = (width as usize).saturating_sub(text_width(label)) / 2 + 1;
+        write!(
+            stdout,
+            "{}{}{}{}",
+            goto(left as u16, top as u16),
+            color::Fg(colors.secondary),
+            label,
+            color::Fg(color::Reset)
+        )?;
+    }
+    let color = if remaining.is_zero() {
+        colors.error
+    } else {
+        colors.primary
+    };
+    write!(stdout, "{}", color::Fg(color))?;
+    for (index, row) in rows.iter().enumerate() {
+        let left = (width as usize).saturating_sub(text_width(row)) / 2 + 1;
+        let row_top = top + label_rows + index;
+        write!(stdout, "{}{}", goto(left as u16, row_top as u16), row)?;
+    }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    Ok(())
+}
+
+fn list_marker_color(theme: &Theme, depth: usize) -> Rgb {
+    let colors = theme.get_theme_colors();
+    [
+        colors.primary,
+        colors.secondary,
+        colors.tertiary,
+        colors.accent,
+    ][depth % 4]
+}
+
+/// Draws the speaker notes in a dimmed panel right above the footer, using at
+/// most a third of the terminal height.
+fn render_notes(
+    notes: &str,
+    pane: NotesPane,
+    stdout: &mut impl Write,
+    theme: &Theme,
+    glyphs: &Glyphs,
+) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    let max_lines = pane.height.unwrap_or(height / 3).max(1) as usize;
+    let lines: Vec<&str> = notes.lines().take(max_lines).collect();
+    // The separator line faces the slide
+    let (separator, first_line) = match pane.position {
+        VerticalPosition::Bottom => {
+            let top = height.saturating_sub(lines.len() as u16 + 3).max(1);
+            (top, top + 1)
+        }
+        VerticalPosition::Top => ((lines.len() as u16 + 1).min(height), 1),
+    };
+    write!(
+        stdout,
+        "{}{}{}{}{}",
+        goto(1, separator),
+        termion::clear::CurrentLine,
+        color::Fg(theme.get_theme_colors().accent),
+        glyphs.horizontal(width as usize),
+        color::Fg(color::Reset)
+    )?;
+    for (index, line) in lines.iter().enumerate() {
+        let line: String = line.chars().take(width as usize).collect();
+        write!(
+            stdout,
+            "{}{}{}{}{}",
+            goto(1, first_line + index as u16),
+            termion::clear::CurrentLine,
+            style::Faint,
+            line,
+            style::Reset
+        )?;
+    }
+    Ok(())
 }
 
 fn render_footer(
     presentation: &Presentation,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
-) {
-    render_text_centered(
+    frame: &Frame,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    // Hidden and appendix slides are left out of the count, the appendix is
+    // numbered on its own
+    let appendix_start = presentation.appendix_start();
+    let mut counter = if presentation.in_appendix() {
+        format!(
+            "Appendix {}/{}",
+            presentation.current_slide - appendix_start + 1,
+            presentation.total_slides() - appendix_start
+        )
+    } else {
         format!(
             "{}/{} slides",
-            presentation.current_slide + 1,
-            presentation.total_slides()
+            presentation.logical_slide_number(),
+            presentation.logical_slide_count()
         )
-        .as_str(),
-        true,
+    };
+    if let Some(cues) = &presentation.cues {
+        counter.push_str(&format!(" · {}", cues.schedule(presentation.current_slide)));
+    }
+    render_text_centered(
+        &counter,
+        frame.stage.1 - 1,
+        frame,
         stdout,
         presentation.current_theme().get_theme_colors().accent,
-    );
+    )?;
+    if let Some((clock, severity)) = presentation.clock() {
+        let row = frame.stage.1 - 1;
+        let (start, end) = free_columns(frame, row);
+        let column = match presentation.panes.clock.position {
+            HorizontalPosition::Left => start + 1,
+            HorizontalPosition::Right => (end + 1).saturating_sub(clock.len() as u16 + 1).max(1),
+        };
+        write!(
+            stdout,
+            "{}{}{}{}{}",
+            goto(column, row),
+            style::Bold,
+            color::Fg(severity_color(severity, presentation.current_theme())),
+            clock,
+            style::Reset
+        )?;
+    }
     render_progress_bar(
-        presentation.current_slide,
-        presentation.total_slides(),
+        presentation.logical_slide_number().saturating_sub(1),
+        presentation.logical_slide_count().max(1),
+        (frame.left, frame.right),
         stdout,
         presentation.current_theme().get_theme_colors().accent,
-    );
+        presentation.glyphs().progress,
+    )
 }
 
-fn extract_image_path(line: &str) -> Option<&str> {
-    if line.starts_with("![") && line.contains("](") && line.ends_with(")") {
-        let start = line.find("](").unwrap() + 2;
-        let end = line.len() - 1;
-        Some(&line[start..end])
+/// Draws the time left on the slide of a lightning talk in banner letters in
+/// the bottom-right corner.
+fn render_slide_countdown(
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let Some(remaining) = presentation.cues.as_ref().and_then(CueTimer::remaining) else {
+        return Ok(());
+    };
+    let (width, height) = stage_size()?;
+    let colors = presentation.current_theme().get_theme_colors();
+    // The last seconds are shown in the error color as a warning
+    let color = if remaining.as_secs() < 5 {
+        colors.error
     } else {
-        None
+        colors.accent
+    };
+    let rows = bigtext::render_line(
+        &cues::format_duration(remaining),
+        1,
+        presentation.glyphs().blocks,
+    );
+    let top = height.saturating_sub(rows.len() as u16 + 2);
+    write!(stdout, "{}", color::Fg(color))?;
+    for (index, row) in rows.iter().enumerate() {
+        let left = width.saturating_sub(text_width(row) as u16 + 1);
+        write!(stdout, "{}{}", goto(left, top + index as u16), row)?;
     }
+    write!(stdout, "{}", color::Fg(color::Reset))?;
+    Ok(())
+}
+
+pub(crate) fn extract_full_image(slide: &str) -> Option<FullImage<'_>> {
+    let directive = extract_directive(slide, "full_image")?;
+    match directive.split_once('"') {
+        Some((path, caption)) => Some(FullImage {
+            path: path.trim(),
+            caption: Some(caption.trim_end_matches('"')),
+        }),
+        None => Some(FullImage {
+            path: directive,
+            caption: None,
+        }),
+    }
+}
+
+pub(crate) fn extract_directive<'a>(slide: &'a str, name: &str) -> Option<&'a str> {
+    slide.lines().find_map(|line| {
+        let directive = line.trim().strip_prefix("<!--")?.strip_suffix("-->")?;
+        let (key, value) = directive.split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+/// Whether the slide contains a directive without a value, like
+/// `<!-- big_text -->`.
+pub(crate) fn has_directive(slide: &str, name: &str) -> bool {
+    slide.lines().any(|line| {
+        line.trim()
+            .strip_prefix("<!--")
+            .and_then(|line| line.strip_suffix("-->"))
+            .is_some_and(|directive| directive.trim() == name)
+    })
+}
+
+pub(crate) fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("<!--") && line.ends_with("-->")
+}
+
+/// Whether the line is a horizontal rule like `***`, `___` or `- - -`. A line of
+/// just `---` ends the slide instead.
+pub(crate) fn is_horizontal_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ['*', '-', '_'].contains(&marks[0])
+        && marks.iter().all(|mark| *mark == marks[0])
+        && !line.starts_with("    ")
+}
+
+fn resolve_image_path(presentation: &Presentation, path: &str) -> Result<PathBuf, Error> {
+    let image_path = Path::new(presentation.presentation_file)
+        .parent()
+        .unwrap()
+        .join(path);
+    if !image_path.exists() {
+        return Err(Error::MissingImage(image_path));
+    }
+    Ok(image_path)
+}
+
+fn open_image(path: &Path) -> Result<DynamicImage, Error> {
+    image::open(path).map_err(|err| Error::Image(path.to_path_buf(), err.to_string()))
+}
+
+/// Draws the dimmed image across the whole terminal before the slide text.
+/// Graphics protocols would draw the image on top of the text, so the block
+/// printer is always used and text simply replaces the cells it covers.
+fn render_background_image(presentation: &Presentation, path: &str) -> Result<(), Error> {
+    let image_path = resolve_image_path(presentation, path)?;
+    let (width, height) = stage_size()?;
+    let image = images::crop_to_cover(&open_image(&image_path)?, width, height);
+    let (x, y) = *STAGE_OFFSET.lock().unwrap();
+    let config = Config {
+        x,
+        y: y as i16,
+        width: Some(width as u32),
+        height: Some(height as u32),
+        use_kitty: false,
+        use_iterm: false,
+        ..Default::default()
+    };
+    print_image(
+        &images::dim(&image, 0.35),
+        &config,
+        &presentation.options.image_options,
+    )
+    .map(|_| ())
+    .map_err(|err| Error::Image(image_path, err))
+}
+
+fn render_full_image(
+    presentation: &Presentation,
+    full_image: &FullImage,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let image_path = resolve_image_path(presentation, full_image.path)?;
+    let (width, height) = stage_size()?;
+    let image = images::crop_to_cover(&open_image(&image_path)?, width, height);
+    let (x, y) = *STAGE_OFFSET.lock().unwrap();
+    let config = Config {
+        x,
+        y: y as i16,
+        width: Some(width as u32),
+        height: Some(height as u32),
+        ..Default::default()
+    };
+    print_image(&image, &config, &presentation.options.image_options)
+        .map_err(|err| Error::Image(image_path, err))?;
+
+    if let Some(caption) = full_image.caption {
+        let caption = format!(" {} ", caption);
+        let start = (width as usize).saturating_sub(text_width(&caption)) / 2 + 1;
+        write!(
+            stdout,
+            "{}{}{}{}{}{}",
+            goto(start as u16, height.saturating_sub(1)),
+            style::Bold,
+            color::Bg(color::Black),
+            color::Fg(color::White),
+            caption,
+            style::Reset
+        )?;
+    }
+    Ok(())
 }
 
 fn render_code_block(
     block: &CodeBlock,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    stdout: &mut impl Write,
     start_line: u16,
-    theme: &Theme,
-) {
-    let indent = 4;
+    frame: &Frame,
+    (tab_width, gutter): (usize, usize),
+    (annotations, highlighted): (Option<&str>, Option<&LineSelection>),
+    (theme, code): (&Theme, &CodeColors),
+) -> Result<(), Error> {
+    let indent = |row: u16| free_columns(frame, row).0 + 3;
+    let mut annotations = annotations.unwrap_or_default().lines();
+    // Tabs would jump to the terminal's tab stops, which ignore the indent
+    let content = expand_tabs(&block.content, tab_width);
 
     // Render language identifier
     write!(
         stdout,
         "{}{}{}{}{}{}",
-        cursor::Goto(indent, start_line),
+        goto(indent(start_line), start_line),
         style::Bold,
         color::Fg(theme.get_theme_colors().primary),
         block.language,
         color::Fg(color::Reset),
         style::Reset
-    )
-    .unwrap();
+    )?;
 
-    let tokens = parse_syntax(&block.content, &block.language, stdout);
+    let tokens = parse_syntax(&content, &block.language)?;
 
     let mut current_pos = 0;
 
-    for (current_line, line) in block.content.lines().enumerate() {
+    for (current_line, line) in content.lines().enumerate() {
+        if start_line + 1 + current_line as u16 > frame.bottom {
+            break;
+        }
         let line_start = current_pos;
         let line_end = line_start + line.len();
 
@@ -362,16 +2489,28 @@ fn render_code_block(
             .filter(|t| t.start >= line_start && t.start < line_end)
             .collect();
 
-        write!(
-            stdout,
-            "{}",
-            cursor::Goto(indent, start_line + 1 + current_line as u16),
-        )
-        .unwrap();
+        let row = start_line + 1 + current_line as u16;
+        write!(stdout, "{}", goto(indent(row), row))?;
+        // The numbers go in front of the line, so the token offsets into the
+        // content stay as they are
+        if gutter > 0 {
+            write!(
+                stdout,
+                "{}{:>width$} {}",
+                style::Faint,
+                current_line + 1,
+                style::Reset,
+                width = gutter - 1
+            )?;
+        }
+        let dimmed = highlighted.is_some_and(|selection| !selection.contains(current_line + 1));
+        if dimmed {
+            write!(stdout, "{}", style::Faint)?;
+        }
 
         if line_tokens.is_empty() {
             // No syntax highlighting for this line
-            write!(stdout, "{}", line).unwrap();
+            write!(stdout, "{}", line)?;
         } else {
             // Create a vector to track which parts of the line have been colored
             let mut colored_positions = vec![false; line.len()];
@@ -380,8 +2519,8 @@ fn render_code_block(
             for token in &line_tokens {
                 let token_start_in_line = token.start - line_start;
                 let token_end_in_line = std::cmp::min(token.end - line_start, line.len());
-                for pos in token_start_in_line..token_end_in_line {
-                    colored_positions[pos] = true;
+                for colored in &mut colored_positions[token_start_in_line..token_end_in_line] {
+                    *colored = true;
                 }
             }
 
@@ -395,7 +2534,7 @@ fn render_code_block(
                         end_pos += 1;
                     }
                     // Write uncolored text
-                    write!(stdout, "{}", &line[current_pos..end_pos]).unwrap();
+                    write!(stdout, "{}", &line[current_pos..end_pos])?;
                     current_pos = end_pos;
                 } else {
                     // Find the token that starts at this position
@@ -410,11 +2549,10 @@ fn render_code_block(
                             // "{:?}{}{}{}",
                             // token.kind,
                             "{}{}{}",
-                            color::Fg(token.kind.color(theme)),
+                            color::Fg(token.kind.color(theme, code)),
                             &line[current_pos..token_end_in_line],
                             color::Fg(color::Reset)
-                        )
-                        .unwrap();
+                        )?;
                         current_pos = token_end_in_line;
                     } else {
                         // Skip this position if no token starts here
@@ -424,124 +2562,1051 @@ fn render_code_block(
             }
         }
 
+        if dimmed {
+            write!(stdout, "{}", style::Reset)?;
+        }
+        if is_annotation_marker(line) {
+            if let Some(result) = annotations.next() {
+                write!(stdout, " {}{}{}", style::Faint, result, style::Reset)?;
+            }
+        }
+
         current_pos += line.len() + 1; // +1 for newline
     }
+    Ok(())
+}
+
+/// Draws the question of a poll with a bar per answer showing its votes so
+/// far, and where to vote as a link and, if there is room, a QR code.
+fn render_poll(
+    poll: &Poll,
+    presentation: &Presentation,
+    stdout: &mut impl Write,
+    start_line: u16,
+    frame: &Frame,
+) -> Result<(), Error> {
+    let colors = presentation.current_theme().get_theme_colors();
+    let (start, end) = free_columns(frame, start_line);
+    let column = start + 3;
+    let url = presentation
+        .server
+        .as_ref()
+        .map(|server| server.poll_url(presentation.current_slide));
+    let qr_code = url
+        .as_deref()
+        .and_then(poll::qr_code)
+        .filter(|rows| start_line as usize + rows.len() < frame.stage.1 as usize);
+    let qr_width = qr_code
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .map_or(0, |row| text_width(row) as u16);
+    let votes = presentation
+        .server
+        .as_ref()
+        .map(|server| server.votes(poll))
+        .unwrap_or_else(|| vec![0; poll.answers.len()]);
+    let label_width = poll
+        .answers
+        .iter()
+        .map(|answer| text_width(answer))
+        .max()
+        .unwrap_or(0) as u16;
+    let count_width = votes.iter().max().unwrap_or(&0).to_string().len() as u16;
+    let text_width = end.saturating_sub(column);
+    // The QR code is left out rather than squeezing the bars
+    let (qr_code, bar_space) = match qr_code {
+        Some(rows) if text_width >= label_width + count_width + qr_width + 14 => {
+            (Some(rows), text_width - qr_width - 2)
+        }
+        _ => (None, text_width),
+    };
+    let bar_width = bar_space
+        .saturating_sub(label_width + count_width + 4)
+        .min(40) as usize;
+    let most_votes = votes.iter().copied().max().unwrap_or(0).max(1);
+    let meter = presentation.glyphs().meter;
+
+    write!(
+        stdout,
+        "{}{}{}{}{}",
+        goto(column, start_line),
+        style::Bold,
+        color::Fg(colors.primary),
+        poll.question,
+        style::Reset
+    )?;
+    for (index, (answer, count)) in poll.answers.iter().zip(&votes).enumerate() {
+        let filled = (bar_width * count).div_ceil(most_votes);
+        write!(
+            stdout,
+            "{}{}  {}{}{}{}{} {}",
+            goto(column, start_line + 1 + index as u16),
+            width::pad(answer, label_width as usize),
+            color::Fg(colors.accent),
+            meter[0].to_string().repeat(filled),
+            style::Faint,
+            meter[1].to_string().repeat(bar_width - filled),
+            style::Reset,
+            count,
+        )?;
+    }
+    let url_row = start_line + 1 + poll.answers.len() as u16;
+    let link = match &url {
+        Some(url) => format!("Vote at {}", url),
+        None => "Voting is unavailable".to_string(),
+    };
+    write!(
+        stdout,
+        "{}{}{}{}",
+        goto(column, url_row),
+        style::Faint,
+        link,
+        style::Reset
+    )?;
+    if let Some(rows) = qr_code {
+        // Light modules on a dark background, whatever the theme's colors
+        for (index, row) in rows.iter().enumerate() {
+            write!(
+                stdout,
+                "{}{}{}{}{}",
+                goto(end - qr_width, start_line + index as u16),
+                color::Fg(color::White),
+                color::Bg(color::Black),
+                row,
+                style::Reset
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Draws the output of an executed code block below it and returns the number
+/// of rows used.
+fn render_exec_output(
+    output: &str,
+    stdout: &mut impl Write,
+    start_line: u16,
+    frame: &Frame,
+) -> Result<u16, Error> {
+    let mut rows = 0;
+    for line in output.lines() {
+        let row = start_line + rows;
+        if row > frame.bottom {
+            break;
+        }
+        let (start, _) = free_columns(frame, row);
+        write!(
+            stdout,
+            "{}{}{}{}",
+            goto(start + 3, row),
+            style::Faint,
+            line,
+            style::Reset
+        )?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+pub(crate) fn is_annotation_marker(line: &str) -> bool {
+    let line = line.trim_end();
+    line.ends_with("# =>") || line.ends_with("// =>")
+}
+
+/// Replaces tabs with spaces up to the next multiple of `tab_width`, keeping
+/// the alignment the author saw in their editor.
+pub(crate) fn expand_tabs(text: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                expanded.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    expanded
 }
 
-fn render_image(image_path: &Path) {
+/// Shows an image on the slide and returns the number of rows it takes up.
+fn render_image(
+    image_path: &Path,
+    attributes: &ImageAttributes,
+    row: u16,
+    frame: &Frame,
+    options: &ImageOptions,
+) -> Result<u16, Error> {
     if !image_path.exists() {
-        eprintln!("Error: File does not exist - {:?}", image_path);
-        std::io::stderr().flush().unwrap(); // Ensure the error message is flushed
-        std::process::exit(1);
+        return Err(Error::MissingImage(image_path.to_path_buf()));
+    }
+
+    if *attributes == ImageAttributes::default() && frame.is_stage() {
+        let config = Config {
+            ..Default::default()
+        };
+        let printed = if options.quantization_enabled() && images::uses_block_fallback() {
+            print_image(&open_image(image_path)?, &config, options)
+        } else {
+            print_from_file(image_path, &config).map_err(|err| err.to_string())
+        };
+        // Shown from the top of the screen, so no room is made for it
+        return printed
+            .map(|_| 1)
+            .map_err(|err| Error::Image(image_path.to_path_buf(), err));
     }
 
+    // Placed on its own row within the free columns
+    let image = open_image(image_path)?;
+    let (start, end) = free_columns(frame, row);
+    let available = end - start + 1;
+    let mut width = match attributes.width {
+        Some(width) => width.columns(available),
+        None => image.width().min(available as u32) as u16,
+    };
+    // Images running into the footer are scaled down to the rows left, two
+    // pixels to a row
+    let rows_left = (frame.bottom + 1).saturating_sub(row).max(1) as u32;
+    let (image_width, image_height) = (image.width().max(1), image.height().max(1));
+    let rows = (width as u32 * image_height).div_ceil(image_width * 2);
+    let height = (rows > rows_left).then(|| {
+        width = (rows_left * 2 * image_width / image_height).max(1) as u16;
+        rows_left
+    });
+    let padding = match attributes.align {
+        Align::Left => 0,
+        Align::Center => (available - width) / 2,
+        Align::Right => available - width,
+    };
+    let (x, y) = *STAGE_OFFSET.lock().unwrap();
     let config = Config {
+        x: x + start - 1 + padding,
+        y: (y + row - 1) as i16,
+        width: Some(width as u32),
+        height,
         ..Default::default()
     };
-    print_from_file(image_path, &config).unwrap();
+    let (_, rows) = print_image(&image, &config, options)
+        .map_err(|err| Error::Image(image_path.to_path_buf(), err))?;
+    Ok(rows.max(1) as u16)
+}
+
+fn print_image(
+    image: &DynamicImage,
+    config: &Config,
+    options: &ImageOptions,
+) -> Result<(u32, u32), String> {
+    let printed = if options.quantization_enabled() && images::uses_block_fallback() {
+        let quantized = images::quantize(image, options, config.width, config.height);
+        print(&quantized, config)
+    } else {
+        print(image, config)
+    };
+    printed.map_err(|err| err.to_string())
 }
 
-fn extract_prefix(s: &str) -> (String, &str) {
+pub(crate) fn extract_prefix(s: &str) -> (String, &str) {
     let prefix = s.chars().take_while(|c| *c == '#').collect::<String>();
     let rest = s.trim_start_matches('#').trim_start();
     (prefix, rest)
 }
 
-pub async fn render_notification(
-    text: &str,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
-    color: Rgb,
-) {
-    let (width, _) = terminal_size().unwrap();
-    let start = width - text.len() as u16;
+fn severity_color(severity: Severity, theme: &Theme) -> Rgb {
+    let colors = theme.get_theme_colors();
+    match severity {
+        Severity::Info => colors.text,
+        Severity::Warning => colors.accent,
+        Severity::Error => colors.error,
+    }
+}
+
+/// Stacks the notifications that have not expired yet in the top-right
+/// corner, newest at the top.
+fn render_notifications(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    let (width, _) = stage_size()?;
+    // Long messages wrap within two thirds of the width, keeping the corner
+    let max_width = (width as usize * 2 / 3).max(24).min(width as usize);
+    let mut area: Option<Area> = None;
+    for notification in presentation.notifications.visible(Instant::now()) {
+        let lines = notifications::layout(&notification.text, max_width);
+        let text_width = lines
+            .iter()
+            .map(|line| text_width(line))
+            .max()
+            .unwrap_or_default() as u16;
+        let left = width.saturating_sub(text_width) + 1;
+        let top = area.map_or(1, |area| area.rows + 1);
+        area = Some(Area {
+            left: area.map_or(width, |area| area.left).min(left),
+            rows: top + lines.len() as u16 - 1,
+        });
+        for (row, line) in (top..).zip(&lines) {
+            write!(
+                stdout,
+                "{}{}{:width$}{}",
+                goto(left, row),
+                color::Fg(severity_color(
+                    notification.severity,
+                    presentation.current_theme()
+                )),
+                line,
+                color::Fg(color::Reset),
+                width = text_width as usize
+            )?;
+        }
+    }
+    presentation.notifications.set_drawn(area);
+    Ok(())
+}
+
+/// Draws every message shown so far in a panel over the middle of the slide,
+/// cutting off the oldest ones if they don't fit.
+fn render_message_log(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    let panel_width = width.saturating_sub(4).clamp(1, 72);
+    let left = (width - panel_width) / 2 + 1;
+    let max_lines = height.saturating_sub(6).max(1) as usize;
+    let notifications = &presentation.notifications;
+    let log = notifications.log();
+    let entries = &log[log.len().saturating_sub(max_lines)..];
+
+    let colors = presentation.current_theme().get_theme_colors();
+    let glyphs = presentation.glyphs();
+    let mut row = 3;
+    let title: String = " Messages (m to close) "
+        .chars()
+        .take(panel_width as usize)
+        .collect();
     write!(
         stdout,
         "{}{}{}{}{}",
-        cursor::Goto(start, 1),
-        color::Fg(color),
-        text,
-        color::Fg(color::Reset),
-        cursor::Hide
-    )
-    .unwrap();
-    stdout.flush().unwrap();
-    tokio::spawn(async move {
-        clear_notification(start, 3).await;
-    });
+        goto(left, row),
+        color::Fg(colors.accent),
+        title,
+        glyphs.horizontal((panel_width as usize).saturating_sub(text_width(&title))),
+        color::Fg(color::Reset)
+    )?;
+    if entries.is_empty() {
+        row += 1;
+        write!(
+            stdout,
+            "{}{:width$}",
+            goto(left, row),
+            "No messages yet",
+            width = panel_width as usize
+        )?;
+    }
+    for notification in entries {
+        row += 1;
+        let line = format!(
+            "{} {:<7} {}",
+            notifications.timestamp(notification),
+            notification.severity.label(),
+            notification.text
+        );
+        let line = width::truncate(&line, panel_width as usize);
+        write!(
+            stdout,
+            "{}{}{:width$}{}",
+            goto(left, row),
+            color::Fg(severity_color(
+                notification.severity,
+                presentation.current_theme()
+            )),
+            line,
+            color::Fg(color::Reset),
+            width = panel_width as usize
+        )?;
+    }
+    write!(
+        stdout,
+        "{}{}{}{}",
+        goto(left, row + 1),
+        color::Fg(colors.accent),
+        glyphs.horizontal(panel_width as usize),
+        color::Fg(color::Reset)
+    )?;
+    Ok(())
+}
+
+/// Draws the key bindings in a bordered box over the middle of the slide,
+/// cutting off the lines that don't fit.
+pub fn render_help(
+    presentation: &Presentation,
+    lines: &[(String, String)],
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    let keys_width = lines
+        .iter()
+        .map(|(keys, _)| text_width(keys))
+        .max()
+        .unwrap_or_default();
+    let content_width = lines
+        .iter()
+        .map(|(_, description)| keys_width + 2 + text_width(description))
+        .max()
+        .unwrap_or_default();
+    let title = " Keys (any key to close) ";
+    let inner = content_width
+        .max(text_width(title))
+        .min(width.saturating_sub(4) as usize);
+    let visible = lines.len().min(height.saturating_sub(4) as usize);
+    let left = (width as usize).saturating_sub(inner + 4) / 2 + 1;
+    let top = (height as usize).saturating_sub(visible + 2) / 2 + 1;
+
+    let colors = presentation.current_theme().get_theme_colors();
+    let glyphs = presentation.glyphs();
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    let title = width::truncate(title, inner);
+    write!(
+        stdout,
+        "{}{}{}{}{}{}{}",
+        goto(left as u16, top as u16),
+        color::Fg(colors.accent),
+        top_left,
+        glyphs.horizontal(1),
+        title,
+        glyphs.horizontal(inner + 1 - text_width(&title)),
+        top_right
+    )?;
+    for (index, (keys, description)) in lines.iter().take(visible).enumerate() {
+        let line = format!("{}  {}", width::pad(keys, keys_width), description);
+        write!(
+            stdout,
+            "{}{} {}{}{} {}",
+            goto(left as u16, (top + 1 + index) as u16),
+            glyphs.vertical(),
+            color::Fg(colors.text),
+            width::pad(&width::truncate(&line, inner), inner),
+            color::Fg(colors.accent),
+            glyphs.vertical(),
+        )?;
+    }
+    write!(
+        stdout,
+        "{}{}{}{}{}",
+        goto(left as u16, (top + 1 + visible) as u16),
+        bottom_left,
+        glyphs.horizontal(inner + 2),
+        bottom_right,
+        color::Fg(color::Reset)
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// The size of a slide in the overview, including its border.
+const OVERVIEW_CELL_WIDTH: u16 = 26;
+const OVERVIEW_CELL_HEIGHT: u16 = 6;
+
+/// The number of slides in a row of the overview.
+pub fn overview_columns() -> Result<usize, Error> {
+    let (width, _) = stage_size()?;
+    Ok((width.saturating_sub(1) / (OVERVIEW_CELL_WIDTH + 1)).max(1) as usize)
+}
+
+/// The first lines of text of a slide, without its title, directives and
+/// code fences.
+fn preview_lines<'a>(content: &'a str, title: Option<&str>) -> impl Iterator<Item = &'a str> {
+    let title = title.map(str::to_string);
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !is_comment(line) && !line.starts_with("```"))
+        .map(|line| line.trim_start_matches('#').trim_start())
+        .filter(move |line| Some(*line) != title.as_deref())
 }
 
-pub async fn clear_notification(start: u16, delay_seconds: i8) {
-    thread::sleep(Duration::from_secs(delay_seconds as u64));
-    let mut stdout = stdout().into_raw_mode().unwrap();
+/// Draws every slide as a small box with its title and first lines, in rows
+/// scrolled so that the selected slide is visible.
+pub fn render_overview(
+    presentation: &Presentation,
+    selected: usize,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
     write!(
         stdout,
         "{}{}{}",
-        cursor::Goto(start, 1),
-        termion::clear::UntilNewline,
-        cursor::Hide
-    )
-    .unwrap();
-    stdout.flush().unwrap();
+        cursor::Hide,
+        termion::clear::All,
+        goto(1, 1)
+    )?;
+    presentation.notifications.set_drawn(None);
+    let (width, height) = stage_size()?;
+    let colors = presentation.current_theme().get_theme_colors();
+    let glyphs = presentation.glyphs();
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    let columns = overview_columns()?;
+    let visible_rows = (height.saturating_sub(1) / OVERVIEW_CELL_HEIGHT).max(1) as usize;
+    let first_row = (selected / columns).saturating_sub(visible_rows - 1);
+    let grid_width = columns as u16 * (OVERVIEW_CELL_WIDTH + 1) - 1;
+    let left = width.saturating_sub(grid_width) / 2 + 1;
+    let inner = (OVERVIEW_CELL_WIDTH - 2) as usize;
+
+    let shown = presentation
+        .slides
+        .iter()
+        .enumerate()
+        .skip(first_row * columns)
+        .take(visible_rows * columns);
+    for (index, slide) in shown {
+        let column = left + (index % columns) as u16 * (OVERVIEW_CELL_WIDTH + 1);
+        let top = 1 + (index / columns - first_row) as u16 * OVERVIEW_CELL_HEIGHT;
+        let title = presentation.slide_title(index);
+        let mut preview = preview_lines(&slide.content, title);
+        let heading = format!(
+            "{} {}",
+            index + 1,
+            title.or_else(|| preview.next()).unwrap_or_default()
+        );
+        let border = if index == selected {
+            colors.accent
+        } else {
+            colors.text
+        };
+        let style = if index == selected {
+            style::Bold.to_string()
+        } else {
+            style::Faint.to_string()
+        };
+        write!(
+            stdout,
+            "{}{}{}{}{}{}",
+            goto(column, top),
+            style,
+            color::Fg(border),
+            top_left,
+            glyphs.horizontal(inner),
+            top_right
+        )?;
+        let lines = std::iter::once(heading).chain(preview.map(String::from));
+        let mut lines = lines.map(|line| line.chars().take(inner).collect::<String>());
+        for row in 1..OVERVIEW_CELL_HEIGHT - 1 {
+            let line = lines.next().unwrap_or_default();
+            let color = if row == 1 {
+                colors.primary
+            } else {
+                colors.text
+            };
+            write!(
+                stdout,
+                "{}{}{}{:inner$}{}{}",
+                goto(column, top + row),
+                glyphs.vertical(),
+                color::Fg(color),
+                line,
+                color::Fg(border),
+                glyphs.vertical(),
+                inner = inner
+            )?;
+        }
+        write!(
+            stdout,
+            "{}{}{}{}{}",
+            goto(column, top + OVERVIEW_CELL_HEIGHT - 1),
+            bottom_left,
+            glyphs.horizontal(inner),
+            bottom_right,
+            style::Reset
+        )?;
+    }
+    let hint = "hjkl to move, Enter to show the slide, Esc to close";
+    let hint: String = hint.chars().take(width as usize).collect();
+    write!(
+        stdout,
+        "{}{}{}{}",
+        goto(
+            width.saturating_sub(hint.chars().count() as u16) / 2 + 1,
+            height
+        ),
+        style::Faint,
+        hint,
+        style::Reset
+    )?;
+    render_notifications(presentation, stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Shows the slides whose titles match the query in a panel over the slide,
+/// with the query above them.
+pub fn render_picker(
+    presentation: &Presentation,
+    query: &str,
+    selected: usize,
+    stdout: &mut impl Write,
+) -> Result<(), Error> {
+    let (width, height) = stage_size()?;
+    let panel_width = width.saturating_sub(4).clamp(1, 72);
+    let left = (width - panel_width) / 2 + 1;
+    let max_lines = height.saturating_sub(8).max(1) as usize;
+    let colors = presentation.current_theme().get_theme_colors();
+    let glyphs = presentation.glyphs();
+    let matches = presentation.find_slides(query);
+    // Scrolled so that the selected match is the last one shown at most
+    let first = (selected + 1).saturating_sub(max_lines);
+    let fit = |line: String| -> String { line.chars().take(panel_width as usize).collect() };
+
+    let mut row = 3;
+    let title = fit(" Go to slide (Enter to go, Esc to close) ".to_string());
+    write!(
+        stdout,
+        "{}{}{}{}{}",
+        goto(left, row),
+        color::Fg(colors.accent),
+        title,
+        glyphs.horizontal((panel_width as usize).saturating_sub(text_width(&title))),
+        color::Fg(color::Reset)
+    )?;
+    row += 1;
+    write!(
+        stdout,
+        "{}{:width$}",
+        goto(left, row),
+        fit(format!("> {}", query)),
+        width = panel_width as usize
+    )?;
+    if matches.is_empty() {
+        row += 1;
+        write!(
+            stdout,
+            "{}{}{:width$}{}",
+            goto(left, row),
+            style::Faint,
+            "No matching slide",
+            style::Reset,
+            width = panel_width as usize
+        )?;
+    }
+    for (index, (slide, title)) in matches.iter().enumerate().skip(first).take(max_lines) {
+        row += 1;
+        let line = fit(format!(
+            "{} {:>3}  {}",
+            if index == selected {
+                glyphs.pointer
+            } else {
+                ' '
+            },
+            slide + 1,
+            title
+        ));
+        if index == selected {
+            write!(
+                stdout,
+                "{}{}{}{:width$}{}{}",
+                goto(left, row),
+                style::Bold,
+                color::Fg(colors.accent),
+                line,
+                color::Fg(color::Reset),
+                style::Reset,
+                width = panel_width as usize
+            )?;
+        } else {
+            write!(
+                stdout,
+                "{}{:width$}",
+                goto(left, row),
+                line,
+                width = panel_width as usize
+            )?;
+        }
+    }
+    write!(
+        stdout,
+        "{}{}{}{}",
+        goto(left, row + 1),
+        color::Fg(colors.accent),
+        glyphs.horizontal(panel_width as usize),
+        color::Fg(color::Reset)
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Lists the questions asked by the audience in a panel over the slide, with
+/// the address to ask them at. The oldest ones are cut off if they don't fit.
+fn render_questions(presentation: &Presentation, stdout: &mut impl Write) -> Result<(), Error> {
+    let Some(server) = &presentation.server else {
+        return Ok(());
+    };
+    let (width, height) = stage_size()?;
+    let panel_width = width.saturating_sub(4).clamp(1, 72);
+    let left = (width - panel_width) / 2 + 1;
+    let max_lines = height.saturating_sub(7).max(1) as usize;
+    let colors = presentation.current_theme().get_theme_colors();
+    let glyphs = presentation.glyphs();
+
+    let mut lines: Vec<String> = Vec::new();
+    for (index, question) in server.questions().iter().enumerate().rev() {
+        let prefix = format!("{:>2}. ", index + 1);
+        let text_width = (panel_width as usize).saturating_sub(prefix.len()).max(1);
+        let wrapped = width::wrap(question, text_width);
+        if lines.len() + wrapped.len() > max_lines {
+            break;
+        }
+        for (line_index, line) in wrapped.iter().enumerate().rev() {
+            let indent = if line_index == 0 {
+                prefix.clone()
+            } else {
+                " ".repeat(prefix.len())
+            };
+            lines.push(format!("{}{}", indent, line));
+        }
+    }
+    lines.reverse();
+    if lines.is_empty() {
+        lines.push("No questions yet".to_string());
+    }
+
+    let mut row = 3;
+    let title: String = " Questions (a to close) "
+        .chars()
+        .take(panel_width as usize)
+        .collect();
+    write!(
+        stdout,
+        "{}{}{}{}{}",
+        goto(left, row),
+        color::Fg(colors.accent),
+        title,
+        glyphs.horizontal((panel_width as usize).saturating_sub(text_width(&title))),
+        color::Fg(color::Reset)
+    )?;
+    for line in lines {
+        row += 1;
+        write!(
+            stdout,
+            "{}{:width$}",
+            goto(left, row),
+            line,
+            width = panel_width as usize
+        )?;
+    }
+    let ask: String = format!("Ask at {}", server.questions_url())
+        .chars()
+        .take(panel_width as usize)
+        .collect();
+    write!(
+        stdout,
+        "{}{}{:width$}{}",
+        goto(left, row + 1),
+        style::Faint,
+        ask,
+        style::Reset,
+        width = panel_width as usize
+    )?;
+    write!(
+        stdout,
+        "{}{}{}{}",
+        goto(left, row + 2),
+        color::Fg(colors.accent),
+        glyphs.horizontal(panel_width as usize),
+        color::Fg(color::Reset)
+    )?;
+    Ok(())
+}
+
+/// Switches the whole terminal to reverse video, swapping its foreground and
+/// background colors.
+pub fn set_reverse_video(
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    enabled: bool,
+) -> Result<(), Error> {
+    write!(stdout, "\x1b[?5{}", if enabled { 'h' } else { 'l' })?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Changes the default background color of the terminal, or restores the one
+/// the terminal started with.
+pub fn set_background(stdout: &mut impl Write, color: Option<Rgb>) -> Result<(), Error> {
+    match color {
+        Some(Rgb(r, g, b)) => write!(stdout, "\x1b]11;#{:02x}{:02x}{:02x}\x07", r, g, b)?,
+        None => write!(stdout, "\x1b]111\x07")?,
+    }
+    Ok(())
+}
+
+/// Switches to the alternate screen, so that the slides don't end up in the
+/// scrollback of the shell.
+pub fn enter_alternate_screen(
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+) -> Result<(), Error> {
+    write!(stdout, "{}", screen::ToAlternateScreen)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Undoes the changes to the terminal made while presenting: the reverse
+/// video, the background color, the hidden cursor and the alternate screen.
+pub fn restore_screen(stdout: &mut impl Write) {
+    let _ = write!(
+        stdout,
+        "\x1b[?5l\x1b]111\x07{}{}",
+        cursor::Show,
+        screen::ToMainScreen
+    );
+    let _ = stdout.flush();
+}
+
+/// Shows a single line input prompt in the last terminal row, with the cursor
+/// placed after the text typed so far.
+pub fn render_prompt(
+    label: &str,
+    input: &str,
+    stdout: &mut impl Write,
+    color: Rgb,
+) -> Result<(), Error> {
+    let (_, height) = stage_size()?;
+    write!(
+        stdout,
+        "{}{}{}{}{}{}{}",
+        goto(1, height),
+        termion::clear::CurrentLine,
+        color::Fg(color),
+        label,
+        color::Fg(color::Reset),
+        input,
+        cursor::Show
+    )?;
+    stdout.flush()?;
+    Ok(())
 }
 
 fn render_text_centered(
     text: &str,
-    goto_bottom: bool,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    y_position: u16,
+    frame: &Frame,
+    stdout: &mut impl Write,
     color: Rgb,
-) {
-    let (width, height) = terminal_size().unwrap();
-    let padding = (width as usize - text.len()) / 2;
-    let spaces = " ".repeat(padding);
-    let (_, y) = stdout.cursor_pos().unwrap();
-    let y_position = if goto_bottom { height - 1 } else { y };
+) -> Result<(), Error> {
+    let (start, end) = free_columns(frame, y_position);
+    let available = (end + 1).saturating_sub(start) as usize;
+    let text = width::truncate(text, available);
+    let spaces = " ".repeat((available - text_width(&text)) / 2);
     write!(
         stdout,
         "{}{}{}{}{}{}{}{}",
-        cursor::Goto(1, y_position),
+        goto(start, y_position),
         style::Bold,
         color::Fg(color),
         spaces,
         text,
         color::Fg(color::Reset),
         style::Reset,
-        cursor::Goto(1, y_position + 1)
-    )
-    .unwrap();
+        goto(1, y_position + 1)
+    )?;
+    Ok(())
 }
 
 fn render_progress_bar(
     current_slide: usize,
     total_slides: usize,
-    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    (left, right): (u16, u16),
+    stdout: &mut impl Write,
     color: Rgb,
-) {
-    let (width, height) = terminal_size().unwrap();
+    glyph: char,
+) -> Result<(), Error> {
+    let (_, height) = stage_size()?;
+    let width = right + 1 - left;
     let progress_ratio = current_slide.add(1) as f32 / total_slides as f32;
     let progress_length = (progress_ratio * width as f32) as usize;
     write!(
         stdout,
         "{}{}{}{}",
-        cursor::Goto(1, height),
+        goto(left, height),
         color::Fg(color),
-        "".repeat(progress_length),
+        glyph.to_string().repeat(progress_length),
         color::Fg(color::Reset)
-    )
-    .unwrap();
+    )?;
 
     write!(
         stdout,
         "{}{}",
         " ".repeat(width as usize - progress_length),
-        cursor::Goto(1, height + 1)
-    )
-    .unwrap();
+        goto(1, height + 1)
+    )?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_viewport() {
+        let lines = [
+            "# Title",
+            "",
+            "```rust",
+            "fn main() {}",
+            "```",
+            "| a |",
+            "|---|",
+            "Last",
+            "",
+        ];
+        assert_eq!(block_starts(&lines), [0, 2, 5, 7]);
+        let viewport = Viewport::new(&lines, 0);
+        assert_eq!(viewport.lines.len(), 8);
+        assert!(!viewport.scrolled);
+        let viewport = Viewport::new(&lines, 2);
+        assert_eq!(viewport.lines, ["| a |", "|---|", "Last"]);
+        assert_eq!(Viewport::new(&lines, 9).lines, ["Last"]);
+    }
+
+    #[test]
+    fn test_list_width() {
+        let lines = ["- one", "- **two**", "  - three", "", "10. ten", "Text"];
+        assert_eq!(list_width(&lines, &Glyphs::unicode()), 9);
+        assert_eq!(list_width(&lines[..3], &Glyphs::ascii()), 9);
+        assert_eq!(list_width(&lines[4..], &Glyphs::unicode()), 6);
+    }
+
+    #[test]
+    fn test_enlarge_message() {
+        assert_eq!(enlarge_message((80, 24)), None);
+        assert_eq!(enlarge_message(MIN_STAGE_SIZE), None);
+        assert_eq!(
+            enlarge_message((30, 24)).unwrap(),
+            "Please enlarge the terminal to at least 40x10 (now 30x24)"
+        );
+        assert!(enlarge_message((80, 9)).is_some());
+    }
+
+    #[test]
+    fn test_extract_full_image_with_caption() {
+        let slide = "<!-- full_image: images/forest.jpg \"Into the woods\" -->\n";
+        let full_image = extract_full_image(slide).unwrap();
+        assert_eq!(full_image.path, "images/forest.jpg");
+        assert_eq!(full_image.caption, Some("Into the woods"));
+    }
+
+    #[test]
+    fn test_extract_full_image_without_caption() {
+        let full_image = extract_full_image("# Title\n<!-- full_image: forest.jpg -->").unwrap();
+        assert_eq!(full_image.path, "forest.jpg");
+        assert_eq!(full_image.caption, None);
+    }
+
+    #[test]
+    fn test_horizontal_rule() {
+        assert!(is_horizontal_rule("***"));
+        assert!(is_horizontal_rule("_____"));
+        assert!(is_horizontal_rule(" - - -"));
+        assert!(!is_horizontal_rule("**"));
+        assert!(!is_horizontal_rule("*-*"));
+        assert!(!is_horizontal_rule("* item"));
+        assert!(!is_horizontal_rule("    ***"));
+    }
+
+    #[test]
+    fn test_extract_directive() {
+        let slide = "# Title\n<!-- background_image: images/logo.png -->\nText";
+        assert_eq!(
+            extract_directive(slide, "background_image"),
+            Some("images/logo.png")
+        );
+        assert_eq!(extract_directive(slide, "full_image"), None);
+    }
+
+    #[test]
+    fn test_has_directive() {
+        let slide = "<!-- big_text -->\n# Welcome";
+        assert!(has_directive(slide, "big_text"));
+        assert!(!has_directive(slide, "big"));
+    }
+
+    #[test]
+    fn test_reserved_region_bottom_right() {
+        let region = ReservedRegion::parse("bottom-right 40x12", 120, 40).unwrap();
+        assert_eq!(
+            region,
+            ReservedRegion {
+                left: 81,
+                top: 29,
+                width: 40,
+                height: 12
+            }
+        );
+        assert_eq!(region.free_columns(10, 120), (1, 120));
+        assert_eq!(region.free_columns(30, 120), (1, 80));
+    }
+
+    #[test]
+    fn test_reserved_region_top_left() {
+        let region = ReservedRegion::parse("top-left 20x5", 80, 24).unwrap();
+        assert_eq!(region.free_columns(3, 80), (21, 80));
+        assert_eq!(region.free_columns(6, 80), (1, 80));
+    }
+
+    #[test]
+    fn test_code_block_line_numbers() {
+        let aliases = LanguageAliases::default();
+        let content = (1..=10)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let block =
+            CodeBlock::parse(&format!("```RS,linenos +exec\n{}\n```", content), &aliases).unwrap();
+        assert_eq!(block.language, "rust");
+        assert!(block.is_executable());
+        assert_eq!((block.gutter(false), block.width(4, 3)), (3, 8));
+        let plain = CodeBlock::parse("```rust,nolinenos\nx\n```", &aliases).unwrap();
+        assert_eq!((plain.gutter(true), plain.gutter(false)), (0, 0));
+        let default = CodeBlock::parse("```\nx\n```", &aliases).unwrap();
+        assert_eq!((default.gutter(true), default.language.as_str()), (2, ""));
+    }
+
+    #[test]
+    fn test_indented_code_block() {
+        let aliases = LanguageAliases::default();
+        let slide = "1. Run:\n\n   ```bash\n   cd  x\n     ls\n ```\n2. Done";
+        let blocks = code_blocks(slide, &aliases);
+        assert_eq!(blocks.len(), 1);
+        let (line, block) = &blocks[0];
+        assert_eq!((*line, block.language.as_str()), (2, "bash"));
+        assert_eq!(block.content, "cd  x\n  ls");
+        assert_eq!(
+            list_width(&slide.lines().collect::<Vec<_>>(), &Glyphs::unicode()),
+            10
+        );
+    }
+
+    #[test]
+    fn test_code_block_highlight() {
+        let aliases = LanguageAliases::default();
+        let block = CodeBlock::parse("```rust {1, 3-4} +exec\na\nb\nc\nd\n```", &aliases).unwrap();
+        assert_eq!(
+            (block.language.as_str(), block.is_executable()),
+            ("rust", true)
+        );
+        let steps = block.highlight_steps().unwrap().unwrap();
+        assert!(steps[0].contains(1) && !steps[0].contains(2) && steps[0].contains(4));
+        let past_end = CodeBlock::parse("```rust {5}\na\n```", &aliases).unwrap();
+        assert!(past_end.highlight_steps().is_err());
+        let invalid = CodeBlock::parse("```rust {x}\na\n```", &aliases).unwrap();
+        assert!(invalid.highlight_steps().is_err());
+    }
+
+    #[test]
+    fn test_expand_tabs_aligns_to_tab_stops() {
+        assert_eq!(expand_tabs("\tx = 1", 4), "    x = 1");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("a\n\tb", 8), "a\n        b");
+    }
+
     #[test]
     fn test_extract_hash_no_hash() {
         let (prefix, rest) = extract_prefix("Hello, world!");
@@ -569,4 +3634,12 @@ mod tests {
         assert_eq!(prefix, "###");
         assert_eq!(rest, "Hello, world!");
     }
+
+    #[test]
+    fn test_highlight_queries_compile() {
+        for language in ["rust", "java", "python"] {
+            let (language, query_source) = get_language_config(language).unwrap();
+            Query::new(&language, query_source).unwrap();
+        }
+    }
 }
@@ -0,0 +1,44 @@
+use std::ops::Range;
+
+/// Finds the places where the pattern occurs in the text, ignoring case.
+/// Returns the ranges of character indices of the matches, which don't
+/// overlap.
+pub fn find_matches(text: &str, pattern: &str) -> Vec<Range<usize>> {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut matches = Vec::new();
+    if pattern.is_empty() {
+        return matches;
+    }
+    let mut start = 0;
+    while start + pattern.len() <= text.len() {
+        let found = text[start..start + pattern.len()]
+            .iter()
+            .zip(&pattern)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()));
+        if found {
+            matches.push(start..start + pattern.len());
+            start += pattern.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(
+            find_matches("Lifetimes and more lifetimes", "lifetime"),
+            vec![0..8, 19..27]
+        );
+        assert_eq!(find_matches("aaaa", "aa"), vec![0..2, 2..4]);
+        assert_eq!(find_matches("Übung", "üb"), vec![0..2]);
+        assert!(find_matches("Traits", "").is_empty());
+        assert!(find_matches("Traits", "generics").is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    net::{Ipv4Addr, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use tiny_http::{Header, Method, Request, Response};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{events::Event, poll::Poll};
+
+/// The port the web server listens on unless `--port` is given.
+pub const DEFAULT_PORT: u16 = 7070;
+
+/// What the web server shares with the presentation.
+#[derive(Debug, Default)]
+struct State {
+    /// The polls of the presentation by slide number
+    polls: BTreeMap<usize, Poll>,
+    /// The votes per answer, by question so that they survive reloads
+    votes: HashMap<String, Vec<usize>>,
+    /// Questions asked by the audience, oldest first
+    questions: Vec<String>,
+}
+
+/// Questions longer than this are cut off.
+const MAX_QUESTION_LENGTH: usize = 280;
+
+/// The cookie a browser keeps the token in after opening an address with it.
+const TOKEN_COOKIE: &str = "term_deck_token";
+
+/// A web server on the local network where the audience takes part in
+/// polls and asks questions.
+pub struct Server {
+    /// The address the audience opens, using the local network address
+    pub url: String,
+    /// The token every request needs, given in the addresses shown
+    token: Option<String>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Server {
+    /// Starts answering requests on a background thread. `sender` is told
+    /// about every vote and question so that they can be shown right away.
+    /// With a token, requests without it are turned away.
+    pub fn start(
+        port: u16,
+        token: Option<String>,
+        sender: UnboundedSender<Event>,
+    ) -> Result<Server, String> {
+        let server = tiny_http::Server::http((Ipv4Addr::UNSPECIFIED, port))
+            .map_err(|err| format!("Could not start the web server on port {}: {}", port, err))?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = Arc::clone(&state);
+        let required = token.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                if let Some(event) = handle(request, &shared, required.as_deref()) {
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Server {
+            url: format!("http://{}:{}", local_address(), port),
+            token,
+            state,
+        })
+    }
+
+    /// Replaces the polls, e.g. after the presentation was reloaded.
+    pub fn set_polls(&self, polls: BTreeMap<usize, Poll>) {
+        self.state.lock().unwrap().polls = polls;
+    }
+
+    /// The votes for each answer of the poll.
+    pub fn votes(&self, poll: &Poll) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+        let votes = state.votes.get(&poll.question);
+        (0..poll.answers.len())
+            .map(|answer| {
+                votes
+                    .and_then(|votes| votes.get(answer))
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    pub fn poll_url(&self, slide: usize) -> String {
+        self.with_token(format!("{}/poll/{}", self.url, slide))
+    }
+
+    pub fn questions(&self) -> Vec<String> {
+        self.state.lock().unwrap().questions.clone()
+    }
+
+    pub fn questions_url(&self) -> String {
+        self.with_token(format!("{}/questions", self.url))
+    }
+
+    fn with_token(&self, url: String) -> String {
+        match &self.token {
+            Some(token) => format!("{}?token={}", url, token),
+            None => url,
+        }
+    }
+}
+
+/// The address of this machine in the local network, found by asking which
+/// interface would be used to reach the internet. Nothing is sent.
+fn local_address() -> String {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket
+                .connect((Ipv4Addr::new(192, 0, 2, 1), 80))
+                .map(|_| socket)
+        })
+        .and_then(|socket| socket.local_addr())
+        .map(|address| address.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// Answers a request and returns the event for a vote or question.
+fn handle(mut request: Request, state: &Mutex<State>, token: Option<&str>) -> Option<Event> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let cookies = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Cookie"))
+        .map(|header| header.value.to_string());
+    if let Some(token) = token {
+        if !is_authorized(query, cookies.as_deref(), token) {
+            let _ = request.respond(Response::from_string("Forbidden").with_status_code(403));
+            return None;
+        }
+    }
+    let slide = path
+        .strip_prefix("/poll/")
+        .and_then(|slide| slide.parse::<usize>().ok());
+    let mut event = None;
+    let response = match (request.method(), path, slide) {
+        (Method::Get, "/", _) => html(&index_page(&state.lock().unwrap().polls)),
+        (Method::Get, "/questions", _) => html(&questions_page(query == "asked")),
+        (Method::Post, "/questions", _) => {
+            let question = form_value(&read_body(&mut request), "question")
+                .map(|question| question.trim().chars().take(MAX_QUESTION_LENGTH).collect())
+                .filter(|question: &String| !question.is_empty());
+            match question {
+                Some(question) => {
+                    state.lock().unwrap().questions.push(question);
+                    event = Some(Event::QuestionAsked);
+                    redirect("/questions?asked")
+                }
+                None => redirect("/questions"),
+            }
+        }
+        (Method::Get, _, Some(slide)) => match state.lock().unwrap().polls.get(&slide) {
+            Some(poll) => html(&poll_page(poll, query == "voted")),
+            None => not_found(),
+        },
+        (Method::Post, _, Some(slide)) => {
+            let answer = form_value(&read_body(&mut request), "answer")
+                .and_then(|answer| answer.parse::<usize>().ok());
+            let mut state = state.lock().unwrap();
+            let poll = state.polls.get(&slide).cloned();
+            match (poll, answer) {
+                (Some(poll), Some(answer)) if answer < poll.answers.len() => {
+                    let votes = state.votes.entry(poll.question).or_default();
+                    votes.resize(votes.len().max(poll.answers.len()), 0);
+                    votes[answer] += 1;
+                    event = Some(Event::PollVoted);
+                    redirect(&format!("/poll/{}?voted", slide))
+                }
+                _ => not_found(),
+            }
+        }
+        _ => not_found(),
+    };
+    // The token is remembered so that links and forms work without it
+    let response = match token {
+        Some(token) => response.with_header(header(
+            "Set-Cookie",
+            &format!(
+                "{}={}; Path=/; HttpOnly; SameSite=Strict",
+                TOKEN_COOKIE, token
+            ),
+        )),
+        None => response,
+    };
+    let _ = request.respond(response);
+    event
+}
+
+/// Whether the token is given in the query or in the cookie set by an earlier
+/// response.
+fn is_authorized(query: &str, cookies: Option<&str>, token: &str) -> bool {
+    let cookie = cookies.and_then(|cookies| {
+        cookies
+            .split(';')
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == TOKEN_COOKIE)
+            .map(|(_, value)| value.to_string())
+    });
+    form_value(query, "token")
+        .into_iter()
+        .chain(cookie)
+        .any(|given| same_token(&given, token))
+}
+
+/// Compares without stopping at the first difference, so that the time taken
+/// doesn't give away how much of a guess was right.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn read_body(request: &mut Request) -> String {
+    let mut body = String::new();
+    let _ = request.as_reader().take(4096).read_to_string(&mut body);
+    body
+}
+
+/// The decoded value of a field of a submitted form.
+fn form_value(body: &str, name: &str) -> Option<String> {
+    body.split('&')
+        .filter_map(|field| field.split_once('='))
+        .find(|(field, _)| *field == name)
+        .map(|(_, value)| decode(value))
+}
+
+/// Undoes the `application/x-www-form-urlencoded` encoding.
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn index_page(polls: &BTreeMap<usize, Poll>) -> String {
+    let links: String = polls
+        .iter()
+        .map(|(slide, poll)| {
+            format!(
+                "<li><a href=\"/poll/{}\">{}</a></li>",
+                slide,
+                escape(&poll.question)
+            )
+        })
+        .collect();
+    page(
+        "Polls",
+        &format!(
+            "<h1>Polls</h1><ul>{}</ul><p><a href=\"/questions\">Ask a question</a></p>",
+            links
+        ),
+    )
+}
+
+fn questions_page(asked: bool) -> String {
+    let thanks = if asked {
+        "<p>Thanks, your question was sent!</p>"
+    } else {
+        ""
+    };
+    page(
+        "Ask a question",
+        &format!(
+            "<h1>Ask a question</h1>{}<form method=\"post\">\
+             <p><textarea name=\"question\" rows=\"4\" cols=\"40\" maxlength=\"{}\" required></textarea></p>\
+             <button>Send</button></form>",
+            thanks, MAX_QUESTION_LENGTH
+        ),
+    )
+}
+
+fn poll_page(poll: &Poll, voted: bool) -> String {
+    if voted {
+        return page(
+            &poll.question,
+            &format!(
+                "<h1>{}</h1><p>Thanks for voting!</p>",
+                escape(&poll.question)
+            ),
+        );
+    }
+    let answers: String = poll
+        .answers
+        .iter()
+        .enumerate()
+        .map(|(index, answer)| {
+            format!(
+                "<p><label><input type=\"radio\" name=\"answer\" value=\"{}\" required> {}</label></p>",
+                index,
+                escape(answer)
+            )
+        })
+        .collect();
+    page(
+        &poll.question,
+        &format!(
+            "<h1>{}</h1><form method=\"post\">{}<button>Vote</button></form>",
+            escape(&poll.question),
+            answers
+        ),
+    )
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title></head><body style=\"font-family: sans-serif\">{}</body></html>",
+        escape(title),
+        body
+    )
+}
+
+fn html(content: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(content).with_header(header("Content-Type", "text/html; charset=utf-8"))
+}
+
+/// Redirecting after a form was sent keeps a reload of the page from sending
+/// it again.
+fn redirect(location: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string("")
+        .with_status_code(303)
+        .with_header(header("Location", location))
+}
+
+fn not_found() -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string("Not found").with_status_code(404)
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_page_escapes_answers() {
+        let poll = Poll {
+            question: "Best <tag>?".to_string(),
+            answers: vec!["<div>".to_string(), "\"span\"".to_string()],
+        };
+        let page = poll_page(&poll, false);
+        assert!(page.contains("<h1>Best &lt;tag&gt;?</h1>"));
+        assert!(page.contains("value=\"1\" required> &quot;span&quot;"));
+        assert!(poll_page(&poll, true).contains("Thanks for voting!"));
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized("token=s3cret", None, "s3cret"));
+        assert!(is_authorized(
+            "voted",
+            Some("a=1; term_deck_token=s3cret"),
+            "s3cret"
+        ));
+        assert!(!is_authorized("token=s3cre", None, "s3cret"));
+        assert!(!is_authorized("", Some("term_deck_token=guess!"), "s3cret"));
+        assert!(!is_authorized("", None, "s3cret"));
+    }
+
+    #[test]
+    fn test_form_value() {
+        let body = "question=What+about+%3Cunsafe%3E%3F&x=1";
+        assert_eq!(
+            form_value(body, "question").as_deref(),
+            Some("What about <unsafe>?")
+        );
+        assert_eq!(form_value("answer=2", "answer").as_deref(), Some("2"));
+        assert_eq!(form_value("answer=2", "question"), None);
+        assert_eq!(decode("caf%C3%A9 100%"), "café 100%");
+    }
+}
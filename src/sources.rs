@@ -0,0 +1,124 @@
+use std::{fs, path::Path};
+
+use crate::highlights::LineSelection;
+
+/// Fills code blocks opened like ```` ```rust file=src/main.rs lines=10-42 ````
+/// with the lines of the file, read relative to the directory of the
+/// presentation. The `file` and `lines` attributes are taken off the fence, so
+/// the expanded content doesn't depend on the file anymore. Files that can't
+/// be included are reported in the returned errors and their blocks left as
+/// they are.
+pub fn expand_code_files(content: &str, directory: &Path) -> (String, Vec<String>) {
+    let mut expanded = String::new();
+    let mut errors = Vec::new();
+    let mut lines = content.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        expanded.push_str(line);
+        if !line.trim_start().starts_with("```") {
+            continue;
+        }
+        let attribute = |name: &str| {
+            line.split_whitespace()
+                .find_map(|token| token.strip_prefix(name)?.strip_prefix('='))
+        };
+        let included = attribute("file").and_then(|file| {
+            read_lines(&directory.join(file), attribute("lines"))
+                .map_err(|err| errors.push(format!("Could not include {}: {}", file, err)))
+                .ok()
+        });
+        let Some(included) = included else {
+            // Copy the block so its closing fence isn't taken for an opening one
+            for line in lines.by_ref() {
+                expanded.push_str(line);
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
+            }
+            continue;
+        };
+        expanded.truncate(expanded.len() - line.len());
+        let fence: String = line
+            .split_inclusive(char::is_whitespace)
+            .filter(|token| {
+                !["file=", "lines="]
+                    .iter()
+                    .any(|name| token.starts_with(name))
+            })
+            .collect();
+        expanded.push_str(fence.trim_end());
+        expanded.push('\n');
+        // Nested in a list item, the code is indented like the fence
+        let indent = &line[..line.len() - line.trim_start().len()];
+        for code in included {
+            expanded.push_str(indent);
+            expanded.push_str(code.trim_end_matches('\r'));
+            expanded.push('\n');
+        }
+        // The block is meant to be empty, anything written in it is replaced
+        let mut body = lines
+            .by_ref()
+            .skip_while(|line| !line.trim_start().starts_with("```"));
+        match body.next() {
+            Some(closing) => expanded.push_str(closing),
+            None => expanded.push_str(&format!("{}```\n", indent)),
+        }
+    }
+    (expanded, errors)
+}
+
+/// The lines of the file, only the selected ones if lines are given.
+fn read_lines(path: &Path, lines: Option<&str>) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let all = content.lines().map(String::from);
+    let Some(spec) = lines else {
+        return Ok(all.collect());
+    };
+    let selection = LineSelection::parse(spec).ok_or(format!(
+        "Invalid lines '{}', expected lines and ranges like 10-42",
+        spec
+    ))?;
+    let count = content.lines().count();
+    if selection.last() > count {
+        return Err(format!(
+            "Line {} is included, but the file ends at line {}",
+            selection.last(),
+            count
+        ));
+    }
+    Ok(all
+        .enumerate()
+        .filter(|(i, _)| selection.contains(i + 1))
+        .map(|(_, line)| line)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_code_files() {
+        let directory = env!("CARGO_MANIFEST_DIR").as_ref();
+        let content = "```toml file=Cargo.toml lines=1-2 {2}\n```\ntext\n";
+        let (expanded, errors) = expand_code_files(content, directory);
+        assert!(errors.is_empty());
+        assert_eq!(
+            expanded,
+            "```toml {2}\n[package]\nname = \"term_deck\"\n```\ntext\n"
+        );
+
+        let nested = "1. Config:\n   ```toml file=Cargo.toml lines=1\n   ```\n";
+        let (expanded, _) = expand_code_files(nested, directory);
+        assert_eq!(expanded, "1. Config:\n   ```toml\n   [package]\n   ```\n");
+
+        let missing = "```rust file=missing.rs\n```\n```toml file=Cargo.toml lines=1\n```\n";
+        let (expanded, errors) = expand_code_files(missing, directory);
+        assert_eq!(
+            expanded,
+            "```rust file=missing.rs\n```\n```toml\n[package]\n```\n"
+        );
+        assert_eq!(errors.len(), 1);
+        let (_, errors) = expand_code_files("```toml file=Cargo.toml lines=9999\n```\n", directory);
+        assert_eq!(errors.len(), 1);
+    }
+}
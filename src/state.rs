@@ -0,0 +1,85 @@
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The contents of `~/.local/share/term_deck/state.toml`, remembered between
+/// runs.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct State {
+    /// The slide index shown when quitting, per absolute presentation path
+    last_slide: BTreeMap<String, usize>,
+}
+
+pub fn state_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("term_deck").join("state.toml"))
+}
+
+/// The key a presentation is remembered by, so that it is found again from
+/// another working directory.
+fn presentation_key(presentation_file: &str) -> String {
+    fs::canonicalize(presentation_file)
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| presentation_file.to_string())
+}
+
+fn read_state() -> Result<State, String> {
+    let Some(path) = state_path() else {
+        return Ok(State::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err.message()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(State::default()),
+        Err(err) => Err(format!("Could not read {}: {}", path.display(), err)),
+    }
+}
+
+/// The slide index the presentation was left at the last time.
+pub fn last_slide(presentation_file: &str) -> Result<Option<usize>, String> {
+    let state = read_state()?;
+    Ok(state
+        .last_slide
+        .get(&presentation_key(presentation_file))
+        .copied())
+}
+
+/// Remembers the slide index the presentation is left at.
+pub fn save_last_slide(presentation_file: &str, slide: usize) -> Result<(), String> {
+    let path = state_path().ok_or("Could not find the home directory")?;
+    // A broken state file is replaced rather than keeping every later save
+    // from working
+    let mut state = read_state().unwrap_or_default();
+    state
+        .last_slide
+        .insert(presentation_key(presentation_file), slide);
+    let content = toml::to_string(&state).map_err(|err| err.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+    }
+    fs::write(&path, content).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut state = State::default();
+        state.last_slide.insert("/talks/rust.md".to_string(), 12);
+        state.last_slide.insert("/talks/my talk.md".to_string(), 0);
+        let content = toml::to_string(&state).unwrap();
+        let parsed: State = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.last_slide, state.last_slide);
+        let empty: State = toml::from_str("").unwrap();
+        assert!(empty.last_slide.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use tree_sitter::{Node, Parser};
+
+use crate::{languages::LanguageAliases, rendering, Slide};
+
+/// An identifier found in one of the code blocks of a slide.
+#[derive(Debug, PartialEq)]
+struct Occurrence {
+    name: String,
+    slide: usize,
+    definition: bool,
+}
+
+/// All identifiers of the code blocks of a presentation, for jumping to the
+/// slides that define or use a symbol.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    occurrences: Vec<Occurrence>,
+}
+
+impl SymbolIndex {
+    pub fn build(slides: &[Slide], aliases: &LanguageAliases) -> SymbolIndex {
+        let mut index = SymbolIndex::default();
+        for (slide_index, slide) in slides.iter().enumerate() {
+            for (_, block) in rendering::code_blocks(&slide.content, aliases) {
+                index.add_snippet(slide_index, &block.language, &block.content);
+            }
+        }
+        index
+    }
+
+    fn add_snippet(&mut self, slide: usize, language: &str, content: &str) {
+        let Some((language, _)) = rendering::get_language_config(language) else {
+            return;
+        };
+        let mut parser = Parser::new();
+        if parser.set_language(&language).is_err() {
+            return;
+        }
+        if let Some(tree) = parser.parse(content, None) {
+            self.add_identifiers(slide, tree.root_node(), content);
+        }
+    }
+
+    fn add_identifiers(&mut self, slide: usize, node: Node, content: &str) {
+        if node.kind().ends_with("identifier") {
+            if let Ok(name) = node.utf8_text(content.as_bytes()) {
+                self.occurrences.push(Occurrence {
+                    name: name.to_string(),
+                    slide,
+                    definition: is_definition(node),
+                });
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.add_identifiers(slide, child, content);
+        }
+    }
+
+    /// The slides mentioning the symbol, the ones defining it first.
+    pub fn slides(&self, name: &str) -> Vec<usize> {
+        let mut slides: Vec<usize> = Vec::new();
+        for definition in [true, false] {
+            for occurrence in &self.occurrences {
+                if occurrence.name == name
+                    && occurrence.definition == definition
+                    && !slides.contains(&occurrence.slide)
+                {
+                    slides.push(occurrence.slide);
+                }
+            }
+        }
+        slides
+    }
+
+    /// The slide to jump to when searching for a symbol from the current
+    /// slide: the defining slide first, then every further slide using it in
+    /// turn.
+    pub fn next_slide(&self, name: &str, current_slide: usize) -> Option<usize> {
+        let slides = self.slides(name);
+        match slides.iter().position(|&slide| slide == current_slide) {
+            Some(position) => slides.get((position + 1) % slides.len()).copied(),
+            None => slides.first().copied(),
+        }
+    }
+}
+
+/// Whether the identifier is the name of a function, type, class or similar
+/// item rather than a use of it.
+fn is_definition(node: Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        let kind = parent.kind();
+        (kind.ends_with("_item") || kind.ends_with("_definition") || kind.ends_with("_declaration"))
+            && parent.child_by_field_name("name") == Some(node)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(slides: &[&str]) -> SymbolIndex {
+        let aliases = LanguageAliases::default();
+        let slides: Vec<Slide> = slides
+            .iter()
+            .map(|slide| Slide::parse(slide, &aliases))
+            .collect();
+        SymbolIndex::build(&slides, &aliases)
+    }
+
+    #[test]
+    fn test_definitions_come_first() {
+        let index = index(&[
+            "```rust\nfn main() { parse(); }\n```",
+            "no code here",
+            "```rs\nfn parse() {}\n```",
+            "```python\ndef other():\n    parse()\n```",
+        ]);
+        assert_eq!(index.slides("parse"), vec![2, 0, 3]);
+        assert_eq!(index.slides("missing"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_next_slide_cycles_through_matches() {
+        let index = index(&[
+            "```python\nclass Deck:\n    pass\n```",
+            "```python\nDeck()\n```",
+            "text",
+        ]);
+        assert_eq!(index.next_slide("Deck", 2), Some(0));
+        assert_eq!(index.next_slide("Deck", 0), Some(1));
+        assert_eq!(index.next_slide("Deck", 1), Some(0));
+        assert_eq!(index.next_slide("deck", 2), None);
+    }
+}
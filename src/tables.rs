@@ -0,0 +1,181 @@
+use serde::Deserialize;
+
+use crate::{inline::parse_inline, width::text_width};
+
+/// How text is placed between the edges of a table column, or of the slide
+/// with `<!-- alignment: center -->`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    pub fn from_name(name: &str) -> Option<Alignment> {
+        match name {
+            "left" => Some(Alignment::Left),
+            "center" => Some(Alignment::Center),
+            "right" => Some(Alignment::Right),
+            _ => None,
+        }
+    }
+
+    fn parse(cell: &str) -> Option<Alignment> {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+            return None;
+        }
+        match (cell.starts_with(':'), cell.ends_with(':')) {
+            (true, true) => Some(Alignment::Center),
+            (false, true) => Some(Alignment::Right),
+            _ => Some(Alignment::Left),
+        }
+    }
+
+    /// Splits the padding needed to fill `width` columns with `text_width`
+    /// columns of text into the space before and after the text.
+    pub fn padding(&self, text_width: usize, width: usize) -> (usize, usize) {
+        let padding = width.saturating_sub(text_width);
+        match self {
+            Alignment::Left => (0, padding),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+            Alignment::Right => (padding, 0),
+        }
+    }
+}
+
+/// A pipe table with a header row and a delimiter row holding the column
+/// alignments.
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    pub header: Vec<String>,
+    pub alignments: Vec<Alignment>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Parses the table starting at the first line and returns it together with
+    /// the number of lines it spans.
+    pub fn parse(lines: &[&str]) -> Option<(Table, usize)> {
+        let header = split_row(lines.first()?)?;
+        let alignments = split_row(lines.get(1)?)?
+            .iter()
+            .map(|cell| Alignment::parse(cell))
+            .collect::<Option<Vec<_>>>()?;
+        if alignments.len() != header.len() {
+            return None;
+        }
+
+        let rows: Vec<Vec<String>> = lines[2..]
+            .iter()
+            .map_while(|line| split_row(line))
+            .map(|mut row| {
+                row.resize(header.len(), String::new());
+                row
+            })
+            .collect();
+        let line_count = rows.len() + 2;
+        Some((
+            Table {
+                header,
+                alignments,
+                rows,
+            },
+            line_count,
+        ))
+    }
+
+    /// The widest cell of every column, measured without inline markup.
+    /// The columns taken up including the borders and the padding.
+    pub fn width(&self) -> usize {
+        self.column_widths()
+            .iter()
+            .map(|width| width + 3)
+            .sum::<usize>()
+            + 1
+    }
+
+    pub fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.header.iter().map(|cell| cell_width(cell)).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell_width(cell));
+            }
+        }
+        widths
+    }
+}
+
+/// The number of columns a cell takes up once its inline markup is rendered.
+pub fn cell_width(cell: &str) -> usize {
+    parse_inline(cell)
+        .iter()
+        .map(|span| text_width(&span.text))
+        .sum()
+}
+
+fn split_row(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    if !line.starts_with('|') {
+        return None;
+    }
+    let line = line.strip_prefix('|').unwrap();
+    let line = line.strip_suffix('|').unwrap_or(line);
+
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    Some(cells.iter().map(|cell| cell.trim().to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table() {
+        let lines = [
+            "| Name | Stars | Notes |",
+            "|:-----|:-----:|------:|",
+            "| **serde** | 9k | a \\| b |",
+            "| tokio | 26k |",
+            "",
+            "| not part of the table |",
+        ];
+        let (table, line_count) = Table::parse(&lines).unwrap();
+        assert_eq!(line_count, 4);
+        assert_eq!(
+            table.alignments,
+            vec![Alignment::Left, Alignment::Center, Alignment::Right]
+        );
+        assert_eq!(table.rows[0][2], "a | b");
+        assert_eq!(table.rows[1], vec!["tokio", "26k", ""]);
+        assert_eq!(table.column_widths(), vec![5, 5, 5]);
+    }
+
+    #[test]
+    fn test_requires_delimiter_row() {
+        assert_eq!(Table::parse(&["| a | b |", "| c | d |"]), None);
+        assert_eq!(Table::parse(&["| a | b |", "|---|"]), None);
+        assert_eq!(Table::parse(&["| a |"]), None);
+    }
+
+    #[test]
+    fn test_alignment_padding() {
+        assert_eq!(Alignment::Left.padding(2, 7), (0, 5));
+        assert_eq!(Alignment::Center.padding(2, 7), (2, 3));
+        assert_eq!(Alignment::Right.padding(2, 7), (5, 0));
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    env,
+    io::{self, Stdout},
+    ops::{Deref, DerefMut},
+    panic,
+};
+
+use termion::raw::{IntoRawMode, RawTerminal};
+
+use crate::{error::Error, rendering};
+
+/// The terminal in raw mode on the alternate screen. Dropping it, also while
+/// unwinding from a panic, restores the terminal the way it was.
+pub struct TerminalGuard {
+    stdout: RawTerminal<Stdout>,
+}
+
+impl TerminalGuard {
+    pub fn new() -> Result<TerminalGuard, Error> {
+        panic::set_hook(Box::new(|info| {
+            rendering::restore_screen(&mut io::stdout());
+            // Raw mode is only left while unwinding, so lines need a carriage
+            // return
+            eprint!("{}\r\n", info.to_string().replace('\n', "\r\n"));
+        }));
+        let mut stdout = io::stdout().into_raw_mode()?;
+        rendering::enter_alternate_screen(&mut stdout)?;
+        Ok(TerminalGuard { stdout })
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = RawTerminal<Stdout>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stdout
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stdout
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        rendering::restore_screen(&mut self.stdout);
+    }
+}
+
+/// Whether the terminal is known to make OSC 8 hyperlinks clickable. There is
+/// no way to ask the terminal, so this goes by the variables terminals set.
+pub fn supports_hyperlinks() -> bool {
+    hyperlinks_supported(|name| env::var(name).ok())
+}
+
+pub(crate) fn hyperlinks_supported(var: impl Fn(&str) -> Option<String>) -> bool {
+    // tmux only passes them on when configured to
+    if var("TMUX").is_some() {
+        return false;
+    }
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+    [
+        "iTerm.app",
+        "WezTerm",
+        "vscode",
+        "ghostty",
+        "Hyper",
+        "Tabby",
+    ]
+    .contains(&program.as_str())
+        || ["kitty", "foot", "alacritty", "wezterm", "ghostty"]
+            .iter()
+            .any(|name| term.contains(name))
+        || var("VTE_VERSION")
+            .and_then(|version| version.parse::<u32>().ok())
+            .is_some_and(|version| version >= 5000)
+        || ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+            .iter()
+            .any(|name| var(name).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperlinks_supported() {
+        let supported = |vars: &[(&str, &str)]| {
+            hyperlinks_supported(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert!(supported(&[("TERM", "xterm-kitty")]));
+        assert!(supported(&[("TERM_PROGRAM", "iTerm.app")]));
+        assert!(supported(&[("VTE_VERSION", "7200")]));
+        assert!(!supported(&[("VTE_VERSION", "4800")]));
+        assert!(!supported(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/x")]));
+        assert!(!supported(&[("TERM", "xterm-256color")]));
+    }
+}
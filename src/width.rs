@@ -0,0 +1,192 @@
+/// Ranges of characters that take up two columns: CJK ideographs, Hangul,
+/// fullwidth forms and emoji.
+const WIDE: [(u32, u32); 14] = [
+    (0x1100, 0x115F),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE30, 0xFE4F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x1F300, 0x1F64F),
+    (0x1F680, 0x1F9FF),
+    (0x20000, 0x3FFFD),
+];
+
+/// Emoji that are drawn as pictures, two columns wide, outside of the ranges
+/// above, like ⚡, ✅ and ⭐.
+const EMOJI: [(u32, u32); 39] = [
+    (0x231A, 0x231B),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F251),
+    (0x1FA70, 0x1FAFF),
+];
+
+/// Ranges of characters that take up no column of their own: combining marks,
+/// zero width spaces and joiners, and variation selectors.
+const ZERO: [(u32, u32); 5] = [
+    (0x0300, 0x036F),
+    (0x200B, 0x200F),
+    (0x20D0, 0x20FF),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+];
+
+/// The number of terminal columns a character takes up.
+pub fn char_width(c: char) -> usize {
+    let code = c as u32;
+    let within = |ranges: &[(u32, u32)]| {
+        ranges
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&code))
+    };
+    if c.is_control() || within(&ZERO) {
+        0
+    } else if within(&WIDE) || within(&EMOJI) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The number of terminal columns a text takes up.
+pub fn text_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Cuts the text off after `width` columns, ending it with an ellipsis if
+/// anything was left out.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text_width(text) <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        if used + char_width(c) + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width(c);
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// The text followed by as many spaces as it takes to fill `width` columns.
+pub fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(text_width(text)))
+    )
+}
+
+/// Greedily wraps the words of the text into lines of at most `max_width`
+/// columns, breaking up words that are wider than a line.
+pub fn wrap(text: &str, max_width: usize) -> Vec<String> {
+    wrap_by(text, max_width, char_width)
+}
+
+/// Wraps the words of the text into lines of at most `max_width`, measuring
+/// each character with `measure`.
+pub(crate) fn wrap_by(text: &str, max_width: usize, measure: fn(char) -> usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for word in text.split_whitespace() {
+        let word_width: usize = word.chars().map(measure).sum();
+        if line_width > 0 && line_width + 1 + word_width > max_width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if line_width > 0 {
+            line.push(' ');
+            line_width += 1;
+        }
+        for c in word.chars() {
+            if line_width > 0 && line_width + measure(c) > max_width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            line.push(c);
+            line_width += measure(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width("plain"), 5);
+        assert_eq!(text_width("日本語"), 6);
+        assert_eq!(text_width("ship it 🚀"), 10);
+        assert_eq!(text_width("⚡✅⭐🫠"), 8);
+        assert_eq!(text_width("e\u{301}"), 1);
+        assert_eq!(text_width("•→…"), 3);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("overlong", 5), "over…");
+        assert_eq!(truncate("fits", 5), "fits");
+        assert_eq!(truncate("日本語です", 6), "日本…");
+        assert_eq!(truncate("日本語です", 5), "日本…");
+        assert_eq!(pad("日本", 6), "日本  ");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("日本語 ok", 6), ["日本語", "ok"]);
+        assert_eq!(wrap("日本語日本語", 5), ["日本", "語日", "本語"]);
+    }
+}